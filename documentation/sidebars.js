@@ -39,6 +39,11 @@ const sidebars = {
         id: 'step_by_step',
         label: 'Step by step'
       },
+      {
+        type: 'doc',
+        id: 'plugins',
+        label: 'Plugins'
+      },
     ],
 };
 
@@ -1,45 +1,129 @@
 // Copyright 2020-2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use clap::Parser;
-use dialoguer::Input;
-use iota_wallet::account::AccountHandle;
+use clap::{CommandFactory, Parser};
+use iota_wallet::{
+    account::AccountHandle, account_manager::AccountManager, iota_client::block::output::feature::MetadataFeature,
+};
 
 use crate::{
-    command::account::{
-        addresses_command, balance_command, burn_native_token_command, burn_nft_command, claim_command,
-        consolidate_command, create_alias_outputs_command, decrease_native_token_command, destroy_alias_command,
-        destroy_foundry_command, faucet_command, increase_native_token_command, mint_native_token_command,
-        mint_nft_command, new_address_command, output_command, outputs_command, send_command, send_micro_command,
-        send_native_token_command, send_nft_command, sync_command, transactions_command, unspent_outputs_command,
-        AccountCli, AccountCommand,
+    command::{
+        account::{
+            address_command, addresses_command, balance_command, balance_history_command, burn_native_token_command,
+            burn_nft_command, claim_command, claim_l2_command, consolidate_command, create_alias_outputs_command,
+            dashboard_command, decrease_native_token_command, deposit_command, deposit_l2_command,
+            destroy_alias_command, destroy_foundry_command, export_deposit_addresses_command,
+            export_transactions_command, faucet_command, increase_native_token_command, invoice_command,
+            mint_collection_command, mint_native_token_command, mint_nft_command, new_address_command, output_command,
+            outputs_command, participate_command, pending_command, prepare_send_command, print_error, report_command,
+            resume_command, retry_command, script_command, send_all_command, send_batch_command, send_command,
+            send_micro_command, send_native_token_command, send_nft_command, set_alias_command, sign_command,
+            submit_command, sync_command, transaction_command, transactions_command, unspent_outputs_command,
+            verify_address_command, vest_command, vesting_command, voting_power_command, why_failed_command,
+            AccountCli, AccountCommand, OutputFormat, RetryAction, WaitParameters,
+        },
+        account_manager::listen_command,
+        participation::participation_command,
     },
     error::Error,
-    helper::bytes_from_hex_or_file,
+    helper::{
+        address_from_qr_image, address_or_clipboard, bytes_from_hex_or_file, irc27_attributes, irc27_metadata,
+        irc30_metadata, lock_stronghold, pick_account, unlock_stronghold,
+    },
+    job::{job, Running},
+    plugin::{account_context, find_plugin, run_plugin},
+    prompt::{read_command, History},
+    signer::ExternalSigner,
+    timings::{report_timings, start_timings},
 };
 
 // loop on the account prompt
-pub async fn account_prompt(account_handle: AccountHandle) -> Result<(), Error> {
+pub async fn account_prompt(
+    account_manager: &AccountManager,
+    account_handle: AccountHandle,
+    nodes: &[String],
+    signer: Option<&ExternalSigner>,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    let mut account_handle = account_handle;
+
+    warn_unfinished_job(&account_handle).await?;
+
     loop {
-        match account_prompt_internal(account_handle.clone()).await {
-            Ok(true) => {
+        match account_prompt_internal(account_manager, account_handle.clone(), nodes, signer, output).await {
+            Ok(PromptOutcome::Exit) => {
                 return Ok(());
             }
+            Ok(PromptOutcome::Switch(other_account_handle)) => {
+                account_handle = *other_account_handle;
+                warn_unfinished_job(&account_handle).await?;
+            }
             Err(e) => {
-                log::error!("{e}");
+                print_error(&e, output);
             }
-            _ => {}
+            Ok(PromptOutcome::Continue) => {}
         }
     }
 }
 
+// What the account prompt does once a command is done.
+pub enum PromptOutcome {
+    Continue,
+    Exit,
+    /// Continue in the prompt of another account.
+    Switch(Box<AccountHandle>),
+}
+
+async fn warn_unfinished_job(account_handle: &AccountHandle) -> Result<(), Error> {
+    if let Some(job) = job(*account_handle.read().await.index())? {
+        log::warn!(
+            "An interrupted {} is unfinished, run `resume` to continue it.",
+            job.name()
+        );
+    }
+
+    Ok(())
+}
+
+// The values completed on the account prompt: the addresses of the account and the ids of its NFTs and native tokens.
+async fn completion_values(account_handle: &AccountHandle) -> Result<Vec<String>, Error> {
+    let balance = account_handle.balance().await?;
+    let mut values = account_handle
+        .addresses()
+        .await?
+        .iter()
+        .map(|address| address.address().to_bech32())
+        .collect::<Vec<_>>();
+
+    values.extend(balance.nfts.iter().map(ToString::to_string));
+    values.extend(
+        balance
+            .native_tokens
+            .iter()
+            .map(|native_token| native_token.token_id.to_string()),
+    );
+
+    Ok(values)
+}
+
 // loop on the account prompt
-pub async fn account_prompt_internal(account_handle: AccountHandle) -> Result<bool, Error> {
+pub async fn account_prompt_internal(
+    account_manager: &AccountManager,
+    account_handle: AccountHandle,
+    nodes: &[String],
+    signer: Option<&ExternalSigner>,
+    output: OutputFormat,
+) -> Result<PromptOutcome, Error> {
     let alias = {
         let account = account_handle.read().await;
         account.alias().clone()
     };
-    let command: String = Input::new().with_prompt(format!("Account \"{}\"", alias)).interact()?;
+    let command = read_command(
+        &format!("Account \"{}\"", alias),
+        &AccountCli::command(),
+        &mut History::load()?,
+        &completion_values(&account_handle).await?,
+    )?;
 
     match command.as_str() {
         "h" => {
@@ -57,91 +141,335 @@ pub async fn account_prompt_internal(account_handle: AccountHandle) -> Result<bo
             let account_cli = match AccountCli::try_parse_from(command.split(' ')) {
                 Ok(account_cli) => account_cli,
                 Err(err) => {
-                    println!("{err}");
-                    return Ok(false);
+                    // Unknown commands are forwarded to plugins, git-style.
+                    let args = command.split(' ').skip(1).collect::<Vec<_>>();
+                    if let Some(plugin) = args
+                        .first()
+                        .filter(|name| AccountCli::command().find_subcommand(**name).is_none())
+                        .and_then(|name| find_plugin(name))
+                    {
+                        run_plugin(&plugin, &args[1..], &account_context(&account_handle, nodes).await)?;
+                    } else {
+                        println!("{err}");
+                    }
+                    return Ok(PromptOutcome::Continue);
                 }
             };
-            if let Err(err) = match account_cli.command {
-                AccountCommand::Addresses => addresses_command(&account_handle).await,
-                AccountCommand::Balance => balance_command(&account_handle).await,
-                AccountCommand::BurnNativeToken { token_id, amount } => {
-                    burn_native_token_command(&account_handle, token_id, amount).await
-                }
-                AccountCommand::BurnNft { nft_id } => burn_nft_command(&account_handle, nft_id).await,
-                AccountCommand::Claim { output_id } => claim_command(&account_handle, output_id).await,
-                AccountCommand::Consolidate => consolidate_command(&account_handle).await,
-                AccountCommand::CreateAliasOutput => create_alias_outputs_command(&account_handle).await,
-                AccountCommand::DecreaseNativeTokenSupply { token_id, amount } => {
-                    decrease_native_token_command(&account_handle, token_id, amount).await
-                }
-                AccountCommand::DestroyAlias { alias_id } => destroy_alias_command(&account_handle, alias_id).await,
-                AccountCommand::DestroyFoundry { foundry_id } => {
-                    destroy_foundry_command(&account_handle, foundry_id).await
-                }
-                AccountCommand::Exit => {
-                    return Ok(true);
-                }
-                AccountCommand::Faucet { url, address } => faucet_command(&account_handle, url, address).await,
-                AccountCommand::IncreaseNativeTokenSupply { token_id, amount } => {
-                    increase_native_token_command(&account_handle, token_id, amount).await
-                }
-                AccountCommand::MintNativeToken {
-                    circulating_supply,
-                    maximum_supply,
-                    foundry_metadata_hex,
-                    foundry_metadata_file,
-                } => {
-                    mint_native_token_command(
-                        &account_handle,
-                        circulating_supply,
-                        maximum_supply,
-                        bytes_from_hex_or_file(foundry_metadata_hex, foundry_metadata_file).await?,
-                    )
-                    .await
-                }
-                AccountCommand::MintNft {
-                    address,
-                    immutable_metadata_hex,
-                    immutable_metadata_file,
-                    metadata_hex,
-                    metadata_file,
-                    tag,
-                    sender,
-                    issuer,
-                } => {
-                    mint_nft_command(
-                        &account_handle,
-                        address,
-                        bytes_from_hex_or_file(immutable_metadata_hex, immutable_metadata_file).await?,
-                        bytes_from_hex_or_file(metadata_hex, metadata_file).await?,
-                        tag,
-                        sender,
-                        issuer,
-                    )
-                    .await
+            match account_cli.command {
+                AccountCommand::Exit => return Ok(PromptOutcome::Exit),
+                AccountCommand::Lock => {
+                    lock_stronghold(account_manager).await?;
+                    log::info!("Stronghold locked, its password is asked for before the next command.");
+                    return Ok(PromptOutcome::Continue);
                 }
-                AccountCommand::NewAddress => new_address_command(&account_handle).await,
-                AccountCommand::Output { output_id } => output_command(&account_handle, output_id).await,
-                AccountCommand::Outputs => outputs_command(&account_handle).await,
-                AccountCommand::Send { address, amount } => send_command(&account_handle, address, amount).await,
-                AccountCommand::SendMicro { address, amount } => {
-                    send_micro_command(&account_handle, address, amount).await
+                AccountCommand::Switch { account } => {
+                    let account = match account {
+                        Some(account) => account,
+                        None => match pick_account(account_manager).await? {
+                            Some(index) => index.to_string(),
+                            None => return Ok(PromptOutcome::Continue),
+                        },
+                    };
+
+                    return Ok(PromptOutcome::Switch(Box::new(
+                        account_manager.get_account(account).await?,
+                    )));
                 }
-                AccountCommand::SendNativeToken {
-                    address,
-                    token_id,
-                    amount,
-                    gift_storage_deposit,
-                } => send_native_token_command(&account_handle, address, token_id, amount, gift_storage_deposit).await,
-                AccountCommand::SendNft { address, nft_id } => send_nft_command(&account_handle, address, nft_id).await,
-                AccountCommand::Sync => sync_command(&account_handle).await,
-                AccountCommand::Transactions => transactions_command(&account_handle).await,
-                AccountCommand::UnspentOutputs => unspent_outputs_command(&account_handle).await,
-            } {
-                log::error!("{}", err);
+                _ => {}
             }
+
+            unlock_stronghold(account_manager).await?;
+
+            let name = account_cli.command.name();
+            let start = start_timings();
+            // An interruption lets the command finish its current step.
+            let _running = Running::start();
+
+            if let Err(err) =
+                account_command(account_manager, &account_handle, account_cli.command, signer, output).await
+            {
+                print_error(&err, output);
+            }
+
+            report_timings(&name, start);
+            // The inactivity only starts once the command is done.
+            unlock_stronghold(account_manager).await?;
         }
     }
 
-    Ok(false)
+    Ok(PromptOutcome::Continue)
+}
+
+/// Runs an account command, from the prompt or directly from the shell with `./wallet account`.
+pub async fn account_command(
+    account_manager: &AccountManager,
+    account_handle: &AccountHandle,
+    command: AccountCommand,
+    signer: Option<&ExternalSigner>,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    match command {
+        AccountCommand::Address { index, qr } => address_command(account_handle, index, qr).await,
+        AccountCommand::Addresses { verbose, unspent, used } => {
+            addresses_command(account_handle, verbose, unspent, used, output).await
+        }
+        AccountCommand::Balance => balance_command(account_handle, output).await,
+        AccountCommand::BalanceHistory { since, chart } => balance_history_command(account_handle, since, chart).await,
+        AccountCommand::BurnNativeToken { token_id, amount } => {
+            burn_native_token_command(account_handle, token_id, amount).await
+        }
+        AccountCommand::BurnNft { nft_id } => burn_nft_command(account_handle, nft_id).await,
+        AccountCommand::Claim { output_id, all } => claim_command(account_handle, output_id, all).await,
+        AccountCommand::ClaimL2 => claim_l2_command(account_handle).await,
+        AccountCommand::Consolidate => consolidate_command(account_handle).await,
+        AccountCommand::CreateAliasOutput => create_alias_outputs_command(account_handle).await,
+        AccountCommand::Dashboard { interval } => dashboard_command(account_handle, interval).await,
+        AccountCommand::DecreaseNativeTokenSupply { token_id, amount } => {
+            decrease_native_token_command(account_handle, token_id, amount).await
+        }
+        AccountCommand::Deposit(command) => deposit_command(account_handle, command).await,
+        AccountCommand::DepositL2 {
+            chain_address,
+            evm_address,
+            amount,
+            token_id,
+        } => deposit_l2_command(account_handle, chain_address, evm_address, amount, token_id).await,
+        AccountCommand::DestroyAlias { alias_id } => destroy_alias_command(account_handle, alias_id).await,
+        AccountCommand::DestroyFoundry { foundry_id } => destroy_foundry_command(account_handle, foundry_id).await,
+        // Handled by the prompt.
+        AccountCommand::Exit | AccountCommand::Lock | AccountCommand::Switch { .. } => Ok(()),
+        AccountCommand::ExportDepositAddresses { count, out } => {
+            export_deposit_addresses_command(account_handle, count, out).await
+        }
+        AccountCommand::ExportTransactions { path } => export_transactions_command(account_handle, path).await,
+        AccountCommand::Faucet { url, address } => faucet_command(account_handle, url, address).await,
+        AccountCommand::IncreaseNativeTokenSupply { token_id, amount, wait } => {
+            increase_native_token_command(account_handle, token_id, amount, wait, output).await
+        }
+        AccountCommand::Invoice(command) => invoice_command(account_handle, command, output).await,
+        AccountCommand::Listen { interval } => {
+            listen_command(account_manager, Some(*account_handle.read().await.index()), interval).await
+        }
+        AccountCommand::MintNativeToken {
+            circulating_supply,
+            maximum_supply,
+            foundry_metadata_hex,
+            foundry_metadata_file,
+            name,
+            symbol,
+            decimals,
+            description,
+            logo_url,
+            alias,
+            dry_run,
+            wait,
+        } => {
+            let foundry_metadata = match (name, symbol, decimals) {
+                (Some(name), Some(symbol), Some(decimals)) => {
+                    Some(irc30_metadata(name, symbol, decimals, description, logo_url)?)
+                }
+                _ => bytes_from_hex_or_file(foundry_metadata_hex, foundry_metadata_file).await?,
+            };
+
+            mint_native_token_command(
+                account_handle,
+                circulating_supply,
+                maximum_supply,
+                foundry_metadata,
+                alias,
+                dry_run,
+                wait,
+                output,
+            )
+            .await
+        }
+        AccountCommand::MintCollection {
+            path,
+            dry_run,
+            yes,
+            wait,
+        } => mint_collection_command(account_handle, path, dry_run, yes, wait, output).await,
+        AccountCommand::MintNft {
+            address,
+            immutable_metadata_hex,
+            immutable_metadata_file,
+            metadata_hex,
+            metadata_file,
+            tag,
+            sender,
+            issuer,
+            name,
+            uri,
+            mime_type,
+            collection,
+            attributes,
+            royalty,
+            dry_run,
+            wait,
+        } => {
+            let immutable_metadata = match (name, uri) {
+                (Some(name), Some(uri)) => {
+                    let attributes = match attributes {
+                        Some(file) => Some(irc27_attributes(&file).await?),
+                        None => None,
+                    };
+                    let metadata = irc27_metadata(name, uri, mime_type, collection, attributes, royalty)?;
+
+                    log::info!(
+                        "IRC-27 metadata: {}\nsize: {} of at most {} bytes",
+                        String::from_utf8_lossy(&metadata),
+                        metadata.len(),
+                        MetadataFeature::LENGTH_RANGE.end()
+                    );
+
+                    Some(metadata)
+                }
+                _ => bytes_from_hex_or_file(immutable_metadata_hex, immutable_metadata_file).await?,
+            };
+
+            mint_nft_command(
+                account_handle,
+                address,
+                immutable_metadata,
+                bytes_from_hex_or_file(metadata_hex, metadata_file).await?,
+                tag,
+                sender,
+                issuer,
+                dry_run,
+                wait,
+                output,
+            )
+            .await
+        }
+        AccountCommand::NewAddress { qr } => new_address_command(account_handle, qr).await,
+        AccountCommand::Output { output_id } => output_command(account_handle, output_id).await,
+        AccountCommand::Outputs => outputs_command(account_handle).await,
+        AccountCommand::Participate {
+            event_id,
+            amount,
+            maintain,
+        } => participate_command(account_handle, event_id, amount, maintain).await,
+        AccountCommand::Participation(command) => participation_command(account_handle, command).await,
+        AccountCommand::Pending => pending_command(account_handle).await,
+        AccountCommand::Promote { transaction_id } => {
+            retry_command(
+                account_handle,
+                transaction_id,
+                RetryAction::Promote,
+                WaitParameters::default(),
+                output,
+            )
+            .await
+        }
+        AccountCommand::Reattach { transaction_id } => {
+            retry_command(
+                account_handle,
+                transaction_id,
+                RetryAction::Reattach,
+                WaitParameters::default(),
+                output,
+            )
+            .await
+        }
+        AccountCommand::PrepareSend { address, amount, out } => {
+            prepare_send_command(account_handle, address, amount, out).await
+        }
+        AccountCommand::Report {
+            period,
+            format,
+            currency,
+            out,
+        } => report_command(account_handle, period, format, currency, out).await,
+        AccountCommand::Resume => resume_command(account_handle).await,
+        AccountCommand::Retry { transaction_id, wait } => {
+            retry_command(account_handle, transaction_id, RetryAction::Retry, wait, output).await
+        }
+        AccountCommand::Script { path } => script_command(account_handle, path).await,
+        AccountCommand::Send {
+            address,
+            amount,
+            from_clipboard,
+            qr_image,
+            options,
+            dry_run,
+            wait,
+        } => {
+            let address = match qr_image {
+                Some(path) => address_from_qr_image(&path, amount)?,
+                None => address_or_clipboard(address, from_clipboard)?,
+            };
+
+            send_command(account_handle, address, amount, signer, options, dry_run, wait, output).await
+        }
+        AccountCommand::SendAll {
+            address,
+            include_assets,
+            dry_run,
+            yes,
+        } => send_all_command(account_handle, address, include_assets, dry_run, yes, output).await,
+        AccountCommand::SendBatch { path, yes } => send_batch_command(account_handle, path, yes, output).await,
+        AccountCommand::SendMicro {
+            address,
+            amount,
+            options,
+            dry_run,
+            wait,
+        } => send_micro_command(account_handle, address, amount, options, dry_run, wait, output).await,
+        AccountCommand::SendNativeToken {
+            address,
+            token_id,
+            amount,
+            gift_storage_deposit,
+            dry_run,
+            wait,
+        } => {
+            send_native_token_command(
+                account_handle,
+                address,
+                token_id,
+                amount,
+                gift_storage_deposit,
+                dry_run,
+                wait,
+                output,
+            )
+            .await
+        }
+        AccountCommand::SendNft {
+            address,
+            nft_id,
+            from_clipboard,
+            dry_run,
+            wait,
+        } => {
+            send_nft_command(
+                account_handle,
+                address_or_clipboard(address, from_clipboard)?,
+                nft_id,
+                dry_run,
+                wait,
+                output,
+            )
+            .await
+        }
+        AccountCommand::SetAlias { alias } => set_alias_command(account_manager, account_handle, alias).await,
+        AccountCommand::Sign { path, out } => sign_command(account_handle, path, out).await,
+        AccountCommand::Submit { path, wait } => submit_command(account_handle, path, wait, output).await,
+        AccountCommand::Sync => sync_command(account_handle, output).await,
+        AccountCommand::Transaction { transaction_id } => transaction_command(account_handle, transaction_id).await,
+        AccountCommand::Transactions { tui } => transactions_command(account_handle, tui, output).await,
+        AccountCommand::UnspentOutputs => unspent_outputs_command(account_handle).await,
+        AccountCommand::VerifyAddress { address, gap } => {
+            verify_address_command(account_manager, account_handle, address, gap, output).await
+        }
+        AccountCommand::Vest {
+            address,
+            total,
+            installments,
+            interval,
+        } => vest_command(account_handle, address, total, installments, interval).await,
+        AccountCommand::Vesting(command) => vesting_command(account_handle, command).await,
+        AccountCommand::VotingPower(command) => voting_power_command(account_handle, command).await,
+        AccountCommand::WhyFailed { transaction_id } => why_failed_command(account_handle, transaction_id).await,
+    }
 }
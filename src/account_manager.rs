@@ -1,87 +1,281 @@
 // Copyright 2020-2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::env::var_os;
+use std::{env::var_os, path::PathBuf};
 
 use iota_wallet::{
     account_manager::AccountManager,
-    secret::{stronghold::StrongholdSecretManager, SecretManager},
+    secret::{placeholder::PlaceholderSecretManager, stronghold::StrongholdSecretManager, SecretManager},
 };
 
 use crate::{
     command::account_manager::{
-        backup_command, change_password_command, init_command, mnemonic_command, new_command, restore_command,
-        set_node_command, sync_command, AccountManagerCli, AccountManagerCommand,
+        accounts_command, add_node_command, backup_command, backup_reminder, bug_report_command,
+        change_password_command, compact_command, config_command, doctor_command, export_mnemonic_command,
+        export_sqlite_command, init_command, list_nodes_command, listen_command, migrate_chrysalis_command,
+        mnemonic_command, new_command, node_info_command, prune_command, recover_command, remove_node_command,
+        repair_storage_command, restore_command, rotate_stronghold_command, self_update_command, set_node_command,
+        set_price_source_command, set_quorum_command, sign_qr_command, split_mnemonic_command, store_seed,
+        stronghold_command, sync_command, verify_command, AccountManagerCli, AccountManagerCommand, InitParameters,
+        MigrateChrysalisParameters, ACTIVE_STRONGHOLD_FILE,
     },
     error::Error,
-    helper::get_password,
+    helper::{get_password, storage_path},
+    signer::ExternalSigner,
+    timings::{timed, Phase},
 };
 
-pub async fn new_account_manager(cli: AccountManagerCli) -> Result<(Option<AccountManager>, Option<String>), Error> {
-    if let Some(AccountManagerCommand::Mnemonic) = cli.command {
-        mnemonic_command().await?;
-        return Ok((None, None));
+pub async fn new_account_manager(
+    cli: AccountManagerCli,
+    signer: Option<&ExternalSigner>,
+) -> Result<(Option<AccountManager>, Option<String>), Error> {
+    match cli.command {
+        Some(AccountManagerCommand::Mnemonic) => {
+            mnemonic_command().await?;
+            return Ok((None, None));
+        }
+        Some(AccountManagerCommand::SelfUpdate { check_only }) => {
+            self_update_command(check_only).await?;
+            return Ok((None, None));
+        }
+        _ => {}
     }
 
-    let storage_path = var_os("WALLET_DATABASE_PATH").map_or_else(
-        || "./stardust-cli-wallet-db".to_string(),
-        |os_str| os_str.into_string().expect("invalid WALLET_DATABASE_PATH"),
-    );
-    let snapshot_path = std::path::Path::new("./stardust-cli-wallet.stronghold");
-    let password = if let Some(AccountManagerCommand::Restore { .. }) = &cli.command {
-        get_password("Stronghold password", false)?
+    let storage_path = storage_path();
+    match cli.command {
+        Some(AccountManagerCommand::BugReport { ref path }) => {
+            bug_report_command(&storage_path, path.clone()).await?;
+            return Ok((None, None));
+        }
+        Some(AccountManagerCommand::Compact) => {
+            compact_command(&storage_path).await?;
+            return Ok((None, None));
+        }
+        Some(AccountManagerCommand::Config(ref command)) => {
+            config_command(command.clone(), cli.profile.as_deref())?;
+            return Ok((None, None));
+        }
+        Some(AccountManagerCommand::Prune { ref before }) => {
+            prune_command(&storage_path, before.clone()).await?;
+            return Ok((None, None));
+        }
+        Some(AccountManagerCommand::SetPriceSource {
+            ref source,
+            ref currency,
+            offline,
+        }) => {
+            set_price_source_command(source.clone(), currency.clone(), offline)?;
+            return Ok((None, None));
+        }
+        _ => {}
+    }
+
+    let snapshot_path = PathBuf::from(cli.stronghold_path.clone().unwrap_or_else(|| {
+        var_os("WALLET_STRONGHOLD_PATH").map_or_else(
+            || {
+                std::fs::read_to_string(ACTIVE_STRONGHOLD_FILE).map_or_else(
+                    |_| "./stardust-cli-wallet.stronghold".to_string(),
+                    |path| path.trim().to_string(),
+                )
+            },
+            |os_str| os_str.into_string().expect("invalid WALLET_STRONGHOLD_PATH"),
+        )
+    }));
+    let snapshot_path = snapshot_path.as_path();
+
+    match &cli.command {
+        Some(AccountManagerCommand::Doctor) => {
+            doctor_command(&storage_path, snapshot_path).await?;
+            return Ok((None, None));
+        }
+        Some(AccountManagerCommand::Stronghold(command)) => {
+            stronghold_command(command.clone(), snapshot_path).await?;
+            return Ok((None, None));
+        }
+        _ => {}
+    }
+    // With an external signer, there is no Stronghold and only the commands that don't need it can be run.
+    if signer.is_some()
+        && !matches!(
+            &cli.command,
+            None | Some(
+                AccountManagerCommand::Account(_)
+                    | AccountManagerCommand::Accounts
+                    | AccountManagerCommand::AddNode { .. }
+                    | AccountManagerCommand::ExportSqlite { .. }
+                    | AccountManagerCommand::Init(InitParameters { seed: None, .. })
+                    | AccountManagerCommand::Listen { .. }
+                    | AccountManagerCommand::ListNodes
+                    | AccountManagerCommand::New { .. }
+                    | AccountManagerCommand::NodeInfo
+                    | AccountManagerCommand::RemoveNode { .. }
+                    | AccountManagerCommand::SetNode { .. }
+                    | AccountManagerCommand::SetQuorum { .. }
+                    | AccountManagerCommand::Sync
+                    | AccountManagerCommand::Verify { .. }
+            )
+        )
+    {
+        return Err(Error::ExternalSignerUnsupported);
+    }
+    let (secret_manager, password) = if signer.is_some() {
+        (SecretManager::Placeholder(PlaceholderSecretManager), String::new())
     } else {
-        get_password("Stronghold password", !snapshot_path.exists())?
+        let password = if let Some(
+            AccountManagerCommand::Restore { .. }
+            | AccountManagerCommand::MigrateChrysalis(MigrateChrysalisParameters { backup: Some(_), .. }),
+        ) = &cli.command
+        {
+            get_password("Stronghold password", false)?
+        } else if let Some(AccountManagerCommand::ChangePassword) = &cli.command {
+            get_password("Stronghold current password", false)?
+        } else {
+            get_password("Stronghold password", !snapshot_path.exists())?
+        };
+        if let Some(AccountManagerCommand::Init(InitParameters { seed: Some(seed), .. })) = &cli.command {
+            store_seed(snapshot_path, &password, seed)?;
+        }
+        // Reading the snapshot derives its key from the password, the bulk of opening the Stronghold.
+        let secret_manager = SecretManager::Stronghold(
+            timed(Phase::SecretManager, async {
+                StrongholdSecretManager::builder()
+                    .password(&password)
+                    .build(snapshot_path)
+            })
+            .await?,
+        );
+
+        (secret_manager, password)
     };
-    let secret_manager = SecretManager::Stronghold(
-        StrongholdSecretManager::builder()
-            .password(&password)
-            .build(snapshot_path)?,
-    );
 
     let (account_manager, account) = if let Some(command) = cli.command {
         if let AccountManagerCommand::Init(mnemonic_url) = command {
             (init_command(secret_manager, storage_path, mnemonic_url).await?, None)
+        } else if let AccountManagerCommand::MigrateChrysalis(parameters) = command {
+            (
+                migrate_chrysalis_command(secret_manager, storage_path, parameters, password).await?,
+                None,
+            )
+        } else if let AccountManagerCommand::RepairStorage = command {
+            (
+                repair_storage_command(secret_manager, storage_path, snapshot_path, &password).await?,
+                None,
+            )
         } else if let AccountManagerCommand::Restore { backup_path } = command {
             (
                 restore_command(secret_manager, storage_path, backup_path, password).await?,
                 None,
             )
         } else {
-            let account_manager = AccountManager::builder()
-                .with_secret_manager(secret_manager)
-                .with_storage_path(&storage_path)
-                .finish()
-                .await?;
+            let account_manager = timed(
+                Phase::Storage,
+                AccountManager::builder()
+                    .with_secret_manager(secret_manager)
+                    .with_storage_path(&storage_path)
+                    .finish(),
+            )
+            .await?;
             let mut account = None;
 
             match command {
+                // Run by `run` once the account manager is built.
+                AccountManagerCommand::Account(_) => {}
+                AccountManagerCommand::Accounts => {
+                    accounts_command(&account_manager, cli.backup_max_age).await?;
+                    return Ok((None, None));
+                }
+                AccountManagerCommand::AddNode { url, primary } => {
+                    add_node_command(&account_manager, url, primary).await?
+                }
                 AccountManagerCommand::Backup { path } => {
                     backup_command(&account_manager, path, &password).await?;
                     return Ok((None, None));
                 }
                 AccountManagerCommand::ChangePassword => change_password_command(&account_manager, &password).await?,
-                AccountManagerCommand::New { alias } => account = Some(new_command(&account_manager, alias).await?),
+                AccountManagerCommand::ExportMnemonic { out } => {
+                    export_mnemonic_command(&account_manager, out).await?;
+                    return Ok((None, None));
+                }
+                AccountManagerCommand::ExportSqlite { path } => {
+                    export_sqlite_command(&account_manager, path).await?;
+                    return Ok((None, None));
+                }
+                AccountManagerCommand::Listen { interval } => {
+                    listen_command(&account_manager, None, interval).await?;
+                    return Ok((None, None));
+                }
+                AccountManagerCommand::ListNodes => {
+                    list_nodes_command(&account_manager).await?;
+                    return Ok((None, None));
+                }
+                AccountManagerCommand::New { alias } => {
+                    account = Some(new_command(&account_manager, alias, signer).await?)
+                }
+                AccountManagerCommand::NodeInfo => {
+                    node_info_command(&account_manager).await?;
+                    return Ok((None, None));
+                }
+                AccountManagerCommand::Recover {
+                    account_gap,
+                    address_gap,
+                } => recover_command(&account_manager, account_gap, address_gap).await?,
+                AccountManagerCommand::RemoveNode { url } => remove_node_command(&account_manager, url).await?,
+                AccountManagerCommand::RotateStronghold { keep_previous } => {
+                    rotate_stronghold_command(&account_manager, snapshot_path, &password, keep_previous).await?;
+                    return Ok((None, None));
+                }
                 AccountManagerCommand::SetNode { url } => set_node_command(&account_manager, url).await?,
+                AccountManagerCommand::SetQuorum {
+                    disable,
+                    min_size,
+                    threshold,
+                } => set_quorum_command(&account_manager, disable, min_size, threshold).await?,
+                AccountManagerCommand::SignQr => {
+                    sign_qr_command(&account_manager).await?;
+                    return Ok((None, None));
+                }
+                AccountManagerCommand::SplitMnemonic { shares, threshold } => {
+                    split_mnemonic_command(&account_manager, shares, threshold).await?;
+                    return Ok((None, None));
+                }
                 AccountManagerCommand::Sync => sync_command(&account_manager).await?,
+                AccountManagerCommand::Verify { repair } => {
+                    verify_command(&account_manager, repair).await?;
+                    return Ok((None, None));
+                }
                 // PANIC: this will never happen because these variants have already been checked.
-                AccountManagerCommand::Init(_)
+                AccountManagerCommand::BugReport { .. }
+                | AccountManagerCommand::Compact
+                | AccountManagerCommand::Config(_)
+                | AccountManagerCommand::Doctor
+                | AccountManagerCommand::Init(_)
+                | AccountManagerCommand::MigrateChrysalis(_)
                 | AccountManagerCommand::Mnemonic
-                | AccountManagerCommand::Restore { .. } => unreachable!(),
+                | AccountManagerCommand::Prune { .. }
+                | AccountManagerCommand::RepairStorage
+                | AccountManagerCommand::Restore { .. }
+                | AccountManagerCommand::SelfUpdate { .. }
+                | AccountManagerCommand::SetPriceSource { .. }
+                | AccountManagerCommand::Stronghold(_) => unreachable!(),
             };
 
             (account_manager, account)
         }
     } else {
         (
-            AccountManager::builder()
-                .with_secret_manager(secret_manager)
-                .with_storage_path(&storage_path)
-                .finish()
-                .await?,
+            timed(
+                Phase::Storage,
+                AccountManager::builder()
+                    .with_secret_manager(secret_manager)
+                    .with_storage_path(&storage_path)
+                    .finish(),
+            )
+            .await?,
             None,
         )
     };
 
+    backup_reminder(&account_manager, cli.backup_max_age).await?;
+
     Ok((Some(account_manager), account))
 }
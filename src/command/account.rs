@@ -1,29 +1,78 @@
 // Copyright 2020-2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::str::FromStr;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use clap::{Parser, Subcommand};
+use clap::{ArgEnum, Args, Parser, Subcommand};
+use dialoguer::Confirm;
 use iota_wallet::{
     account::{
-        types::{AccountAddress, TransactionDto},
-        AccountHandle, OutputsToClaim,
+        types::{AccountAddress, AddressWithUnspentOutputs, InclusionState, OutputData, Transaction},
+        AccountHandle, Assets, OutputOptions, OutputsToClaim, SyncOptions, TransactionOptions, Unlocks,
     },
+    account_manager::AccountManager,
     iota_client::{
+        api::{PreparedTransactionData, PreparedTransactionDataDto, SignedTransactionData, SignedTransactionDataDto},
         block::{
-            address::Address,
+            address::{Address, AliasAddress},
+            input::Input,
             output::{
-                unlock_condition::AddressUnlockCondition, AliasId, BasicOutputBuilder, FoundryId, NativeToken, NftId,
-                OutputId, TokenId, UnlockCondition,
+                feature::{Feature, IssuerFeature, MetadataFeature, SenderFeature, TagFeature},
+                unlock_condition::{
+                    AddressUnlockCondition, ExpirationUnlockCondition, ImmutableAliasAddressUnlockCondition,
+                    StorageDepositReturnUnlockCondition, TimelockUnlockCondition,
+                },
+                AliasId, AliasOutputBuilder, BasicOutputBuilder, FoundryId, FoundryOutputBuilder, NativeToken,
+                NativeTokens, NftId, NftOutputBuilder, Output, OutputId, Rent, SimpleTokenScheme, TokenId, TokenScheme,
+                UnlockCondition, OUTPUT_COUNT_MAX,
+            },
+            payload::{
+                transaction::{TransactionEssence, TransactionId},
+                Payload,
             },
+            semantic::ConflictReason,
+            Block, BlockId,
         },
+        error::Error as ClientError,
+        packable::PackableExt,
         request_funds_from_faucet,
+        secret::{types::InputSigningData, SecretManage},
     },
     AddressAndNftId, AddressNativeTokens, AddressWithAmount, AddressWithMicroAmount, NativeTokenOptions, NftOptions,
     U256,
 };
 
-use crate::error::Error;
+use crate::{
+    command::{account_manager::log_transaction_outputs, participation::ParticipationCommand},
+    config::config,
+    deposit::{assign_address, received_by_order, report_deposits},
+    error::Error,
+    helper::{
+        balance_history, check_network, format_amount, format_timestamp, format_token_amount, foundry_irc30,
+        irc27_metadata, parse_date, parse_duration, print_paged, record_balance, Irc30,
+    },
+    invoice::{create_invoice, update_invoices, Invoice, InvoiceStatus},
+    isc,
+    job::{check_no_job, interrupted, job, set_job, Job},
+    participation::{
+        current_voting_power, decrease_voting_power, increase_voting_power, maintain_participation, participate,
+    },
+    price::{cached_price, price_config},
+    qr::{qr_text, write_qr_png},
+    report::{accounting_report, transactions_csv},
+    script::script_engine,
+    signer::ExternalSigner,
+    timings::{timed, untimed, Phase},
+    tui::{dashboard, transactions_table, TransactionRow},
+};
+
+// Outputs expiring within this period, in seconds, are warned about.
+const EXPIRATION_WARNING_PERIOD: u32 = 7 * 24 * 60 * 60;
 
 #[derive(Debug, Parser)]
 #[clap(version, long_about = None)]
@@ -33,38 +82,131 @@ pub struct AccountCli {
     pub command: AccountCommand,
 }
 
-#[derive(Debug, Subcommand)]
+#[derive(Debug, Clone, Subcommand)]
 pub enum AccountCommand {
-    /// List the account addresses.
-    Addresses,
-    /// Print the account balance.
+    /// Print an address of the account by its index, as a QR code with `--qr`: `address 0 --qr [--png address.png]`
+    Address {
+        index: u32,
+        #[clap(flatten)]
+        qr: AddressQrParameters,
+    },
+    /// List the account addresses, one line per address with its balance unless `--verbose` is given, only the ones
+    /// holding funds with `--unspent` or the ones that received some with `--used`
+    Addresses {
+        /// Print the detailed view of each address, including its unspent outputs.
+        #[clap(long)]
+        verbose: bool,
+        /// Only list the addresses holding unspent outputs.
+        #[clap(long)]
+        unspent: bool,
+        /// Only list the addresses that received funds, spent or not.
+        #[clap(long)]
+        used: bool,
+    },
+    /// Print the account balance, with the IRC-30 name and symbol of the native tokens: `balance`
     Balance,
+    /// Print the balance changes recorded at each sync and the transactions responsible: `balance-history [--since
+    /// 2022-12-31]`
+    BalanceHistory {
+        #[clap(long)]
+        since: Option<String>,
+        /// Render the total balance over time as a chart instead.
+        #[clap(long)]
+        chart: bool,
+    },
     /// Burn a native token: `burn-native-token 0x... 100`
     BurnNativeToken { token_id: String, amount: String },
     /// Burn an NFT: `burn-nft 0x...`
     BurnNft { nft_id: String },
-    /// Claim outputs with storage deposit return, expiration or timelock unlock conditions.
-    Claim { output_id: Option<String> },
+    /// List the outputs with storage deposit return, expiration or timelock unlock conditions and when they become
+    /// claimable, or claim one of them: `claim 0x...`, or all the claimable ones: `claim --all`
+    Claim {
+        output_id: Option<String>,
+        #[clap(long, conflicts_with = "output-id")]
+        all: bool,
+    },
+    /// List the outputs sent by smart contract chains, e.g. withdrawals from L2, with their request, and claim the
+    /// ones that need it.
+    ClaimL2,
     /// Consolidate all basic outputs into one address.
     Consolidate,
-    /// Create a new alias output.
+    /// Create a new alias output, e.g. to control the foundries of native tokens: `create-alias-output`, or
+    /// `create-alias`
+    #[clap(visible_alias = "create-alias")]
     CreateAliasOutput,
-    /// Melt a native token: `decrease-native-token-supply 0x... 100`
+    /// Show live panels for the balance, the recent transactions, the pending operations and the node, synced every
+    /// `interval` seconds: `dashboard [--interval 10]`
+    Dashboard {
+        #[clap(long, default_value = "10")]
+        interval: u64,
+    },
+    /// Melt a native token: `decrease-native-token-supply 0x... 100`, or `melt-native-token 0x... 100`
+    #[clap(visible_alias = "melt-native-token")]
     DecreaseNativeTokenSupply { token_id: String, amount: String },
+    /// Assign deposit addresses to external orders and follow what they received: `deposit assign order-42`,
+    /// `deposit status [order-42]`
+    #[clap(subcommand)]
+    Deposit(DepositCommand),
+    /// Deposit base tokens, or native tokens with `--token-id`, to an EVM address on a smart contract chain:
+    /// `deposit-l2 smr1pr0waglqq3jjjhqxz0s5jkk9r97myuds789qym40xwstcd3g24jh2tk62ts
+    /// 0x48b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5 1000000`
+    DepositL2 {
+        chain_address: String,
+        evm_address: String,
+        amount: String,
+        #[clap(long)]
+        token_id: Option<String>,
+    },
     /// Destroy an alias: `destroy-alias 0x...`
     DestroyAlias { alias_id: String },
     /// Destroy a foundry: `destroy-foundry 0x...`
     DestroyFoundry { foundry_id: String },
     /// Exit from the account prompt.
     Exit,
+    /// Generate a batch of deposit addresses and write them with their indices to a CSV file, or a JSON one with a
+    /// `.json` extension: `export-deposit-addresses --count 1000 --out addresses.csv`
+    ExportDepositAddresses {
+        /// Number of addresses to generate.
+        #[clap(long, default_value = "1000")]
+        count: u32,
+        #[clap(long)]
+        out: String,
+    },
+    /// Write the whole transaction history to a CSV file, with the amounts moved relative to the account, to import
+    /// it into accounting or tax tools: `export-transactions transactions.csv`
+    ExportTransactions { path: String },
     /// Request funds from the faucet to the latest address, `url` is optional, default is `http://localhost:8091/api/enqueue`
     Faucet {
         url: Option<String>,
         address: Option<String>,
     },
-    /// Mint more of a native token: `increase-native-token-supply 0x... 100`
-    IncreaseNativeTokenSupply { token_id: String, amount: String },
-    /// Mint a native token: `mint-native-token 100 100 --foundry-metadata-hex 0x...`
+    /// Mint more of a native token of a foundry of the account, up to its maximum supply:
+    /// `increase-native-token-supply 0x... 100`
+    IncreaseNativeTokenSupply {
+        token_id: String,
+        amount: String,
+        #[clap(flatten)]
+        wait: WaitParameters,
+    },
+    /// Invoice incoming payments, marked paid or expired by `sync`: `invoice create 1000000 --expires 7d`, `invoice
+    /// list`, `invoice status 1`
+    #[clap(subcommand)]
+    Invoice(InvoiceCommand),
+    /// Mint the NFTs of a collection described by a JSON manifest, in as few transactions as possible:
+    /// `mint-collection collection.json [--yes]`
+    MintCollection {
+        path: String,
+        /// List the NFTs and the transactions they need without minting them.
+        #[clap(long)]
+        dry_run: bool,
+        /// Mint without asking for confirmation.
+        #[clap(long, conflicts_with = "dry-run")]
+        yes: bool,
+        #[clap(flatten)]
+        wait: WaitParameters,
+    },
+    /// Mint a native token: `mint-native-token 100 100 --foundry-metadata-hex 0x...` or, with IRC-30 metadata,
+    /// `mint-native-token 100 100 --name MyToken --symbol MTK --decimals 6`
     MintNativeToken {
         circulating_supply: String,
         maximum_supply: String,
@@ -72,9 +214,35 @@ pub enum AccountCommand {
         foundry_metadata_hex: Option<String>,
         #[clap(long, group = "foundry_metadata")]
         foundry_metadata_file: Option<String>,
+        /// IRC-30 name of the token.
+        #[clap(long, conflicts_with = "foundry_metadata", requires_all = &["symbol", "decimals"])]
+        name: Option<String>,
+        /// IRC-30 symbol of the token.
+        #[clap(long, requires = "name")]
+        symbol: Option<String>,
+        /// IRC-30 number of decimals of the token.
+        #[clap(long, requires = "name")]
+        decimals: Option<u32>,
+        /// IRC-30 description of the token.
+        #[clap(long, requires = "name")]
+        description: Option<String>,
+        /// IRC-30 URL of the logo of the token.
+        #[clap(long, requires = "name")]
+        logo_url: Option<String>,
+        /// Alias controlling the foundry of the token, as an alias id or a bech32 alias address, any alias of the
+        /// account otherwise.
+        #[clap(long)]
+        alias: Option<String>,
+        /// Print the selected inputs, the outputs with their storage deposit and the remainder, without signing or
+        /// sending anything.
+        #[clap(long, conflicts_with = "wait")]
+        dry_run: bool,
+        #[clap(flatten)]
+        wait: WaitParameters,
     },
     /// Mint an NFT to an optional bech32 encoded address: `mint-nft
     /// rms1qztwng6cty8cfm42nzvq099ev7udhrnk0rw8jt8vttf9kpqnxhpsx869vr3 "immutable metadata" "metadata"`
+    /// or, with IRC-27 immutable metadata, `mint-nft --name MyNft --uri https://example.com/nft.png`
     MintNft {
         address: Option<String>,
         #[clap(long, group = "immutable_metadata")]
@@ -89,21 +257,166 @@ pub enum AccountCommand {
         tag: Option<String>,
         #[clap(long)]
         sender: Option<String>,
+        /// Bech32 encoded address of the issuer feature, one of the account.
         #[clap(long)]
         issuer: Option<String>,
+        /// IRC-27 name of the NFT.
+        #[clap(long, conflicts_with = "immutable_metadata", requires = "uri")]
+        name: Option<String>,
+        /// IRC-27 URI of the NFT file.
+        #[clap(long, requires = "name")]
+        uri: Option<String>,
+        /// IRC-27 MIME type of the NFT file, guessed from the extension of the URI by default.
+        #[clap(long, requires = "name")]
+        mime_type: Option<String>,
+        /// IRC-27 name of the collection of the NFT.
+        #[clap(long, requires = "name")]
+        collection: Option<String>,
+        /// JSON file of IRC-27 attributes of the NFT, an array of `trait_type` and `value` objects.
+        #[clap(long, requires = "name")]
+        attributes: Option<String>,
+        /// IRC-27 royalty of a bech32 encoded address, as `<address>:<percent>`, repeatable.
+        #[clap(long, requires = "name")]
+        royalty: Vec<String>,
+        /// Print the selected inputs, the outputs with their storage deposit and the remainder, without signing or
+        /// sending anything.
+        #[clap(long, conflicts_with = "wait")]
+        dry_run: bool,
+        #[clap(flatten)]
+        wait: WaitParameters,
+    },
+    /// Show the new outputs, spent outputs and transaction confirmations of the account as they are synced, with its
+    /// balance, until interrupted: `listen --interval 30s`
+    Listen {
+        /// Time between syncs, 7s by default.
+        #[clap(long)]
+        interval: Option<String>,
+    },
+    /// Clear the Stronghold password from memory, asking for it again before the next command.
+    Lock,
+    /// Generate a new address, printed as a QR code with `--qr`: `new-address [--qr] [--png address.png]`
+    NewAddress {
+        #[clap(flatten)]
+        qr: AddressQrParameters,
     },
-    /// Generate a new address.
-    NewAddress,
-    /// Display an output.
+    /// Display an output with its unlock conditions, features and state: `output 0x...`
     Output { output_id: String },
-    /// List all outputs.
+    /// List all outputs, spent ones included, with their kind, amount, native tokens and state.
     Outputs,
+    /// Stake for an event, the amount, the whole balance by default, replacing the one staked for other events. With
+    /// `--maintain`, the outputs are tagged again by `sync` when spent: `participate
+    /// 0x8a6e2bd9d9b0a1b2ef1ffd4d1e1f36a2e3a2f1f2b3c4d5e6f708192a3b4c5d6e 1000000 [--maintain]`
+    Participate {
+        event_id: String,
+        amount: Option<u64>,
+        /// Let `sync` tag the outputs again without asking once the voting output is spent, staking the amount again.
+        #[clap(long)]
+        maintain: bool,
+    },
+    /// Vote in the ballots and stake for the events of the participation plugin of the node: `participation events`,
+    /// `participation status`, `participation vote 0x... 1 2`, `participation stop-vote 0x...`, `participation
+    /// increase-voting-power 1000000`
+    #[clap(subcommand)]
+    Participation(ParticipationCommand),
+    /// List the transactions that aren't included yet, with their age and what the node advises for their block.
+    Pending,
+    /// Prepare sending an amount to a bech32 encoded address without signing it, writing the unsigned transaction to
+    /// a file to `sign` on an offline wallet: `prepare-send
+    /// rms1qztwng6cty8cfm42nzvq099ev7udhrnk0rw8jt8vttf9kpqnxhpsx869vr3 1000000 --out tx.json`
+    PrepareSend {
+        address: String,
+        amount: u64,
+        #[clap(long)]
+        out: String,
+    },
+    /// Promote the block of a pending transaction, for a milestone to reference it sooner: `promote 0x...`
+    Promote { transaction_id: String },
+    /// Reattach a pending transaction in a new block, once its block is too old to be referenced: `reattach 0x...`
+    Reattach { transaction_id: String },
+    /// Write the accounting report of a year, quarter or month, with the value of the base coins moved at the price of
+    /// their day: `report --period 2023-Q1 --format csv --out report.csv`
+    Report {
+        /// `YYYY`, `YYYY-QN` or `YYYY-MM`.
+        #[clap(long)]
+        period: String,
+        #[clap(long, arg_enum, default_value = "pdf-friendly")]
+        format: ReportFormat,
+        /// Fiat currency of the values, the one set with `set-price-source` by default.
+        #[clap(long)]
+        currency: Option<String>,
+        /// File to write the report to instead of printing it.
+        #[clap(long)]
+        out: Option<String>,
+    },
+    /// Continue the `vest` or `claim` interrupted by Ctrl-C, a termination or a failure.
+    Resume,
+    /// Promote or reattach the block of a pending transaction, as the node advises: `retry 0x... [--wait]`
+    Retry {
+        transaction_id: String,
+        #[clap(flatten)]
+        wait: WaitParameters,
+    },
+    /// Run a Rhai script using the account: `script consolidate.rhai`
+    Script { path: String },
     /// Send an amount to a bech32 encoded address: `send
-    /// rms1qztwng6cty8cfm42nzvq099ev7udhrnk0rw8jt8vttf9kpqnxhpsx869vr3 1000000`
-    Send { address: String, amount: u64 },
+    /// rms1qztwng6cty8cfm42nzvq099ev7udhrnk0rw8jt8vttf9kpqnxhpsx869vr3 1000000`, or to the one on the clipboard or in
+    /// a QR code: `send --from-clipboard 1000000`, `send --qr-image qr.png 1000000`
+    #[clap(allow_missing_positional = true)]
+    Send {
+        #[clap(required_unless_present_any = &["from-clipboard", "qr-image"])]
+        address: Option<String>,
+        amount: u64,
+        /// Read the address from the system clipboard.
+        #[clap(long, conflicts_with = "address")]
+        from_clipboard: bool,
+        /// Read the address, or a payment URI, from the QR code of an image file.
+        #[clap(long, conflicts_with_all = &["address", "from-clipboard"])]
+        qr_image: Option<String>,
+        #[clap(flatten)]
+        options: SendOptions,
+        /// Print the selected inputs, the outputs with their storage deposit and the remainder, without signing or
+        /// sending anything.
+        #[clap(long, conflicts_with = "wait")]
+        dry_run: bool,
+        #[clap(flatten)]
+        wait: WaitParameters,
+    },
+    /// Move the whole balance of the account to an address, with its native tokens and NFTs if `--include-assets` is
+    /// given: `send-all rms1qztwng6cty8cfm42nzvq099ev7udhrnk0rw8jt8vttf9kpqnxhpsx869vr3 [--include-assets]`
+    SendAll {
+        address: String,
+        /// Also move the outputs holding native tokens and the NFTs.
+        #[clap(long)]
+        include_assets: bool,
+        /// Only print what would be moved.
+        #[clap(long)]
+        dry_run: bool,
+        /// Send without asking for confirmation.
+        #[clap(long, conflicts_with = "dry-run")]
+        yes: bool,
+    },
+    /// Send to the recipients of a CSV file of `address,amount[,token_id]` rows, or of a JSON array of `{"address",
+    /// "amount", "tokenId"}` objects, in a single transaction: `send-batch payouts.csv [--yes]`
+    SendBatch {
+        path: String,
+        /// Send without asking for confirmation.
+        #[clap(long)]
+        yes: bool,
+    },
     /// Send an amount below the storage deposit minimum to a bech32 address: `send
     /// rms1qztwng6cty8cfm42nzvq099ev7udhrnk0rw8jt8vttf9kpqnxhpsx869vr3 1`
-    SendMicro { address: String, amount: u64 },
+    SendMicro {
+        address: String,
+        amount: u64,
+        #[clap(flatten)]
+        options: SendOptions,
+        /// Print the selected inputs, the outputs with their storage deposit and the remainder, without signing or
+        /// sending anything.
+        #[clap(long, conflicts_with = "wait")]
+        dry_run: bool,
+        #[clap(flatten)]
+        wait: WaitParameters,
+    },
     /// Send native tokens to a bech32 address: `send-native-token
     /// rms1qztwng6cty8cfm42nzvq099ev7udhrnk0rw8jt8vttf9kpqnxhpsx869vr3
     /// 0x08e3a2f76cc934bc0cc21575b4610c1d7d4eb589ae0100000000000000000000000000000000 10`
@@ -114,27 +427,339 @@ pub enum AccountCommand {
         token_id: String,
         amount: String,
         gift_storage_deposit: Option<bool>,
+        /// Print the selected inputs, the outputs with their storage deposit and the remainder, without signing or
+        /// sending anything.
+        #[clap(long, conflicts_with = "wait")]
+        dry_run: bool,
+        #[clap(flatten)]
+        wait: WaitParameters,
+    },
+    /// Send an NFT to a bech32 encoded address, or to the one on the clipboard: `send-nft [--from-clipboard] [address]
+    /// 0x...`
+    #[clap(allow_missing_positional = true)]
+    SendNft {
+        #[clap(required_unless_present = "from-clipboard")]
+        address: Option<String>,
+        nft_id: String,
+        /// Read the address from the system clipboard.
+        #[clap(long, conflicts_with = "address")]
+        from_clipboard: bool,
+        /// Print the selected inputs, the outputs with their storage deposit and the remainder, without signing or
+        /// sending anything.
+        #[clap(long, conflicts_with = "wait")]
+        dry_run: bool,
+        #[clap(flatten)]
+        wait: WaitParameters,
+    },
+    /// Rename the account, to an alias no other account has: `set-alias savings`
+    SetAlias { alias: String },
+    /// Sign a transaction written by `prepare-send`, writing the signed transaction to a file to `submit` on the
+    /// online wallet: `sign tx.json --out signed.json`
+    Sign {
+        path: String,
+        #[clap(long)]
+        out: String,
+    },
+    /// Broadcast a transaction signed by `sign`: `submit signed.json`
+    Submit {
+        path: String,
+        #[clap(flatten)]
+        wait: WaitParameters,
     },
-    /// Send an NFT to a bech32 encoded address
-    SendNft { address: String, nft_id: String },
+    /// Continue in the prompt of another account, by alias or index, or the one picked from the list of accounts
+    /// without: `switch savings`, `switch 1`, `switch`
+    Switch { account: Option<String> },
     /// Sync the account with the Tangle.
     Sync,
-    /// List the account transactions.
-    Transactions,
+    /// Show the details of a transaction, its inputs and outputs decoded: `transaction 0x...`
+    Transaction { transaction_id: String },
+    /// List the account transactions, or browse them in an interactive table: `transactions [--tui]`
+    Transactions {
+        /// Open a scrollable, sortable and filterable table, showing the details of a transaction on enter.
+        #[clap(long)]
+        tui: bool,
+    },
     /// List the unspent outputs.
     UnspentOutputs,
+    /// Check that an address belongs to the account, before sending a large amount to it, deriving the addresses
+    /// following the generated ones if needed: `verify-address
+    /// rms1qztwng6cty8cfm42nzvq099ev7udhrnk0rw8jt8vttf9kpqnxhpsx869vr3 [--gap 20]`
+    VerifyAddress {
+        address: String,
+        /// Number of public and change addresses to derive past the generated ones.
+        #[clap(long, default_value = "20")]
+        gap: u32,
+    },
+    /// Vest a total amount to a bech32 encoded address in timelocked installments, the first one unlocking after an
+    /// interval: `vest rms1qztwng6cty8cfm42nzvq099ev7udhrnk0rw8jt8vttf9kpqnxhpsx869vr3 12000000 --installments 12
+    /// --interval 30d`
+    Vest {
+        address: String,
+        total: u64,
+        /// Number of installments.
+        #[clap(long, default_value = "12")]
+        installments: u32,
+        /// Time between installments, in seconds, minutes, hours, days or weeks: `90s`, `30m`, `12h`, `30d`, `2w`.
+        #[clap(long, default_value = "30d")]
+        interval: String,
+    },
+    /// Show the vesting schedules received by the account: `vesting status`
+    #[clap(subcommand)]
+    Vesting(VestingCommand),
+    /// Change the voting power, the amount of the voting output: `voting-power increase 1000000`, `voting-power
+    /// decrease 1000000`
+    #[clap(subcommand)]
+    VotingPower(VotingPowerCommand),
+    /// Explain why a transaction conflicted or was never included: `why-failed
+    /// 0x84fe6b1796bddc022c9bc40206f0a692f4536b02aa8c13140264e2e01a3b7e4b`
+    WhyFailed { transaction_id: String },
 }
 
-/// `addresses` command
-pub async fn addresses_command(account_handle: &AccountHandle) -> Result<(), Error> {
+impl AccountCommand {
+    /// Name of the command, as typed, e.g. `send-native-token`.
+    pub fn name(&self) -> String {
+        // Variants are named after their commands, in kebab-case.
+        let debug = format!("{self:?}");
+        let variant = debug
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .next()
+            .unwrap_or_default();
+        let mut name = String::new();
+
+        for (index, c) in variant.char_indices() {
+            if index > 0 && c.is_ascii_uppercase() {
+                name.push('-');
+            }
+            name.push(c.to_ascii_lowercase());
+        }
+
+        name
+    }
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum DepositCommand {
+    /// Assign an unused address of the pool to an order, the same one if the order already has one.
+    Assign { order_id: String },
+    /// List the orders with their address and the amount they received, or only the given one.
+    Status { order_id: Option<String> },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum InvoiceCommand {
+    /// Create an invoice of an amount, paid on a new address of the account unless one is given.
+    Create {
+        amount: u64,
+        /// Bech32 encoded address of the account to be paid on.
+        #[clap(long)]
+        address: Option<String>,
+        /// Time after which the invoice expires if not paid: `30m`, `12h`, `7d`.
+        #[clap(long)]
+        expires: Option<String>,
+    },
+    /// List the invoices.
+    List,
+    /// Show an invoice.
+    Status { id: u32 },
+}
+
+/// Output of the commands supporting JSON, human-readable text by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum OutputFormat {
+    Text,
+    /// JSON printed on stdout, without the log prefix, to be parsed by scripts.
+    Json,
+}
+
+/// Unlock conditions and features of the output sent by `send` and `send-micro`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Args)]
+pub struct SendOptions {
+    /// Let the output return to the sender if the recipient doesn't claim it within this duration, e.g. `2h` or `1d`.
+    #[clap(long)]
+    pub expiration: Option<String>,
+    /// Lock the output until a date, `YYYY-MM-DD` at midnight UTC, a Unix timestamp or after a duration, e.g. `30d`.
+    #[clap(long)]
+    pub timelock: Option<String>,
+    /// Address the expired output and the storage deposit of `send-micro` return to, the first one of the account by
+    /// default.
+    #[clap(long)]
+    pub return_address: Option<String>,
+    /// Tag the output, UTF-8 text or `0x` prefixed hex.
+    #[clap(long)]
+    pub tag: Option<String>,
+    /// Attach metadata to the output, `0x` prefixed hex.
+    #[clap(long)]
+    pub metadata: Option<String>,
+}
+
+/// Sharing an address as a QR code, to be scanned instead of copied.
+#[derive(Debug, Clone, Default, Args)]
+pub struct AddressQrParameters {
+    /// Print the address as a QR code in the terminal.
+    #[clap(long)]
+    pub qr: bool,
+    /// Write the address as a QR code to a PNG image.
+    #[clap(long)]
+    pub png: Option<String>,
+}
+
+/// Waiting for a sent transaction to be included, e.g. by scripts relying on its outputs.
+#[derive(Debug, Clone, Default, Args)]
+pub struct WaitParameters {
+    /// Wait until the transaction is included, promoting or reattaching its block if needed, and fail if it conflicts.
+    #[clap(long)]
+    pub wait: bool,
+    /// Fail if the transaction isn't included within this duration, e.g. `90s` or `10m`, 5 minutes by default.
+    #[clap(long, requires = "wait")]
+    pub timeout: Option<String>,
+}
+
+/// Layout of an accounting report.
+#[derive(Debug, Clone, Copy, ArgEnum)]
+pub enum ReportFormat {
+    /// A line per asset moved by each transaction, then the totals of the period.
+    Csv,
+    /// A printable summary followed by the transactions.
+    PdfFriendly,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum VestingCommand {
+    /// List the timelocked outputs of the account by sender, with their unlock dates and what already vested.
+    Status,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum VotingPowerCommand {
+    /// Decrease the voting power, dissolving the voting output when it drops to zero.
+    Decrease { amount: u64 },
+    /// Increase the voting power, creating the voting output if needed.
+    Increase { amount: u64 },
+}
+
+/// `address` command
+pub async fn address_command(account_handle: &AccountHandle, index: u32, qr: AddressQrParameters) -> Result<(), Error> {
     let addresses = account_handle.addresses().await?;
+    let address = addresses
+        .iter()
+        .find(|address| *address.key_index() == index && !address.internal())
+        .ok_or_else(|| {
+            Error::Miscellaneous(format!(
+                "the account has no address {index}, generate it with `new-address`"
+            ))
+        })?;
 
-    if addresses.is_empty() {
+    print_address(account_handle, address, qr).await
+}
+
+/// `addresses` command
+pub async fn addresses_command(
+    account_handle: &AccountHandle,
+    verbose: bool,
+    unspent: bool,
+    used: bool,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    let mut addresses = account_handle.addresses().await?;
+
+    if unspent {
+        let addresses_with_unspent_outputs = account_handle.addresses_with_unspent_outputs().await?;
+
+        addresses.retain(|address| {
+            addresses_with_unspent_outputs.iter().any(|with_outputs| {
+                with_outputs.address().as_ref() == address.address().as_ref() && !with_outputs.output_ids().is_empty()
+            })
+        });
+    }
+    if used {
+        addresses.retain(|address| *address.used());
+    }
+
+    if output == OutputFormat::Json {
+        let addresses_with_unspent_outputs = account_handle.addresses_with_unspent_outputs().await?;
+        let aliases = owned_aliases(account_handle).await?;
+        let mut balances = HashMap::new();
+
+        for output_data in account_handle.unspent_outputs(None).await? {
+            *balances.entry(output_data.address).or_insert(0) += output_data.output.amount();
+        }
+
+        let addresses = addresses
+            .iter()
+            .map(|address| {
+                let output_ids = addresses_with_unspent_outputs
+                    .iter()
+                    .find(|with_outputs| with_outputs.address().as_ref() == address.address().as_ref())
+                    .map(|with_outputs| {
+                        with_outputs
+                            .output_ids()
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+
+                serde_json::json!({
+                    "address": address.address().to_bech32(),
+                    "keyIndex": address.key_index(),
+                    "internal": address.internal(),
+                    "used": address.used(),
+                    "balance": balances.get(address.address().as_ref()).copied().unwrap_or_default(),
+                    "outputIds": output_ids,
+                    "aliases": aliases
+                        .iter()
+                        .filter(|(_, controller)| controller == address.address().as_ref())
+                        .map(|(alias_id, _)| alias_id.to_string())
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        print_json(&serde_json::Value::from(addresses))?;
+    } else if addresses.is_empty() {
         log::info!("No addresses found");
+    } else if verbose {
+        // Fetched once for all the addresses rather than for each of them.
+        let addresses_with_unspent_outputs = account_handle.addresses_with_unspent_outputs().await?;
+
+        let log = addresses
+            .iter()
+            .map(|address| format_address(address, &addresses_with_unspent_outputs))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        print_paged(&log);
     } else {
+        let coin_type = *account_handle.read().await.coin_type();
+        let aliases = owned_aliases(account_handle).await?;
+        let mut balances = HashMap::new();
+
+        for output_data in account_handle.unspent_outputs(None).await? {
+            *balances.entry(output_data.address).or_insert(0) += output_data.output.amount();
+        }
+
+        let mut log = String::new();
+
         for address in addresses {
-            print_address(account_handle, &address).await?;
+            let balance = balances.get(address.address().as_ref()).copied().unwrap_or_default();
+
+            log = format!(
+                "{log}\n{:>4}{} {} {}",
+                address.key_index(),
+                if *address.internal() { "*" } else { " " },
+                address.address().to_bech32(),
+                format_amount(balance, coin_type)
+            );
+
+            for (alias_id, _) in aliases
+                .iter()
+                .filter(|(_, controller)| controller == address.address().as_ref())
+            {
+                log = format!("{log}\n      controls alias {alias_id}");
+            }
         }
+
+        print_paged(&format!("Addresses, change addresses marked with *:{log}"));
     }
 
     Ok(())
@@ -181,14 +806,285 @@ pub async fn burn_nft_command(account_handle: &AccountHandle, nft_id: String) ->
 }
 
 // `balance` command
-pub async fn balance_command(account_handle: &AccountHandle) -> Result<(), Error> {
-    log::info!("{:?}", account_handle.balance().await?);
+pub async fn balance_command(account_handle: &AccountHandle, output: OutputFormat) -> Result<(), Error> {
+    let balance = account_handle.balance().await?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs()) as u32;
+    let (mut timelocked, mut expiring, mut storage_deposit_returns, mut pending) = (0, 0, 0, 0);
+    let coin_type;
+
+    {
+        let account = account_handle.read().await;
+        coin_type = *account.coin_type();
+        let addresses = account
+            .public_addresses()
+            .iter()
+            .chain(account.internal_addresses())
+            .map(|address| *address.address().as_ref())
+            .collect::<HashSet<_>>();
+
+        for output_data in account.unspent_outputs().values() {
+            let amount = output_data.output.amount();
+            let unlock_conditions = output_data.output.unlock_conditions();
+
+            // Each output is only counted once, in the first matching category.
+            if account.locked_outputs().contains(&output_data.output_id) {
+                pending += amount;
+            } else if unlock_conditions
+                .and_then(|unlock_conditions| unlock_conditions.timelock())
+                .is_some_and(|timelock| timelock.timestamp() > now)
+            {
+                timelocked += amount;
+            } else if unlock_conditions
+                .and_then(|unlock_conditions| unlock_conditions.expiration())
+                .is_some_and(|expiration| {
+                    expiration.timestamp() > now && addresses.contains(expiration.return_address())
+                })
+            {
+                // Sent by the account and not yet claimed by the receiver.
+                expiring += amount;
+            } else if let Some(storage_deposit_return) = unlock_conditions
+                .and_then(|unlock_conditions| unlock_conditions.storage_deposit_return())
+                .filter(|storage_deposit_return| !addresses.contains(storage_deposit_return.return_address()))
+            {
+                storage_deposit_returns += storage_deposit_return.amount();
+            }
+        }
+    }
+
+    let irc30 = native_tokens_irc30(
+        account_handle,
+        balance.native_tokens.iter().map(|native_token| native_token.token_id),
+    )
+    .await?;
+
+    if output == OutputFormat::Json {
+        return print_json(&serde_json::json!({
+            "baseCoin": {
+                "total": balance.base_coin.total,
+                "available": balance.base_coin.available,
+                "timelocked": timelocked,
+                "expiring": expiring,
+                "storageDepositReturns": storage_deposit_returns,
+                "pending": pending,
+            },
+            "requiredStorageDeposit": balance.required_storage_deposit,
+            "nativeTokens": balance
+                .native_tokens
+                .iter()
+                .map(|native_token| {
+                    let irc30 = irc30.get(&native_token.token_id);
+
+                    serde_json::json!({
+                        "tokenId": native_token.token_id.to_string(),
+                        "total": native_token.total.to_string(),
+                        "available": native_token.available.to_string(),
+                        "name": irc30.map(|irc30| &irc30.name),
+                        "symbol": irc30.map(|irc30| &irc30.symbol),
+                        "decimals": irc30.map(|irc30| irc30.decimals),
+                    })
+                })
+                .collect::<Vec<_>>(),
+            "nfts": balance.nfts.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "aliases": balance.aliases.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "foundries": balance.foundries.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        }));
+    }
+
+    let amount = |amount: u64| format_amount(amount, coin_type);
+    let mut log = format!(
+        "Base coin:\n  {:<34}{}\n  {:<34}{}\n  {:<34}{}\n  {:<34}{}\n  {:<34}{}\n  {:<34}{}\n  {:<34}{}",
+        "Total:",
+        amount(balance.base_coin.total),
+        "Available:",
+        amount(balance.base_coin.available),
+        "Locked by timelocks:",
+        amount(timelocked),
+        "Locked by expirations:",
+        amount(expiring),
+        "Owed as storage deposit returns:",
+        amount(storage_deposit_returns),
+        "Used by pending transactions:",
+        amount(pending),
+        "Required storage deposit:",
+        amount(balance.required_storage_deposit)
+    );
+
+    if !balance.native_tokens.is_empty() {
+        log = format!(
+            "{log}\nNative tokens:\n  {:<78} {:>20} {:>20}  Name",
+            "Token ID", "Total", "Available"
+        );
+
+        for native_token in &balance.native_tokens {
+            // Without IRC-30 metadata, the amounts are left in the smallest unit of the token.
+            log = match irc30.get(&native_token.token_id) {
+                Some(irc30) => format!(
+                    "{log}\n  {:<78} {:>20} {:>20}  {}",
+                    native_token.token_id.to_string(),
+                    format_token_amount(native_token.total, irc30),
+                    format_token_amount(native_token.available, irc30),
+                    irc30.name
+                ),
+                None => format!(
+                    "{log}\n  {:<78} {:>20} {:>20}  -",
+                    native_token.token_id.to_string(),
+                    native_token.total.to_string(),
+                    native_token.available.to_string()
+                ),
+            };
+        }
+    }
+
+    log = format!(
+        "{log}\nNFTs: {}\nAliases: {}",
+        balance.nfts.len(),
+        balance.aliases.len()
+    );
+
+    for alias_id in &balance.aliases {
+        log = format!("{log}\n  {alias_id}");
+    }
+
+    log = format!("{log}\nFoundries: {}", balance.foundries.len());
+
+    for foundry_id in &balance.foundries {
+        log = format!(
+            "{log}\n  {foundry_id} controlled by alias {}",
+            foundry_id.alias_address().alias_id()
+        );
+    }
+
+    log::info!("{log}");
+
+    print_expiration_warnings(account_handle).await?;
+
+    Ok(())
+}
+
+// IRC-30 metadata of native tokens, from the foundries of the account or else from the node, leaving out the tokens
+// without any and the ones whose foundry can't be fetched.
+async fn native_tokens_irc30(
+    account_handle: &AccountHandle,
+    token_ids: impl Iterator<Item = TokenId>,
+) -> Result<HashMap<TokenId, Irc30>, Error> {
+    let unspent_outputs = account_handle.unspent_outputs(None).await?;
+    let mut metadata = HashMap::new();
+
+    for token_id in token_ids {
+        let owned_foundry = unspent_outputs.iter().find(|output_data| {
+            matches!(&output_data.output, Output::Foundry(foundry) if TokenId::new(*foundry.id()) == token_id)
+        });
+        let foundry = match owned_foundry {
+            Some(output_data) => Some(output_data.output.clone()),
+            None => timed(Phase::Node, async {
+                let client = account_handle.client();
+                let output_id = client.foundry_output_id(FoundryId::from(token_id)).await?;
+                let response = client.get_output(&output_id).await?;
+
+                Output::try_from_dto_unverified(&response.output).map_err(|e| Error::Miscellaneous(format!("{e:?}")))
+            })
+            .await
+            .ok(),
+        };
+
+        if let Some(irc30) = foundry.as_ref().and_then(foundry_irc30) {
+            metadata.insert(token_id, irc30);
+        }
+    }
+
+    Ok(metadata)
+}
+
+// `balance-history` command
+pub async fn balance_history_command(
+    account_handle: &AccountHandle,
+    since: Option<String>,
+    chart: bool,
+) -> Result<(), Error> {
+    let since = since.as_deref().map(parse_date).transpose()?.unwrap_or_default();
+    let history = balance_history(*account_handle.read().await.index())?;
+
+    if chart {
+        let points = history
+            .iter()
+            .filter(|(timestamp, _, _)| *timestamp >= since)
+            .map(|(timestamp, total, _)| (*timestamp, *total))
+            .collect::<Vec<_>>();
+
+        if points.len() < 2 {
+            log::info!("Not enough balance records to draw a chart, the balance is recorded at each sync.");
+        } else {
+            log::info!("Balance history:\n{}", render_chart(&points));
+        }
+
+        return Ok(());
+    }
+
+    let outputs = account_handle.outputs(None).await?;
+    let mut previous: Option<(u64, u64, u64)> = None;
+    let mut log = String::new();
+
+    for (timestamp, total, available) in history {
+        // Only changes are shown, the sync records in between are left out.
+        if previous.is_some_and(|(_, previous_total, _)| previous_total == total) {
+            continue;
+        }
+
+        if timestamp >= since {
+            let delta = match previous {
+                Some((_, previous_total, _)) if total >= previous_total => format!(" (+{})", total - previous_total),
+                Some((_, previous_total, _)) => format!(" (-{})", previous_total - total),
+                None => String::new(),
+            };
+            log = format!(
+                "{log}\n{}: total {total}{delta}, available {available}",
+                format_timestamp(timestamp)
+            );
+
+            if let Some((previous_timestamp, _, _)) = previous {
+                let in_window = |milestone_timestamp: u32| {
+                    (previous_timestamp + 1..=timestamp).contains(&milestone_timestamp.into())
+                };
+                // Transactions that created or spent outputs of the account between the two records.
+                let mut transaction_ids = outputs
+                    .iter()
+                    .flat_map(|output_data| {
+                        let metadata = &output_data.metadata;
+                        let created =
+                            in_window(metadata.milestone_timestamp_booked).then(|| metadata.transaction_id.clone());
+                        let spent = metadata
+                            .milestone_timestamp_spent
+                            .filter(|milestone_timestamp| in_window(*milestone_timestamp))
+                            .and(metadata.transaction_id_spent.clone());
+
+                        created.into_iter().chain(spent)
+                    })
+                    .collect::<Vec<_>>();
+                transaction_ids.sort();
+                transaction_ids.dedup();
+
+                for transaction_id in transaction_ids {
+                    log = format!("{log}\n  transaction {transaction_id}");
+                }
+            }
+        }
+
+        previous = Some((timestamp, total, available));
+    }
+
+    if log.is_empty() {
+        log::info!("No balance change recorded, the balance is recorded at each sync.");
+    } else {
+        log::info!("Balance history:{log}");
+    }
 
     Ok(())
 }
 
 // `claim` command
-pub async fn claim_command(account_handle: &AccountHandle, output_id: Option<String>) -> Result<(), Error> {
+pub async fn claim_command(account_handle: &AccountHandle, output_id: Option<String>, all: bool) -> Result<(), Error> {
     if let Some(output_id) = output_id {
         log::info!("Claiming output {output_id}");
 
@@ -201,28 +1097,199 @@ pub async fn claim_command(account_handle: &AccountHandle, output_id: Option<Str
             transaction.transaction_id,
             transaction.block_id
         );
+    } else if all {
+        check_no_job(*account_handle.read().await.index())?;
+        claim_all(account_handle).await?;
     } else {
-        log::info!("Claiming outputs.");
+        list_claimable_outputs(account_handle).await?;
+    };
 
-        let output_ids = account_handle
-            .get_unlockable_outputs_with_additional_unlock_conditions(OutputsToClaim::All)
-            .await?;
+    Ok(())
+}
 
-        if output_ids.is_empty() {
-            log::info!("No outputs available to claim.");
+// Lists the outputs with additional unlock conditions, with what they hold back and when they become claimable.
+async fn list_claimable_outputs(account_handle: &AccountHandle) -> Result<(), Error> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs()) as u32;
+    let claimable = account_handle
+        .get_unlockable_outputs_with_additional_unlock_conditions(OutputsToClaim::All)
+        .await?;
+    let addresses = account_handle
+        .addresses()
+        .await?
+        .iter()
+        .map(|address| *address.address().as_ref())
+        .collect::<HashSet<_>>();
+    let coin_type = *account_handle.read().await.coin_type();
+    let mut listed = 0;
+
+    for output_data in account_handle.unspent_outputs(None).await? {
+        let unlock_conditions = match output_data.output.unlock_conditions() {
+            Some(unlock_conditions) if unlock_conditions.len() > 1 => unlock_conditions,
+            _ => continue,
+        };
+        let timelock = unlock_conditions.timelock().map(|timelock| timelock.timestamp());
+        let expiration = unlock_conditions.expiration();
+        let state = if claimable.contains(&output_data.output_id) {
+            "claimable now".to_string()
+        } else if let Some(timelock) = timelock.filter(|timelock| *timelock > now) {
+            format!("claimable from {}", format_timestamp(timelock.into()))
+        } else if let Some(expiration) = expiration
+            .filter(|expiration| expiration.timestamp() > now && addresses.contains(expiration.return_address()))
+        {
+            // Sent by the account, it comes back unless its receiver claims it first.
+            format!(
+                "claimable from {} unless claimed by its receiver",
+                format_timestamp(expiration.timestamp().into())
+            )
+        } else {
+            // Expired, back to its sender.
+            continue;
+        };
+        let mut conditions = Vec::new();
+
+        if let Some(storage_deposit_return) = unlock_conditions.storage_deposit_return() {
+            conditions.push(format!(
+                "returns a storage deposit of {}",
+                format_amount(storage_deposit_return.amount(), coin_type)
+            ));
+        }
+        if let Some(expiration) = expiration.filter(|expiration| !addresses.contains(expiration.return_address())) {
+            conditions.push(format!(
+                "returns to its sender on {} unless claimed",
+                format_timestamp(expiration.timestamp().into())
+            ));
         }
 
-        // Doing chunks of only 60, because we might need to create the double amount of outputs, because of potential
-        // storage deposit return unlock conditions and also consider the remainder output.
-        for output_ids_chunk in output_ids.chunks(60) {
-            let transaction = account_handle.claim_outputs(output_ids_chunk.to_vec()).await?;
-            log::info!(
-                "Claiming transaction sent:\ntransaction id: {}\n{:?}",
-                transaction.transaction_id,
-                transaction.block_id
-            );
+        listed += 1;
+        log::info!(
+            "Output {}: {}, {state}{}",
+            output_data.output_id,
+            format_amount(output_data.output.amount(), coin_type),
+            conditions
+                .iter()
+                .map(|condition| format!(", {condition}"))
+                .collect::<String>()
+        );
+    }
+
+    if listed == 0 {
+        log::info!("No outputs to claim.");
+    } else if !claimable.is_empty() {
+        log::info!(
+            "{} output(s) claimable now, use `claim <output id>` or `claim --all`.",
+            claimable.len()
+        );
+    }
+
+    Ok(())
+}
+
+// Claims all the claimable outputs, recording it to resume it if interrupted.
+async fn claim_all(account_handle: &AccountHandle) -> Result<(), Error> {
+    let account_index = *account_handle.read().await.index();
+
+    log::info!("Claiming outputs.");
+
+    let output_ids = account_handle
+        .get_unlockable_outputs_with_additional_unlock_conditions(OutputsToClaim::All)
+        .await?;
+
+    if output_ids.is_empty() {
+        log::info!("No outputs available to claim.");
+    } else {
+        set_job(account_index, Some(&Job::Claim))?;
+    }
+
+    // Doing chunks of only 60, because we might need to create the double amount of outputs, because of potential
+    // storage deposit return unlock conditions and also consider the remainder output.
+    for output_ids_chunk in output_ids.chunks(60) {
+        if interrupted() {
+            log::warn!("Claiming interrupted, run `resume` to continue.");
+            return Ok(());
         }
+
+        let transaction = account_handle.claim_outputs(output_ids_chunk.to_vec()).await?;
+        log::info!(
+            "Claiming transaction sent:\ntransaction id: {}\n{:?}",
+            transaction.transaction_id,
+            transaction.block_id
+        );
+    }
+
+    set_job(account_index, None)
+}
+
+// `claim-l2` command
+pub async fn claim_l2_command(account_handle: &AccountHandle) -> Result<(), Error> {
+    let (bech32_hrp, coin_type) = {
+        let account = account_handle.read().await;
+        (
+            account
+                .public_addresses()
+                .first()
+                .map(|address| address.address().bech32_hrp().to_string())
+                .unwrap_or_default(),
+            *account.coin_type(),
+        )
     };
+    let claimable = account_handle
+        .get_unlockable_outputs_with_additional_unlock_conditions(OutputsToClaim::All)
+        .await?;
+    let mut to_claim = Vec::new();
+
+    // Chains are alias addresses, their outputs are the ones with an alias as sender.
+    for output_data in account_handle.unspent_outputs(None).await? {
+        let features = match output_data.output.features() {
+            Some(features) => features,
+            None => continue,
+        };
+        let chain = match features.sender().map(|sender| sender.address()) {
+            Some(chain @ Address::Alias(_)) => chain.to_bech32(&bech32_hrp),
+            _ => continue,
+        };
+        let request = features.metadata().map(|metadata| {
+            isc::describe_request(metadata.data()).unwrap_or_else(|| prefix_hex::encode(metadata.data()))
+        });
+        let native_tokens = output_data
+            .output
+            .native_tokens()
+            .map_or(0, |native_tokens| native_tokens.len());
+        let state = if claimable.contains(&output_data.output_id) {
+            to_claim.push(output_data.output_id);
+            "to claim"
+        } else if output_data
+            .output
+            .unlock_conditions()
+            .is_some_and(|unlock_conditions| unlock_conditions.len() > 1)
+        {
+            "not claimable yet"
+        } else {
+            "credited"
+        };
+
+        log::info!(
+            "Output {} from chain {chain}: {}, {native_tokens} native token(s), {state}\nRequest: {}",
+            output_data.output_id,
+            format_amount(output_data.output.amount(), coin_type),
+            request.unwrap_or_else(|| "none".to_string())
+        );
+    }
+
+    if to_claim.is_empty() {
+        log::info!("No outputs from chains to claim.");
+    }
+
+    // Same chunks as the `claim` command.
+    for output_ids_chunk in to_claim.chunks(60) {
+        let transaction = account_handle.claim_outputs(output_ids_chunk.to_vec()).await?;
+        log::info!(
+            "Claiming transaction sent:\ntransaction id: {}\n{:?}",
+            transaction.transaction_id,
+            transaction.block_id
+        );
+    }
 
     Ok(())
 }
@@ -254,9 +1321,30 @@ pub async fn create_alias_outputs_command(account_handle: &AccountHandle) -> Res
         transaction.block_id
     );
 
+    // A new alias gets its id from the id of its first output.
+    let TransactionEssence::Regular(essence) = transaction.payload.essence();
+
+    for (index, output) in essence.outputs().iter().enumerate() {
+        if let Output::Alias(alias) = output {
+            if alias.alias_id().is_null() {
+                log::info!(
+                    "Alias id: {}, to use with `mint-native-token --alias` once confirmed.",
+                    alias
+                        .alias_id()
+                        .or_from_output_id(OutputId::new(transaction.transaction_id, index as u16)?)
+                );
+            }
+        }
+    }
+
     Ok(())
 }
 
+// `dashboard` command
+pub async fn dashboard_command(account_handle: &AccountHandle, interval: u64) -> Result<(), Error> {
+    dashboard(account_handle, Duration::from_secs(interval.max(1))).await
+}
+
 // `decrease-native-token-supply` command
 pub async fn decrease_native_token_command(
     account_handle: &AccountHandle,
@@ -280,19 +1368,146 @@ pub async fn decrease_native_token_command(
     Ok(())
 }
 
-// `destroy-alias` command
-pub async fn destroy_alias_command(account_handle: &AccountHandle, alias_id: String) -> Result<(), Error> {
-    log::info!("Destroying alias {alias_id}.");
+/// `deposit` command
+pub async fn deposit_command(account_handle: &AccountHandle, command: DepositCommand) -> Result<(), Error> {
+    match command {
+        DepositCommand::Assign { order_id } => {
+            let (order, new) = assign_address(account_handle, &order_id).await?;
 
-    let transaction = account_handle
-        .destroy_alias(AliasId::from_str(&alias_id)?, None)
-        .await?;
+            if new {
+                log::info!(
+                    "Order {order_id} assigned address {}: {}",
+                    order.key_index,
+                    order.address
+                );
+            } else {
+                log::info!(
+                    "Order {order_id} was already assigned address {} on {}: {}",
+                    order.key_index,
+                    format_timestamp(order.assigned_at),
+                    order.address
+                );
+            }
+        }
+        DepositCommand::Status { order_id } => {
+            let coin_type = *account_handle.read().await.coin_type();
+            let (pool, mut orders) = received_by_order(account_handle).await?;
 
-    log::info!(
-        "Destroying alias transaction sent:\ntransaction id: {}\n{:?}",
-        transaction.transaction_id,
-        transaction.block_id
-    );
+            if let Some(order_id) = &order_id {
+                orders.retain(|known, _| known == order_id);
+
+                if orders.is_empty() {
+                    return Err(Error::Miscellaneous(format!("no address assigned to order {order_id}")));
+                }
+            }
+
+            let log = orders.iter().fold(
+                format!(
+                    "{:<24}  {:<23}  {:>20}  {}",
+                    "Order", "Assigned on", "Received", "Address"
+                ),
+                |log, (order_id, order)| {
+                    format!(
+                        "{log}\n{order_id:<24}  {:<23}  {:>20}  {}",
+                        format_timestamp(order.assigned_at),
+                        format_amount(order.received, coin_type),
+                        order.address
+                    )
+                },
+            );
+
+            log::info!("{log}\n{pool} unused address(es) left in the pool, run `sync` first to update the amounts.");
+        }
+    }
+
+    Ok(())
+}
+
+// `deposit-l2` command
+pub async fn deposit_l2_command(
+    account_handle: &AccountHandle,
+    chain_address: String,
+    evm_address: String,
+    amount: String,
+    token_id: Option<String>,
+) -> Result<(), Error> {
+    check_network(account_handle, &chain_address).await?;
+
+    let chain_address = match Address::try_from_bech32(&chain_address)?.1 {
+        address @ Address::Alias(_) => address,
+        _ => {
+            return Err(Error::Miscellaneous(format!(
+                "\"{chain_address}\" isn't the alias address of a chain"
+            )));
+        }
+    };
+    let evm_address = prefix_hex::decode::<[u8; 20]>(&evm_address)
+        .map_err(|e| Error::Miscellaneous(format!("invalid EVM address \"{evm_address}\": {e}")))?;
+    let (base_tokens, native_token) = match token_id {
+        Some(token_id) => (
+            0,
+            Some(NativeToken::new(
+                TokenId::from_str(&token_id)?,
+                U256::from_dec_str(&amount).map_err(|e| Error::Miscellaneous(e.to_string()))?,
+            )?),
+        ),
+        None => (
+            amount
+                .parse::<u64>()
+                .map_err(|e| Error::Miscellaneous(format!("invalid amount \"{amount}\": {e}")))?,
+            None,
+        ),
+    };
+    let sender = *account_handle
+        .read()
+        .await
+        .public_addresses()
+        .first()
+        .ok_or_else(|| Error::Miscellaneous("the account has no address".to_string()))?
+        .address()
+        .as_ref();
+    let rent_structure = account_handle.client().get_rent_structure()?;
+    let token_supply = account_handle.client().get_token_supply()?;
+    let builder = BasicOutputBuilder::new_with_minimum_storage_deposit(rent_structure)?
+        .add_unlock_condition(UnlockCondition::Address(AddressUnlockCondition::new(chain_address)))
+        .with_native_tokens(native_token.clone())
+        .add_feature(Feature::Sender(SenderFeature::new(sender)))
+        .add_feature(Feature::Metadata(MetadataFeature::new(isc::deposit_metadata(
+            evm_address,
+            base_tokens,
+            native_token.as_ref(),
+        ))?));
+    let minimum_amount = builder.clone().finish_output(token_supply)?.amount();
+    // The chain takes the gas fee from the tokens of the output that aren't in the allowance, whatever isn't used
+    // staying on the L2 account of the sender address.
+    let output = builder
+        .with_amount((base_tokens + isc::GAS_FEE).max(minimum_amount))?
+        .finish_output(token_supply)?;
+
+    let transaction = account_handle.send(vec![output], None).await?;
+
+    log::info!(
+        "L2 deposit transaction sent:\ntransaction id: {}\n{:?}",
+        transaction.transaction_id,
+        transaction.block_id
+    );
+
+    Ok(())
+}
+
+// `destroy-alias` command
+pub async fn destroy_alias_command(account_handle: &AccountHandle, alias_id: String) -> Result<(), Error> {
+    log::info!("Destroying alias {alias_id}.");
+
+    let transaction = account_handle
+        .destroy_alias(AliasId::from_str(&alias_id)?, None)
+        .await?;
+
+    log::info!(
+        "Destroying alias transaction sent:\ntransaction id: {}\n{:?}",
+        transaction.transaction_id,
+        transaction.block_id
+    );
 
     Ok(())
 }
@@ -314,6 +1529,79 @@ pub async fn destroy_foundry_command(account_handle: &AccountHandle, foundry_id:
     Ok(())
 }
 
+// `export-deposit-addresses` command
+pub async fn export_deposit_addresses_command(
+    account_handle: &AccountHandle,
+    count: u32,
+    out: String,
+) -> Result<(), Error> {
+    if Path::new(&out).exists() {
+        return Err(Error::Miscellaneous(format!(
+            "\"{out}\" already exists, refusing to overwrite it"
+        )));
+    }
+    if count == 0 {
+        return Err(Error::Miscellaneous("at least one address is needed".to_string()));
+    }
+
+    log::info!("Generating {count} addresses.");
+
+    // Generated and stored together, in a single Stronghold session.
+    let addresses = timed(Phase::SecretManager, account_handle.generate_addresses(count, None)).await?;
+
+    // Without the keys, with an external signer, no address is generated.
+    if addresses.is_empty() {
+        return Err(Error::ExternalSignerUnsupported);
+    }
+
+    let content = if out.to_lowercase().ends_with(".json") {
+        serde_json::to_string_pretty(
+            &addresses
+                .iter()
+                .map(|address| {
+                    serde_json::json!({
+                        "index": address.key_index(),
+                        "address": address.address().to_bech32(),
+                    })
+                })
+                .collect::<Vec<_>>(),
+        )?
+    } else {
+        addresses
+            .iter()
+            .fold("index,address\n".to_string(), |content, address| {
+                format!("{content}{},{}\n", address.key_index(), address.address().to_bech32())
+            })
+    };
+
+    std::fs::write(&out, content)?;
+
+    log::info!(
+        "Addresses {} to {} have been exported to \"{out}\".",
+        addresses[0].key_index(),
+        addresses[addresses.len() - 1].key_index()
+    );
+
+    Ok(())
+}
+
+/// `export-transactions` command
+pub async fn export_transactions_command(account_handle: &AccountHandle, path: String) -> Result<(), Error> {
+    if Path::new(&path).exists() {
+        return Err(Error::Miscellaneous(format!(
+            "\"{path}\" already exists, refusing to overwrite it"
+        )));
+    }
+
+    let (csv, count) = transactions_csv(account_handle).await?;
+
+    std::fs::write(&path, csv)?;
+
+    log::info!("{count} transaction(s) have been exported to \"{path}\".");
+
+    Ok(())
+}
+
 // `faucet` command
 pub async fn faucet_command(
     account_handle: &AccountHandle,
@@ -321,6 +1609,7 @@ pub async fn faucet_command(
     address: Option<String>,
 ) -> Result<(), Error> {
     let address = if let Some(address) = address {
+        check_network(account_handle, &address).await?;
         address
     } else {
         match account_handle.addresses().await?.last() {
@@ -328,7 +1617,7 @@ pub async fn faucet_command(
             None => return Err(Error::NoAddressForFaucet),
         }
     };
-    let faucet_url = match &url {
+    let faucet_url = match url.as_ref().or(config().faucet_url.as_ref()) {
         Some(faucet_url) => faucet_url,
         None => "http://localhost:8091/api/enqueue",
     };
@@ -343,51 +1632,692 @@ pub async fn increase_native_token_command(
     account_handle: &AccountHandle,
     token_id: String,
     amount: String,
+    wait: WaitParameters,
+    output: OutputFormat,
 ) -> Result<(), Error> {
+    let token_id = TokenId::from_str(&token_id)?;
+    let amount = U256::from_dec_str(&amount).map_err(|e| Error::Miscellaneous(e.to_string()))?;
+
+    if amount.is_zero() {
+        return Err(Error::Miscellaneous("the amount to mint must be positive".to_string()));
+    }
+
+    // Checked here to tell how much is left to mint, the library only reports that the maximum would be exceeded.
+    let (circulating_supply, maximum_supply) = account_handle
+        .unspent_outputs(None)
+        .await?
+        .iter()
+        .find_map(|output_data| match &output_data.output {
+            Output::Foundry(foundry) if TokenId::new(*foundry.id()) == token_id => {
+                let TokenScheme::Simple(token_scheme) = foundry.token_scheme();
+
+                Some((token_scheme.circulating_supply(), token_scheme.maximum_supply()))
+            }
+            _ => None,
+        })
+        .ok_or_else(|| {
+            Error::Miscellaneous(format!(
+                "the account doesn't hold the foundry of native token {token_id}, only its minter can mint more"
+            ))
+        })?;
+
+    if amount > maximum_supply - circulating_supply {
+        return Err(Error::Miscellaneous(format!(
+            "only {} more can be minted, {circulating_supply} of the maximum supply of {maximum_supply} circulating",
+            maximum_supply - circulating_supply
+        )));
+    }
+
     let mint_transaction = account_handle
-        .increase_native_token_supply(
-            TokenId::from_str(&token_id)?,
-            U256::from_dec_str(&amount).map_err(|e| Error::Miscellaneous(e.to_string()))?,
-            None,
-            None,
-        )
+        .increase_native_token_supply(token_id, amount, None, None)
         .await?;
+    let transaction = &mint_transaction.transaction;
 
-    log::info!(
-        "Minting more native token transaction sent:\ntransaction id: {}\n{:?}",
-        mint_transaction.transaction.transaction_id,
-        mint_transaction.transaction.block_id
-    );
+    if output == OutputFormat::Text {
+        log::info!(
+            "Minting more native token transaction sent:\ntransaction id: {}\n{:?}\ncirculating supply: {} of {maximum_supply}",
+            transaction.transaction_id,
+            transaction.block_id,
+            circulating_supply + amount
+        );
+    }
+
+    let inclusion = wait_for_inclusion(account_handle, transaction, &wait).await?;
+
+    match output {
+        OutputFormat::Text => {
+            log_inclusion(transaction, inclusion);
+
+            Ok(())
+        }
+        OutputFormat::Json => print_json(&serde_json::json!({
+            "transactionId": transaction.transaction_id.to_string(),
+            "blockId": inclusion.map_or(transaction.block_id, |(block_id, _)| Some(block_id)).map(|block_id| block_id.to_string()),
+            "inclusionState": inclusion.map(|(_, inclusion_state)| format!("{inclusion_state:?}")),
+            "tokenId": token_id.to_string(),
+            "circulatingSupply": (circulating_supply + amount).to_string(),
+            "maximumSupply": maximum_supply.to_string(),
+        })),
+    }
+}
+
+/// `invoice` command
+pub async fn invoice_command(
+    account_handle: &AccountHandle,
+    command: InvoiceCommand,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    let coin_type = *account_handle.read().await.coin_type();
+    let invoices = match command {
+        InvoiceCommand::Create {
+            amount,
+            address,
+            expires,
+        } => vec![create_invoice(account_handle, amount, address, expires).await?],
+        InvoiceCommand::List => update_invoices(account_handle).await?.0,
+        InvoiceCommand::Status { id } => {
+            let invoice = update_invoices(account_handle)
+                .await?
+                .0
+                .into_iter()
+                .find(|invoice| invoice.id == id)
+                .ok_or_else(|| Error::Miscellaneous(format!("no invoice {id}")))?;
+
+            vec![invoice]
+        }
+    };
+
+    if output == OutputFormat::Json {
+        print_json(&serde_json::Value::from(
+            invoices.iter().map(Invoice::to_json).collect::<Vec<_>>(),
+        ))?;
+    } else if invoices.is_empty() {
+        log::info!("No invoices.");
+    } else {
+        log::info!(
+            "{}",
+            invoices.iter().fold(
+                format!(
+                    "{:>4}  {:<8}  {:>16}  {:>16}  {:<23}  {:<23}  {}",
+                    "#", "Status", "Amount", "Received", "Created on", "Expires on", "Address"
+                ),
+                |log, invoice| format!(
+                    "{log}\n{:>4}  {:<8}  {:>16}  {:>16}  {:<23}  {:<23}  {}",
+                    invoice.id,
+                    invoice.status.as_str(),
+                    format_amount(invoice.amount, coin_type),
+                    format_amount(invoice.received, coin_type),
+                    format_timestamp(invoice.created_at),
+                    invoice.expires_at.map_or_else(|| "never".to_string(), format_timestamp),
+                    invoice.address
+                ),
+            )
+        );
+    }
+
+    Ok(())
+}
+
+// Reports the invoices that `sync` marked paid or expired.
+pub async fn report_invoices(account_handle: &AccountHandle) -> Result<(), Error> {
+    let coin_type = *account_handle.read().await.coin_type();
+    let (invoices, changed) = update_invoices(account_handle).await?;
+
+    for invoice in invoices.iter().filter(|invoice| changed.contains(&invoice.id)) {
+        match invoice.status {
+            InvoiceStatus::Paid => log::info!(
+                "Invoice {} of {} paid on {}: {}",
+                invoice.id,
+                format_amount(invoice.amount, coin_type),
+                invoice.address,
+                invoice.paid_by.join(", ")
+            ),
+            _ => log::info!(
+                "Invoice {} of {} expired, {} received on {}.",
+                invoice.id,
+                format_amount(invoice.amount, coin_type),
+                format_amount(invoice.received, coin_type),
+                invoice.address
+            ),
+        }
+    }
 
     Ok(())
 }
 
+// `mint-collection` command
+pub async fn mint_collection_command(
+    account_handle: &AccountHandle,
+    path: String,
+    dry_run: bool,
+    yes: bool,
+    wait: WaitParameters,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    let nfts = read_collection(account_handle, &path).await?;
+
+    if nfts.is_empty() {
+        return Err(Error::Miscellaneous(format!("\"{path}\" has no NFT")));
+    }
+
+    let coin_type = *account_handle.read().await.coin_type();
+    let batches = collection_batches(&nfts);
+    let storage_deposit = nfts.iter().map(|nft| nft.output.amount()).sum::<u64>();
+    let mut summary = String::new();
+
+    for nft in &nfts {
+        summary = format!(
+            "{summary}\n{:>4}  {}  {}  {}",
+            nft.entry,
+            nft.name.as_deref().unwrap_or("-"),
+            nft.address,
+            format_amount(nft.output.amount(), coin_type)
+        );
+    }
+    summary = format!(
+        "{summary}\n{} NFTs in {} transactions, storage deposits: {}",
+        nfts.len(),
+        batches.len(),
+        format_amount(storage_deposit, coin_type)
+    );
+
+    log::info!("NFTs of \"{path}\":{summary}");
+
+    let available = account_handle.balance().await?.base_coin.available;
+
+    if storage_deposit > available {
+        return Err(Error::Miscellaneous(format!(
+            "the storage deposits of the collection amount to {} but only {} is available",
+            format_amount(storage_deposit, coin_type),
+            format_amount(available, coin_type)
+        )));
+    }
+
+    if dry_run {
+        log::info!("Dry run, nothing was minted.");
+        return Ok(());
+    }
+    if !yes
+        && !untimed(|| {
+            Confirm::new()
+                .with_prompt(format!(
+                    "Mint these {} NFTs in {} transactions?",
+                    nfts.len(),
+                    batches.len()
+                ))
+                .interact()
+        })?
+    {
+        log::info!("Collection not minted.");
+        return Ok(());
+    }
+
+    let mut minted = Vec::new();
+    let result = async {
+        for (index, batch) in batches.iter().enumerate() {
+            let last = index + 1 == batches.len();
+            let transaction = account_handle
+                .send(batch.iter().map(|nft| nft.output.clone()).collect(), None)
+                .await?;
+
+            if output == OutputFormat::Text {
+                log::info!(
+                    "Collection transaction {}/{} sent:\ntransaction id: {}\n{:?}",
+                    index + 1,
+                    batches.len(),
+                    transaction.transaction_id,
+                    transaction.block_id
+                );
+            }
+
+            // The next batch spends the remainder of this one, which has to be included first.
+            let wait = if last {
+                wait.clone()
+            } else {
+                WaitParameters {
+                    wait: true,
+                    timeout: wait.timeout.clone(),
+                }
+            };
+            let inclusion = wait_for_inclusion(account_handle, &transaction, &wait).await?;
+
+            if output == OutputFormat::Text {
+                log_inclusion(&transaction, inclusion);
+            }
+
+            // The new NFTs, with a null id until minted, are in the order of the batch.
+            let TransactionEssence::Regular(essence) = transaction.payload.essence();
+            let nft_ids = essence
+                .outputs()
+                .iter()
+                .enumerate()
+                .filter(|(_, output)| matches!(output, Output::Nft(nft) if nft.nft_id().is_null()))
+                .map(|(index, _)| Ok(NftId::from(OutputId::new(transaction.transaction_id, index as u16)?)))
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            minted.extend(
+                batch
+                    .iter()
+                    .zip(nft_ids)
+                    .map(|(nft, nft_id)| (nft.entry, nft.name.clone(), nft_id, transaction.transaction_id)),
+            );
+
+            if !last {
+                timed(Phase::Node, account_handle.sync(None)).await?;
+            }
+        }
+
+        Ok::<_, Error>(())
+    }
+    .await;
+
+    match output {
+        OutputFormat::Text if !minted.is_empty() => {
+            let mut report = String::new();
+
+            for (entry, name, nft_id, transaction_id) in &minted {
+                report = format!(
+                    "{report}\n{entry:>4}  {}  {nft_id}  {transaction_id}",
+                    name.as_deref().unwrap_or("-")
+                );
+            }
+
+            log::info!("Minted NFTs of \"{path}\":{report}");
+        }
+        OutputFormat::Text => {}
+        OutputFormat::Json => print_json(&serde_json::Value::from(
+            minted
+                .iter()
+                .map(|(entry, name, nft_id, transaction_id)| {
+                    serde_json::json!({
+                        "entry": entry,
+                        "name": name,
+                        "nftId": nft_id.to_string(),
+                        "transactionId": transaction_id.to_string(),
+                    })
+                })
+                .collect::<Vec<_>>(),
+        ))?,
+    }
+
+    result.map_err(|e| {
+        if minted.is_empty() {
+            e
+        } else {
+            Error::Miscellaneous(format!(
+                "only {} of the {} NFTs were minted, the others are still to mint: {e}",
+                minted.len(),
+                nfts.len()
+            ))
+        }
+    })
+}
+
+// Room left in a block besides the NFT outputs of a batch, for its inputs, their unlocks, the remainder and the other
+// fields of the transaction.
+const COLLECTION_BATCH_RESERVED_LENGTH: usize = 8 * 1024;
+
+// Fields of a manifest entry, to report the unknown ones.
+const COLLECTION_ENTRY_FIELDS: [&str; 12] = [
+    "address",
+    "attributes",
+    "collection",
+    "immutableMetadata",
+    "issuer",
+    "metadata",
+    "mimeType",
+    "name",
+    "royalties",
+    "sender",
+    "tag",
+    "uri",
+];
+
+// An NFT of a collection manifest, with the number of its entry, its IRC-27 name if it has one and its owner.
+struct CollectionNft {
+    entry: usize,
+    name: Option<String>,
+    address: String,
+    output: Output,
+}
+
+// Reads the NFTs of a collection manifest, a JSON array of entries or an object with an `nfts` array and defaults of
+// the entries, checking them all before anything is minted and reporting all the invalid ones at once.
+async fn read_collection(account_handle: &AccountHandle, path: &str) -> Result<Vec<CollectionNft>, Error> {
+    let manifest = serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(path)?)
+        .map_err(|e| Error::Miscellaneous(format!("invalid JSON in \"{path}\": {e}")))?;
+    let (defaults, entries) = match manifest {
+        serde_json::Value::Array(entries) => (serde_json::Map::new(), entries),
+        serde_json::Value::Object(mut defaults) => {
+            let entries = match defaults.remove("nfts") {
+                Some(serde_json::Value::Array(entries)) => entries,
+                _ => {
+                    return Err(Error::Miscellaneous(format!("\"{path}\" must hold an `nfts` array")));
+                }
+            };
+
+            if let Some(field) = defaults
+                .keys()
+                .find(|field| !["collection", "issuer", "royalties"].contains(&field.as_str()))
+            {
+                return Err(Error::Miscellaneous(format!(
+                    "unknown field `{field}` in \"{path}\", only `collection`, `issuer` and `royalties` can be defaulted"
+                )));
+            }
+
+            (defaults, entries)
+        }
+        _ => {
+            return Err(Error::Miscellaneous(format!(
+                "\"{path}\" must hold an array of NFTs or an object with an `nfts` array"
+            )));
+        }
+    };
+
+    let rent_structure = account_handle.client().get_rent_structure()?;
+    let token_supply = account_handle.client().get_token_supply()?;
+    let first_address = account_handle
+        .read()
+        .await
+        .public_addresses()
+        .first()
+        .ok_or_else(|| Error::Miscellaneous("the account has no address".to_string()))?
+        .address()
+        .to_bech32();
+    let default_collection = manifest_string(&defaults, "collection")?;
+    let default_issuer = manifest_string(&defaults, "issuer")?;
+    let default_royalties = manifest_royalties(&defaults)?;
+    let mut nfts = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, entry) in entries.iter().enumerate() {
+        let nft = async {
+            let entry = entry
+                .as_object()
+                .ok_or_else(|| Error::Miscellaneous("an object is expected".to_string()))?;
+
+            if let Some(field) = entry
+                .keys()
+                .find(|field| !COLLECTION_ENTRY_FIELDS.contains(&field.as_str()))
+            {
+                return Err(Error::Miscellaneous(format!("unknown field `{field}`")));
+            }
+
+            let address = manifest_string(entry, "address")?.unwrap_or_else(|| first_address.clone());
+            check_network(account_handle, &address).await?;
+
+            let mut builder =
+                NftOutputBuilder::new_with_minimum_storage_deposit(rent_structure.clone(), NftId::null())?
+                    .add_unlock_condition(UnlockCondition::Address(AddressUnlockCondition::new(
+                        Address::try_from_bech32(&address)?.1,
+                    )));
+
+            if let Some(sender) = manifest_string(entry, "sender")? {
+                check_network(account_handle, &sender).await?;
+                builder = builder.add_feature(Feature::Sender(SenderFeature::new(
+                    Address::try_from_bech32(&sender)?.1,
+                )));
+            }
+            if let Some(metadata) = manifest_bytes(entry, "metadata")? {
+                builder = builder.add_feature(Feature::Metadata(MetadataFeature::new(metadata)?));
+            }
+            if let Some(tag) = manifest_string(entry, "tag")? {
+                let tag = prefix_hex::decode(&tag).map_err(|e| Error::Miscellaneous(format!("invalid `tag`: {e}")))?;
+                builder = builder.add_feature(Feature::Tag(TagFeature::new(tag)?));
+            }
+            if let Some(issuer) = manifest_string(entry, "issuer")?.or_else(|| default_issuer.clone()) {
+                check_network(account_handle, &issuer).await?;
+                builder = builder.add_immutable_feature(Feature::Issuer(IssuerFeature::new(
+                    Address::try_from_bech32(&issuer)?.1,
+                )));
+            }
+
+            let name = manifest_string(entry, "name")?;
+            let immutable_metadata = match &name {
+                Some(_) if entry.contains_key("immutableMetadata") => {
+                    return Err(Error::Miscellaneous(
+                        "`name` builds IRC-27 immutable metadata, it can't come with `immutableMetadata`".to_string(),
+                    ));
+                }
+                Some(name) => Some(irc27_metadata(
+                    name.clone(),
+                    manifest_string(entry, "uri")?
+                        .ok_or_else(|| Error::Miscellaneous("a `uri` is needed with a `name`".to_string()))?,
+                    manifest_string(entry, "mimeType")?,
+                    manifest_string(entry, "collection")?.or_else(|| default_collection.clone()),
+                    entry.get("attributes").cloned(),
+                    match manifest_royalties(entry)? {
+                        Some(royalties) => royalties,
+                        None => default_royalties.clone().unwrap_or_default(),
+                    },
+                )?),
+                None => {
+                    if let Some(field) = ["attributes", "collection", "mimeType", "royalties", "uri"]
+                        .into_iter()
+                        .find(|field| entry.contains_key(*field))
+                    {
+                        return Err(Error::Miscellaneous(format!(
+                            "`{field}` is part of IRC-27 immutable metadata, which needs a `name`"
+                        )));
+                    }
+
+                    manifest_bytes(entry, "immutableMetadata")?
+                }
+            };
+            if let Some(immutable_metadata) = immutable_metadata {
+                builder = builder.add_immutable_feature(Feature::Metadata(MetadataFeature::new(immutable_metadata)?));
+            }
+
+            Ok(CollectionNft {
+                entry: index + 1,
+                name,
+                address,
+                output: builder.finish_output(token_supply)?,
+            })
+        }
+        .await;
+
+        match nft {
+            Ok(nft) => nfts.push(nft),
+            Err(e) => errors.push(format!("entry {}: {e}", index + 1)),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(Error::Miscellaneous(format!(
+            "invalid entries in \"{path}\", nothing was minted:\n{}",
+            errors.join("\n")
+        )));
+    }
+
+    Ok(nfts)
+}
+
+// A string field of a manifest entry.
+fn manifest_string(entry: &serde_json::Map<String, serde_json::Value>, field: &str) -> Result<Option<String>, Error> {
+    match entry.get(field) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::String(value)) => Ok(Some(value.clone())),
+        Some(_) => Err(Error::Miscellaneous(format!("`{field}` must be a string"))),
+    }
+}
+
+// A metadata field of a manifest entry, 0x prefixed hex or any other JSON value, kept as written.
+fn manifest_bytes(entry: &serde_json::Map<String, serde_json::Value>, field: &str) -> Result<Option<Vec<u8>>, Error> {
+    match entry.get(field) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::String(hex)) => prefix_hex::decode(hex)
+            .map(Some)
+            .map_err(|e| Error::Miscellaneous(format!("`{field}` must be 0x prefixed hex or JSON: {e}"))),
+        Some(value) => Ok(Some(value.to_string().into_bytes())),
+    }
+}
+
+// The IRC-27 royalties of a manifest entry, as `<address>:<percent>` strings like `mint-nft --royalty`.
+fn manifest_royalties(entry: &serde_json::Map<String, serde_json::Value>) -> Result<Option<Vec<String>>, Error> {
+    match entry.get("royalties") {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::Array(royalties)) => royalties
+            .iter()
+            .map(|royalty| royalty.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .map(Some)
+            .ok_or_else(|| Error::Miscellaneous("`royalties` must be `<address>:<percent>` strings".to_string())),
+        Some(_) => Err(Error::Miscellaneous(
+            "`royalties` must be an array of `<address>:<percent>` strings".to_string(),
+        )),
+    }
+}
+
+// Splits the NFTs of a collection in batches fitting in a transaction, with room for a remainder output below the
+// maximum of 128 outputs and for the inputs below the maximum length of a block.
+fn collection_batches(nfts: &[CollectionNft]) -> Vec<&[CollectionNft]> {
+    let mut batches = Vec::new();
+    let (mut start, mut length) = (0, 0);
+
+    for (index, nft) in nfts.iter().enumerate() {
+        let nft_length = nft.output.packed_len();
+
+        if index > start
+            && (index - start == OUTPUT_COUNT_MAX as usize - 1
+                || length + nft_length > Block::LENGTH_MAX - COLLECTION_BATCH_RESERVED_LENGTH)
+        {
+            batches.push(&nfts[start..index]);
+            (start, length) = (index, 0);
+        }
+        length += nft_length;
+    }
+    batches.push(&nfts[start..]);
+
+    batches
+}
+
 // `mint-native-token` command
+#[allow(clippy::too_many_arguments)]
 pub async fn mint_native_token_command(
     account_handle: &AccountHandle,
     circulating_supply: String,
     maximum_supply: String,
     foundry_metadata: Option<Vec<u8>>,
+    alias: Option<String>,
+    dry_run: bool,
+    wait: WaitParameters,
+    output: OutputFormat,
 ) -> Result<(), Error> {
+    let alias_id = alias.as_deref().map(parse_alias_id).transpose()?;
+    let aliases = owned_aliases(account_handle).await?;
+
+    // Checked here as the library only reports a missing alias output.
+    match alias_id {
+        Some(alias_id) if !aliases.iter().any(|(owned, _)| *owned == alias_id) => {
+            return Err(Error::Miscellaneous(format!(
+                "the account doesn't control alias {alias_id}, its aliases are listed by `balance`"
+            )));
+        }
+        None if aliases.is_empty() => {
+            return Err(Error::Miscellaneous(
+                "the account has no alias to control the foundry, create one with `create-alias`".to_string(),
+            ));
+        }
+        _ => {}
+    }
+
     let native_token_options = NativeTokenOptions {
-        alias_id: None,
+        alias_id,
         circulating_supply: U256::from_dec_str(&circulating_supply).map_err(|e| Error::Miscellaneous(e.to_string()))?,
         maximum_supply: U256::from_dec_str(&maximum_supply).map_err(|e| Error::Miscellaneous(e.to_string()))?,
         foundry_metadata,
     };
 
+    if native_token_options.circulating_supply > native_token_options.maximum_supply {
+        return Err(Error::Miscellaneous(format!(
+            "the circulating supply {circulating_supply} exceeds the maximum supply {maximum_supply}, mint less now \
+             and the rest later with `increase-native-token-supply`"
+        )));
+    }
+
+    if dry_run {
+        // The outputs of `mint_native_token`: the alias with its state index and foundry counter incremented, and the
+        // new foundry, the minted tokens going to the remainder.
+        let rent_structure = account_handle.client().get_rent_structure()?;
+        let token_supply = account_handle.client().get_token_supply()?;
+        let (alias_id, alias_output) = account_handle
+            .unspent_outputs(None)
+            .await?
+            .into_iter()
+            .find_map(|output_data| match output_data.output {
+                Output::Alias(alias_output) => {
+                    let id = alias_output.alias_id().or_from_output_id(output_data.output_id);
+
+                    alias_id
+                        .is_none_or(|alias_id| alias_id == id)
+                        .then_some((id, alias_output))
+                }
+                _ => None,
+            })
+            .ok_or_else(|| Error::Miscellaneous("the account has no alias to control the foundry".to_string()))?;
+        let foundry_counter = alias_output.foundry_counter() + 1;
+        let mut foundry_builder = FoundryOutputBuilder::new_with_minimum_storage_deposit(
+            rent_structure,
+            foundry_counter,
+            TokenScheme::Simple(SimpleTokenScheme::new(
+                native_token_options.circulating_supply,
+                U256::zero(),
+                native_token_options.maximum_supply,
+            )?),
+        )?
+        .add_unlock_condition(UnlockCondition::ImmutableAliasAddress(
+            ImmutableAliasAddressUnlockCondition::new(AliasAddress::new(alias_id)),
+        ));
+
+        if let Some(foundry_metadata) = native_token_options.foundry_metadata {
+            foundry_builder =
+                foundry_builder.add_immutable_feature(Feature::Metadata(MetadataFeature::new(foundry_metadata)?));
+        }
+
+        let outputs = vec![
+            AliasOutputBuilder::from(&alias_output)
+                .with_alias_id(alias_id)
+                .with_state_index(alias_output.state_index() + 1)
+                .with_foundry_counter(foundry_counter)
+                .finish_output(token_supply)?,
+            foundry_builder.finish_output(token_supply)?,
+        ];
+
+        return dry_run_transaction(account_handle, outputs, output).await;
+    }
+
     let mint_transaction = account_handle.mint_native_token(native_token_options, None).await?;
+    let transaction = &mint_transaction.transaction;
 
-    log::info!(
-        "Native token minting transaction sent:\ntransaction id: {}\n{:?}",
-        mint_transaction.transaction.transaction_id,
-        mint_transaction.transaction.block_id
-    );
+    if output == OutputFormat::Text {
+        log::info!(
+            "Native token minting transaction sent:\ntransaction id: {}\n{:?}\ntoken id: {}",
+            transaction.transaction_id,
+            transaction.block_id,
+            mint_transaction.token_id
+        );
+    }
 
-    Ok(())
+    let inclusion = wait_for_inclusion(account_handle, transaction, &wait).await?;
+
+    match output {
+        OutputFormat::Text => {
+            log_inclusion(transaction, inclusion);
+
+            Ok(())
+        }
+        OutputFormat::Json => print_json(&serde_json::json!({
+            "transactionId": transaction.transaction_id.to_string(),
+            "blockId": inclusion.map_or(transaction.block_id, |(block_id, _)| Some(block_id)).map(|block_id| block_id.to_string()),
+            "inclusionState": inclusion.map(|(_, inclusion_state)| format!("{inclusion_state:?}")),
+            "tokenId": mint_transaction.token_id.to_string(),
+        })),
+    }
 }
 
 // `mint-nft` command
+#[allow(clippy::too_many_arguments)]
 pub async fn mint_nft_command(
     account_handle: &AccountHandle,
     address: Option<String>,
@@ -396,12 +2326,60 @@ pub async fn mint_nft_command(
     tag: Option<String>,
     sender: Option<String>,
     issuer: Option<String>,
+    dry_run: bool,
+    wait: WaitParameters,
+    output: OutputFormat,
 ) -> Result<(), Error> {
+    if let Some(address) = &address {
+        check_network(account_handle, address).await?;
+    }
+
     let tag = if let Some(hex) = tag {
         Some(prefix_hex::decode(&hex).map_err(|e| Error::Miscellaneous(e.to_string()))?)
     } else {
         None
     };
+
+    if dry_run {
+        // The NFT output of `mint_nfts`, owned by the first address of the account unless another one is given.
+        let rent_structure = account_handle.client().get_rent_structure()?;
+        let token_supply = account_handle.client().get_token_supply()?;
+        let address = match address {
+            Some(address) => Address::try_from_bech32(&address)?.1,
+            None => *account_handle
+                .read()
+                .await
+                .public_addresses()
+                .first()
+                .ok_or_else(|| Error::Miscellaneous("the account has no address".to_string()))?
+                .address()
+                .as_ref(),
+        };
+        let mut builder = NftOutputBuilder::new_with_minimum_storage_deposit(rent_structure, NftId::null())?
+            .add_unlock_condition(UnlockCondition::Address(AddressUnlockCondition::new(address)));
+
+        if let Some(sender) = sender {
+            builder = builder.add_feature(Feature::Sender(SenderFeature::new(
+                Address::try_from_bech32(&sender)?.1,
+            )));
+        }
+        if let Some(metadata) = metadata {
+            builder = builder.add_feature(Feature::Metadata(MetadataFeature::new(metadata)?));
+        }
+        if let Some(tag) = tag {
+            builder = builder.add_feature(Feature::Tag(TagFeature::new(tag)?));
+        }
+        if let Some(issuer) = issuer {
+            builder = builder.add_immutable_feature(Feature::Issuer(IssuerFeature::new(
+                Address::try_from_bech32(&issuer)?.1,
+            )));
+        }
+        if let Some(immutable_metadata) = immutable_metadata {
+            builder = builder.add_immutable_feature(Feature::Metadata(MetadataFeature::new(immutable_metadata)?));
+        }
+
+        return dry_run_transaction(account_handle, vec![builder.finish_output(token_supply)?], output).await;
+    }
     let nft_options = vec![NftOptions {
         issuer,
         sender,
@@ -412,20 +2390,39 @@ pub async fn mint_nft_command(
     }];
     let transaction = account_handle.mint_nfts(nft_options, None).await?;
 
-    log::info!(
-        "NFT minting transaction sent:\ntransaction id: {}\n{:?}",
-        transaction.transaction_id,
-        transaction.block_id
-    );
-
-    Ok(())
+    transaction_sent(account_handle, "NFT minting transaction", &transaction, &wait, output).await
 }
 
 // `new-address` command
-pub async fn new_address_command(account_handle: &AccountHandle) -> Result<(), Error> {
-    let address = account_handle.generate_addresses(1, None).await?;
+pub async fn new_address_command(account_handle: &AccountHandle, qr: AddressQrParameters) -> Result<(), Error> {
+    let addresses = timed(Phase::SecretManager, account_handle.generate_addresses(1, None)).await?;
+    // Without the keys, with an external signer, no address is generated.
+    let address = addresses.first().ok_or(Error::ExternalSignerUnsupported)?;
+
+    print_address(account_handle, address, qr).await
+}
+
+// Prints an address with its outputs, and as a QR code if asked to.
+async fn print_address(
+    account_handle: &AccountHandle,
+    address: &AccountAddress,
+    qr: AddressQrParameters,
+) -> Result<(), Error> {
+    let bech32 = address.address().to_bech32();
 
-    print_address(account_handle, &address[0]).await?;
+    log::info!(
+        "{}",
+        format_address(address, &account_handle.addresses_with_unspent_outputs().await?)
+    );
+
+    if qr.qr {
+        println!("{}", qr_text(&bech32)?);
+    }
+
+    if let Some(path) = qr.png {
+        write_qr_png(&bech32, &path)?;
+        log::info!("QR code of {bech32} written to \"{path}\".");
+    }
 
     Ok(())
 }
@@ -434,10 +2431,21 @@ pub async fn new_address_command(account_handle: &AccountHandle) -> Result<(), E
 pub async fn output_command(account_handle: &AccountHandle, output_id: String) -> Result<(), Error> {
     let output = account_handle.get_output(&OutputId::from_str(&output_id)?).await;
 
-    if let Some(output) = output {
-        log::info!("{output:#?}");
-    } else {
-        log::info!("Output not found");
+    if let Some(output_data) = output {
+        let context = output_context(account_handle).await?;
+        let metadata = &output_data.metadata;
+
+        log::info!(
+            "{}\n      Address: {}\n      Booked in milestone {} on {}, block {}\n      State: {}",
+            format_output(&output_data.output_id, &output_data.output, &context),
+            output_data.address.to_bech32(&context.bech32_hrp),
+            metadata.milestone_index_booked,
+            format_timestamp(metadata.milestone_timestamp_booked.into()),
+            metadata.block_id,
+            output_state(account_handle, &output_data, &context).await
+        );
+    } else {
+        log::info!("Output not found");
     }
 
     Ok(())
@@ -450,20 +2458,26 @@ pub async fn outputs_command(account_handle: &AccountHandle) -> Result<(), Error
     if outputs.is_empty() {
         log::info!("No outputs found");
     } else {
-        let output_ids: Vec<OutputId> = outputs.iter().map(|o| o.output_id).collect();
-        log::info!("Outputs: {output_ids:#?}");
+        print_paged(&format!(
+            "Outputs:{}",
+            format_output_list(account_handle, &outputs).await?
+        ));
     }
 
     Ok(())
 }
 
-// `send` command
-pub async fn send_command(account_handle: &AccountHandle, address: String, amount: u64) -> Result<(), Error> {
-    let outputs = vec![AddressWithAmount { address, amount }];
-    let transaction = account_handle.send_amount(outputs, None).await?;
+// `participate` command
+pub async fn participate_command(
+    account_handle: &AccountHandle,
+    event_id: String,
+    amount: Option<u64>,
+    maintain: bool,
+) -> Result<(), Error> {
+    let transaction = participate(account_handle, &event_id, amount, maintain).await?;
 
     log::info!(
-        "Transaction sent:\ntransaction id: {}\n{:?}",
+        "Participation transaction sent:\ntransaction id: {}\n{:?}",
         transaction.transaction_id,
         transaction.block_id
     );
@@ -471,145 +2485,2882 @@ pub async fn send_command(account_handle: &AccountHandle, address: String, amoun
     Ok(())
 }
 
-// `send-micro` command
-pub async fn send_micro_command(account_handle: &AccountHandle, address: String, amount: u64) -> Result<(), Error> {
-    let outputs = vec![AddressWithMicroAmount {
-        address,
-        amount,
-        return_address: None,
-        expiration: None,
-    }];
+/// `report` command
+pub async fn report_command(
+    account_handle: &AccountHandle,
+    period: String,
+    format: ReportFormat,
+    currency: Option<String>,
+    out: Option<String>,
+) -> Result<(), Error> {
+    let report = accounting_report(account_handle, &period, format, currency).await?;
+
+    match out {
+        Some(out) => {
+            std::fs::write(&out, report)?;
+            log::info!("Report of {period} has been written to \"{out}\".");
+        }
+        // Printed as is, without the log prefix.
+        None => print!("{report}"),
+    }
 
-    let transaction = account_handle.send_micro_transaction(outputs, None).await?;
+    Ok(())
+}
 
-    log::info!(
-        "Micro transaction sent:\ntransaction id: {}\n{:?}",
-        transaction.transaction_id,
-        transaction.block_id
-    );
+// `pending` command
+pub async fn pending_command(account_handle: &AccountHandle) -> Result<(), Error> {
+    let mut transactions = account_handle.pending_transactions().await?;
+
+    if transactions.is_empty() {
+        log::info!("No pending transaction.");
+        return Ok(());
+    }
+
+    transactions.sort_by_key(|transaction| transaction.timestamp);
+
+    let client = account_handle.client();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let mut list = String::new();
+
+    for transaction in transactions {
+        let sent = u64::try_from(transaction.timestamp / 1000).unwrap_or_default();
+        // The node may be unreachable, the local state is listed anyway.
+        let state = match transaction.block_id {
+            Some(block_id) => match client.get_included_block(&transaction.transaction_id).await {
+                Ok(block) => format!("included in block {}, run `sync` to update the account", block.id()),
+                Err(_) => match client.get_block_metadata(&block_id).await {
+                    Ok(metadata) if metadata.should_reattach == Some(true) => {
+                        format!("block {block_id} too old to be referenced, `reattach` it")
+                    }
+                    Ok(metadata) if metadata.should_promote == Some(true) => {
+                        format!("block {block_id} waiting for a milestone, `promote` it")
+                    }
+                    Ok(_) => format!("block {block_id} waiting for a milestone"),
+                    Err(ClientError::NotFound(_)) => format!("block {block_id} unknown to the node, `reattach` it"),
+                    Err(e) => format!("block {block_id}, node unreachable: {e}"),
+                },
+            },
+            None => "never sent in a block".to_string(),
+        };
+
+        list = format!(
+            "{list}\n{}  sent {}, {} ago: {state}",
+            transaction.transaction_id,
+            format_timestamp(sent),
+            format_age(now.saturating_sub(sent))
+        );
+    }
+
+    log::info!("Pending transactions:{list}");
 
     Ok(())
 }
 
-// `send-native-token` command
-pub async fn send_native_token_command(
+// Formats a number of seconds as its two largest units, e.g. `2d 3h` or `5m 12s`.
+fn format_age(seconds: u64) -> String {
+    let units = [(24 * 60 * 60, "d"), (60 * 60, "h"), (60, "m"), (1, "s")];
+    let parts = units
+        .iter()
+        .scan(seconds, |rest, (unit, suffix)| {
+            let value = *rest / unit;
+
+            *rest %= unit;
+            Some((value, suffix))
+        })
+        .skip_while(|(value, _)| *value == 0)
+        .take(2)
+        .map(|(value, suffix)| format!("{value}{suffix}"))
+        .collect::<Vec<_>>();
+
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// What to do with the block of a pending transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryAction {
+    Promote,
+    Reattach,
+    /// Whichever the node advises.
+    Retry,
+}
+
+/// `promote`, `reattach` and `retry` commands
+pub async fn retry_command(
     account_handle: &AccountHandle,
-    address: String,
-    token_id: String,
-    amount: String,
-    gift_storage_deposit: Option<bool>,
+    transaction_id: String,
+    action: RetryAction,
+    wait: WaitParameters,
+    output: OutputFormat,
 ) -> Result<(), Error> {
-    let transaction = if gift_storage_deposit.unwrap_or(false) {
-        // Send native tokens together with the required storage deposit
-        let rent_structure = account_handle.client().get_rent_structure()?;
-        let token_supply = account_handle.client().get_token_supply()?;
+    let transaction_id = TransactionId::from_str(&transaction_id)?;
+    let transaction = account_handle
+        .get_transaction(&transaction_id)
+        .await
+        .ok_or_else(|| Error::Miscellaneous(format!("transaction {transaction_id} not found")))?;
 
-        let outputs = vec![
-            BasicOutputBuilder::new_with_minimum_storage_deposit(rent_structure)?
-                .add_unlock_condition(UnlockCondition::Address(AddressUnlockCondition::new(
-                    Address::try_from_bech32(address)?.1,
-                )))
-                .with_native_tokens(vec![NativeToken::new(
-                    TokenId::from_str(&token_id)?,
-                    U256::from_dec_str(&amount).map_err(|e| Error::Miscellaneous(e.to_string()))?,
-                )?])
-                .finish_output(token_supply)?,
-        ];
+    if transaction.inclusion_state != InclusionState::Pending {
+        return Err(Error::Miscellaneous(format!(
+            "transaction {transaction_id} isn't pending, it's {:?}",
+            transaction.inclusion_state
+        )));
+    }
 
-        account_handle.send(outputs, None).await?
+    let block_id = transaction
+        .block_id
+        .ok_or_else(|| Error::Miscellaneous(format!("transaction {transaction_id} was never sent in a block")))?;
+    let client = account_handle.client();
+    let metadata = timed(Phase::Node, client.get_block_metadata(&block_id)).await?;
+    let (should_promote, should_reattach) = (
+        metadata.should_promote == Some(true),
+        metadata.should_reattach == Some(true),
+    );
+    let reattach = match action {
+        RetryAction::Promote if should_promote => false,
+        RetryAction::Reattach if should_reattach => true,
+        RetryAction::Retry if should_promote || should_reattach => should_reattach,
+        _ => {
+            return Err(Error::Miscellaneous(format!(
+                "the node doesn't advise to {} block {block_id}{}, run `pending` to see its state",
+                match action {
+                    RetryAction::Promote => "promote",
+                    RetryAction::Reattach => "reattach",
+                    RetryAction::Retry => "promote or reattach",
+                },
+                if metadata.referenced_by_milestone_index.is_some() {
+                    ", already referenced by a milestone"
+                } else {
+                    ""
+                }
+            )));
+        }
+    };
+
+    let (new_block_id, _) = if reattach {
+        timed(Phase::Node, client.reattach_unchecked(&block_id)).await?
     } else {
-        // Send native tokens with storage deposit return and expiration
-        let outputs = vec![AddressNativeTokens {
-            address,
-            native_tokens: vec![(
-                TokenId::from_str(&token_id)?,
-                U256::from_dec_str(&amount).map_err(|e| Error::Miscellaneous(e.to_string()))?,
-            )],
-            ..Default::default()
-        }];
-        account_handle.send_native_tokens(outputs, None).await?
+        timed(Phase::Node, client.promote_unchecked(&block_id)).await?
     };
 
-    log::info!(
-        "Native token transaction sent:\ntransaction id: {}\n{:?}",
-        transaction.transaction_id,
-        transaction.block_id
-    );
+    if output == OutputFormat::Text {
+        log::info!(
+            "Transaction {transaction_id} {} in block {new_block_id}.",
+            if reattach { "reattached" } else { "promoted" }
+        );
+    }
 
-    Ok(())
+    // Waiting on the original block also follows its reattachments.
+    let inclusion = wait_for_inclusion(account_handle, &transaction, &wait).await?;
+
+    match output {
+        OutputFormat::Text => {
+            log_inclusion(&transaction, inclusion);
+
+            Ok(())
+        }
+        OutputFormat::Json => print_json(&serde_json::json!({
+            "transactionId": transaction_id.to_string(),
+            "action": if reattach { "reattach" } else { "promote" },
+            "blockId": new_block_id.to_string(),
+            "includedBlockId": inclusion.map(|(block_id, _)| block_id.to_string()),
+        })),
+    }
 }
 
-// `send-nft` command
-pub async fn send_nft_command(account_handle: &AccountHandle, address: String, nft_id: String) -> Result<(), Error> {
-    let outputs = vec![AddressAndNftId {
-        address,
-        nft_id: NftId::from_str(&nft_id)?,
-    }];
-    let transaction = account_handle.send_nft(outputs, None).await?;
+// `resume` command
+pub async fn resume_command(account_handle: &AccountHandle) -> Result<(), Error> {
+    match job(*account_handle.read().await.index())? {
+        Some(Job::Vest {
+            address,
+            installments,
+            pending_block_id,
+        }) => {
+            log::info!(
+                "Resuming the vesting to {address}, {} installment(s) left.",
+                installments.len()
+            );
+            send_vesting(account_handle, address, installments, pending_block_id).await
+        }
+        Some(Job::Claim) => {
+            log::info!("Resuming the claiming.");
+            claim_all(account_handle).await
+        }
+        None => {
+            log::info!("Nothing to resume.");
+            Ok(())
+        }
+    }
+}
 
-    log::info!(
-        "Nft transaction sent:\ntransaction id: {}\n{:?}",
-        transaction.transaction_id,
-        transaction.block_id
-    );
+// `script` command
+pub async fn script_command(account_handle: &AccountHandle, path: String) -> Result<(), Error> {
+    let script = tokio::fs::read_to_string(&path).await?;
+    let account_handle = account_handle.clone();
+    let runtime = tokio::runtime::Handle::current();
 
-    Ok(())
+    // The script functions block on the async operations of the account, which can't be done on a runtime thread.
+    tokio::task::spawn_blocking(move || {
+        script_engine(account_handle, runtime)
+            .run(&script)
+            .map_err(|e| Error::Miscellaneous(format!("script \"{path}\" failed: {e}")))
+    })
+    .await
+    .map_err(|e| Error::Miscellaneous(e.to_string()))?
 }
 
-// `sync` command
-pub async fn sync_command(account_handle: &AccountHandle) -> Result<(), Error> {
-    let sync = account_handle.sync(None).await?;
+// `set-alias` command
+pub async fn set_alias_command(
+    manager: &AccountManager,
+    account_handle: &AccountHandle,
+    alias: String,
+) -> Result<(), Error> {
+    let (index, current_alias) = {
+        let account = account_handle.read().await;
+        (*account.index(), account.alias().clone())
+    };
+
+    if alias.trim().is_empty() {
+        return Err(Error::Miscellaneous("the alias can't be empty".to_string()));
+    }
+    // Accounts are picked by alias or index, a numeric alias would be taken for an index.
+    if alias.parse::<u32>().is_ok() {
+        return Err(Error::Miscellaneous(format!(
+            "\"{alias}\" would be taken for an account index, pick an alias that isn't a number"
+        )));
+    }
+
+    for other_account_handle in manager.get_accounts().await? {
+        let other_account = other_account_handle.read().await;
 
-    log::info!("Synced: {sync:?}");
+        // Compared like the wallet does when creating accounts.
+        if *other_account.index() != index && other_account.alias().to_lowercase() == alias.to_lowercase() {
+            return Err(Error::Miscellaneous(format!(
+                "account {} is already named \"{}\"",
+                other_account.index(),
+                other_account.alias()
+            )));
+        }
+    }
+
+    account_handle.set_alias(&alias).await?;
+
+    log::info!("Account \"{current_alias}\" renamed to \"{alias}\".");
 
     Ok(())
 }
 
-/// `transactions` command
-pub async fn transactions_command(account_handle: &AccountHandle) -> Result<(), Error> {
-    let transactions = account_handle.transactions().await?;
+// `send` command
+#[allow(clippy::too_many_arguments)]
+pub async fn send_command(
+    account_handle: &AccountHandle,
+    address: String,
+    amount: u64,
+    signer: Option<&ExternalSigner>,
+    options: SendOptions,
+    dry_run: bool,
+    wait: WaitParameters,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    check_network(account_handle, &address).await?;
 
-    if transactions.is_empty() {
-        log::info!("No transactions found");
+    if dry_run {
+        let outputs = vec![send_output(account_handle, &address, amount, &options, false).await?];
+
+        return dry_run_transaction(account_handle, outputs, output).await;
+    }
+
+    let transaction = if options == SendOptions::default() {
+        let outputs = vec![AddressWithAmount { address, amount }];
+
+        match signer {
+            Some(signer) => {
+                let prepared_transaction_data = account_handle.prepare_send_amount(outputs, None).await?;
+
+                signer
+                    .sign_and_submit(account_handle, prepared_transaction_data)
+                    .await?
+            }
+            None => account_handle.send_amount(outputs, None).await?,
+        }
     } else {
-        for tx in transactions {
-            log::info!("{}", serde_json::to_string(&TransactionDto::from(&tx))?);
+        let outputs = vec![send_output(account_handle, &address, amount, &options, false).await?];
+
+        match signer {
+            Some(signer) => {
+                let prepared_transaction_data = account_handle.prepare_transaction(outputs, None).await?;
+
+                signer
+                    .sign_and_submit(account_handle, prepared_transaction_data)
+                    .await?
+            }
+            None => account_handle.send(outputs, None).await?,
         }
+    };
+
+    transaction_sent(account_handle, "Transaction", &transaction, &wait, output).await
+}
+
+// `prepare-send` command
+pub async fn prepare_send_command(
+    account_handle: &AccountHandle,
+    address: String,
+    amount: u64,
+    out: String,
+) -> Result<(), Error> {
+    check_network(account_handle, &address).await?;
+
+    if Path::new(&out).exists() {
+        return Err(Error::Miscellaneous(format!(
+            "\"{out}\" already exists, refusing to overwrite it"
+        )));
     }
 
+    // The inputs stay locked until the transaction is submitted, so that no other transaction spends them meanwhile.
+    let prepared_transaction_data = account_handle
+        .prepare_send_amount(vec![AddressWithAmount { address, amount }], None)
+        .await?;
+
+    std::fs::write(
+        &out,
+        serde_json::to_string_pretty(&serde_json::json!({
+            "type": "preparedTransaction",
+            "preparedTransactionData": PreparedTransactionDataDto::from(&prepared_transaction_data),
+        }))?,
+    )?;
+
+    log_transaction_outputs(&prepared_transaction_data)?;
+    log::info!("Transaction prepared to \"{out}\", sign it with `sign` on the offline wallet.");
+
     Ok(())
 }
 
-/// `unspent-outputs` command
-pub async fn unspent_outputs_command(account_handle: &AccountHandle) -> Result<(), Error> {
-    let outputs = account_handle.unspent_outputs(None).await?;
+// `sign` command
+pub async fn sign_command(account_handle: &AccountHandle, path: String, out: String) -> Result<(), Error> {
+    if Path::new(&out).exists() {
+        return Err(Error::Miscellaneous(format!(
+            "\"{out}\" already exists, refusing to overwrite it"
+        )));
+    }
 
-    if outputs.is_empty() {
-        log::info!("No outputs found");
-    } else {
-        let output_ids: Vec<OutputId> = outputs.iter().map(|o| o.output_id).collect();
-        log::info!("Unspent outputs: {output_ids:#?}");
+    let file = read_transaction_file(&path, "preparedTransaction")?;
+    let prepared_transaction_data =
+        PreparedTransactionData::try_from_dto_unverified(&serde_json::from_value::<PreparedTransactionDataDto>(
+            file["preparedTransactionData"].clone(),
+        )?)
+        .map_err(|e| Error::Miscellaneous(format!("invalid transaction: {e}")))?;
+
+    log_transaction_outputs(&prepared_transaction_data)?;
+
+    if !Confirm::new().with_prompt("Sign this transaction?").interact()? {
+        return Err(Error::Miscellaneous("transaction not signed".to_string()));
     }
 
+    let signed_transaction_data = timed(
+        Phase::SecretManager,
+        account_handle.sign_transaction_essence(&prepared_transaction_data),
+    )
+    .await?;
+
+    std::fs::write(
+        &out,
+        serde_json::to_string_pretty(&serde_json::json!({
+            "type": "signedTransaction",
+            "signedTransactionData": SignedTransactionDataDto::from(&signed_transaction_data),
+        }))?,
+    )?;
+
+    log::info!("Transaction signed to \"{out}\", broadcast it with `submit` on the online wallet.");
+
     Ok(())
 }
 
-pub async fn print_address(account_handle: &AccountHandle, address: &AccountAddress) -> Result<(), Error> {
-    let mut log = format!("Address {}: {}", address.key_index(), address.address().to_bech32());
+// `submit` command
+pub async fn submit_command(
+    account_handle: &AccountHandle,
+    path: String,
+    wait: WaitParameters,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    let file = read_transaction_file(&path, "signedTransaction")?;
+    let signed_transaction_data =
+        SignedTransactionData::try_from_dto_unverified(&serde_json::from_value::<SignedTransactionDataDto>(
+            file["signedTransactionData"].clone(),
+        )?)
+        .map_err(|e| Error::Miscellaneous(format!("invalid transaction: {e}")))?;
+    let transaction = account_handle
+        .submit_and_store_transaction(signed_transaction_data)
+        .await?;
 
-    if *address.internal() {
-        log = format!("{log}\nChange address");
+    transaction_sent(account_handle, "Transaction", &transaction, &wait, output).await
+}
+
+// Reads a transaction file written by `prepare-send` or `sign`, checking it is of the expected type.
+fn read_transaction_file(path: &str, kind: &str) -> Result<serde_json::Value, Error> {
+    let file = serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(path)?)?;
+
+    match file["type"].as_str() {
+        Some(file_kind) if file_kind == kind => Ok(file),
+        Some(file_kind) => Err(Error::Miscellaneous(format!(
+            "\"{path}\" holds a {} transaction, expected a {} one",
+            file_kind.trim_end_matches("Transaction"),
+            kind.trim_end_matches("Transaction")
+        ))),
+        None => Err(Error::Miscellaneous(format!(
+            "\"{path}\" isn't a transaction file of `prepare-send` or `sign`"
+        ))),
+    }
+}
+
+// `send-all` command
+pub async fn send_all_command(
+    account_handle: &AccountHandle,
+    address: String,
+    include_assets: bool,
+    dry_run: bool,
+    yes: bool,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    check_network(account_handle, &address).await?;
+
+    let recipient = Address::try_from_bech32(&address)?.1;
+
+    if account_handle
+        .addresses()
+        .await?
+        .iter()
+        .any(|account_address| *account_address.address().as_ref() == recipient)
+    {
+        return Err(Error::Miscellaneous(format!("{address} is an address of this account")));
+    }
+
+    // Outputs spent or received since the last sync would make the transactions conflict or be left behind.
+    timed(Phase::Node, account_handle.sync(None)).await?;
+
+    let coin_type = *account_handle.read().await.coin_type();
+    let locked_outputs = account_handle.read().await.locked_outputs().clone();
+    let mut inputs = Vec::new();
+    let mut left_behind = BTreeMap::<&str, usize>::new();
+    let (mut base_coin, mut native_tokens, mut nfts) = (0u64, BTreeMap::<TokenId, U256>::new(), 0);
+
+    for output_data in account_handle.unspent_outputs(None).await? {
+        let (unlock_conditions, output_native_tokens) = match &output_data.output {
+            Output::Basic(output) => (output.unlock_conditions(), output.native_tokens()),
+            Output::Nft(output) => (output.unlock_conditions(), output.native_tokens()),
+            _ => {
+                *left_behind.entry("alias or foundry output(s)").or_default() += 1;
+                continue;
+            }
+        };
+
+        if locked_outputs.contains(&output_data.output_id) {
+            *left_behind
+                .entry("output(s) used by a pending transaction")
+                .or_default() += 1;
+        } else if unlock_conditions.len() > 1 {
+            *left_behind
+                .entry("output(s) with unlock conditions, to `claim` first")
+                .or_default() += 1;
+        } else if !include_assets && (matches!(output_data.output, Output::Nft(_)) || !output_native_tokens.is_empty())
+        {
+            *left_behind
+                .entry("output(s) holding native tokens or NFTs, moved with `--include-assets`")
+                .or_default() += 1;
+        } else {
+            base_coin += output_data.output.amount();
+            for native_token in output_native_tokens.iter() {
+                *native_tokens.entry(*native_token.token_id()).or_default() += native_token.amount();
+            }
+            if matches!(output_data.output, Output::Nft(_)) {
+                nfts += 1;
+            }
+            inputs.push(output_data);
+        }
+    }
+
+    if inputs.is_empty() {
+        return Err(Error::Miscellaneous("the account has nothing to send".to_string()));
     }
 
-    let addresses = account_handle.addresses_with_unspent_outputs().await?;
+    // Leaves room for the outputs holding the native tokens below the maximum of 128 inputs and outputs of a
+    // transaction, the bigger sets of outputs being consolidated into several transactions.
+    let chunks = inputs.chunks(100).collect::<Vec<_>>();
+    let mut transactions = Vec::new();
 
-    if let Ok(index) = addresses.binary_search_by_key(&(address.key_index(), address.internal()), |a| {
-        (a.key_index(), a.internal())
-    }) {
-        log = format!("{log}\nOutputs: {:#?}", addresses[index].output_ids());
+    for chunk in &chunks {
+        transactions.push((
+            chunk
+                .iter()
+                .map(|output_data| output_data.output_id)
+                .collect::<Vec<_>>(),
+            send_all_outputs(account_handle, recipient, chunk).await?,
+        ));
     }
 
-    log::info!("{log}");
+    let mut summary = format!(
+        "Sending {} output(s) to {address} in {} transaction(s):\n{}",
+        inputs.len(),
+        transactions.len(),
+        format_amount(base_coin, coin_type)
+    );
+
+    for (token_id, amount) in &native_tokens {
+        summary = format!("{summary}\n{amount} of {token_id}");
+    }
+    if nfts > 0 {
+        summary = format!("{summary}\n{nfts} NFT(s)");
+    }
+    for (reason, count) in &left_behind {
+        summary = format!("{summary}\nLeft behind: {count} {reason}");
+    }
+
+    log::info!("{summary}");
+
+    if dry_run {
+        return Ok(());
+    }
+
+    if !yes
+        && !untimed(|| {
+            Confirm::new()
+                .with_prompt(format!("Send everything to {address}?"))
+                .interact()
+        })?
+    {
+        return Err(Error::Miscellaneous("nothing was sent".to_string()));
+    }
+
+    for (inputs, outputs) in transactions {
+        let options = TransactionOptions {
+            custom_inputs: Some(inputs),
+            ..Default::default()
+        };
+        let transaction = account_handle.send(outputs, Some(options)).await?;
+
+        transaction_sent(
+            account_handle,
+            "Sweeping transaction",
+            &transaction,
+            &WaitParameters::default(),
+            output,
+        )
+        .await?;
+    }
 
     Ok(())
 }
+
+// Builds the outputs moving a set of outputs to an address, without remainder: an NFT output for each NFT and basic
+// outputs of at most 64 native tokens with their storage deposits, the first one holding the rest of the base coin.
+async fn send_all_outputs(
+    account_handle: &AccountHandle,
+    recipient: Address,
+    inputs: &[OutputData],
+) -> Result<Vec<Output>, Error> {
+    let coin_type = *account_handle.read().await.coin_type();
+    let rent_structure = account_handle.client().get_rent_structure()?;
+    let token_supply = account_handle.client().get_token_supply()?;
+    let address_unlock_condition = UnlockCondition::Address(AddressUnlockCondition::new(recipient));
+    let mut nft_outputs = Vec::new();
+    let mut base_coin = 0;
+    let mut native_tokens = BTreeMap::<TokenId, U256>::new();
+
+    for output_data in inputs {
+        match &output_data.output {
+            Output::Nft(output) => {
+                // The sender feature would require to unlock an address that isn't necessarily one of the account.
+                nft_outputs.push(
+                    NftOutputBuilder::from(output)
+                        .with_nft_id(output.nft_id().or_from_output_id(output_data.output_id))
+                        .with_unlock_conditions(vec![address_unlock_condition.clone()])
+                        .with_features(
+                            output
+                                .features()
+                                .iter()
+                                .filter(|feature| !matches!(feature, Feature::Sender(_)))
+                                .cloned(),
+                        )
+                        .finish_output(token_supply)?,
+                );
+            }
+            output => {
+                base_coin += output.amount();
+                for native_token in output
+                    .native_tokens()
+                    .iter()
+                    .flat_map(|native_tokens| native_tokens.iter())
+                {
+                    *native_tokens.entry(*native_token.token_id()).or_default() += native_token.amount();
+                }
+            }
+        }
+    }
+
+    let native_tokens = native_tokens
+        .into_iter()
+        .map(|(token_id, amount)| NativeToken::new(token_id, amount))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut outputs = Vec::new();
+
+    for native_tokens in native_tokens.chunks(NativeTokens::COUNT_MAX.into()) {
+        outputs.push(
+            BasicOutputBuilder::new_with_minimum_storage_deposit(rent_structure.clone())?
+                .add_unlock_condition(address_unlock_condition.clone())
+                .with_native_tokens(native_tokens.to_vec())
+                .finish_output(token_supply)?,
+        );
+    }
+
+    let storage_deposits = outputs.iter().map(Output::amount).sum::<u64>();
+    let rest = base_coin.checked_sub(storage_deposits).ok_or_else(|| {
+        Error::Miscellaneous(format!(
+            "the native tokens need {} of storage deposit but the outputs only hold {}",
+            format_amount(storage_deposits, coin_type),
+            format_amount(base_coin, coin_type)
+        ))
+    })?;
+
+    if let Some(Output::Basic(first)) = outputs.first() {
+        outputs[0] = BasicOutputBuilder::from(first)
+            .with_amount(first.amount() + rest)?
+            .finish_output(token_supply)?;
+    } else if rest > 0 {
+        outputs.push(
+            BasicOutputBuilder::new_with_amount(rest)?
+                .add_unlock_condition(address_unlock_condition)
+                .finish_output(token_supply)?,
+        );
+    }
+
+    outputs.extend(nft_outputs);
+
+    Ok(outputs)
+}
+
+// `send-batch` command
+pub async fn send_batch_command(
+    account_handle: &AccountHandle,
+    path: String,
+    yes: bool,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    let rows = read_batch(&path)?;
+
+    if rows.is_empty() {
+        return Err(Error::Miscellaneous(format!("\"{path}\" has no recipient")));
+    }
+    // Leaves room for a remainder output below the maximum of 128 outputs of a transaction.
+    if rows.len() > 127 {
+        return Err(Error::Miscellaneous(format!(
+            "{} recipients don't fit in a transaction, split the file in batches of at most 127",
+            rows.len()
+        )));
+    }
+
+    let coin_type = *account_handle.read().await.coin_type();
+    let mut seen = HashSet::new();
+    let mut recipients = Vec::new();
+    let mut errors = Vec::new();
+    let (mut base_coin, mut native_tokens) = (0u64, BTreeMap::<TokenId, U256>::new());
+
+    // Every row is checked before anything is sent, reporting all the invalid ones at once.
+    for BatchRow {
+        line,
+        address,
+        amount,
+        token_id,
+    } in rows
+    {
+        let recipient = async {
+            check_network(account_handle, &address).await?;
+
+            let token_id = token_id.as_deref().map(TokenId::from_str).transpose()?;
+
+            if !seen.insert((address.clone(), token_id)) {
+                return Err(Error::Miscellaneous("duplicate recipient".to_string()));
+            }
+
+            let amount = match token_id {
+                Some(_) => U256::from_dec_str(&amount).map_err(|e| Error::Miscellaneous(e.to_string()))?,
+                None => U256::from(
+                    amount
+                        .parse::<u64>()
+                        .map_err(|e| Error::Miscellaneous(format!("invalid amount \"{amount}\": {e}")))?,
+                ),
+            };
+
+            if amount.is_zero() {
+                return Err(Error::Miscellaneous("zero amount".to_string()));
+            }
+
+            Ok((address.clone(), amount, token_id))
+        }
+        .await;
+
+        match recipient {
+            Ok((address, amount, token_id)) => {
+                match token_id {
+                    Some(token_id) => *native_tokens.entry(token_id).or_default() += amount,
+                    None => base_coin = base_coin.saturating_add(amount.as_u64()),
+                }
+                recipients.push((address, amount, token_id));
+            }
+            Err(e) => errors.push(format!("line {line}: {e}")),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(Error::Miscellaneous(format!(
+            "invalid rows in \"{path}\", nothing was sent:\n{}",
+            errors.join("\n")
+        )));
+    }
+
+    // Outputs are prepared before checking the balance so that their storage deposits are accounted for.
+    let expiration_unix_time = account_handle.client().get_time_checked()? + 24 * 60 * 60;
+    let mut outputs = Vec::new();
+
+    // Outputs under the minimum storage deposit, like native token ones, have it returned, or reclaimed after a day,
+    // like with `send-native-token`.
+    for (address, amount, token_id) in &recipients {
+        let (amount, assets) = match token_id {
+            Some(token_id) => (
+                0,
+                Some(Assets {
+                    native_tokens: Some(vec![NativeToken::new(*token_id, *amount)?]),
+                    nft_id: None,
+                }),
+            ),
+            None => (amount.as_u64(), None),
+        };
+        let mut options = OutputOptions {
+            recipient_address: address.clone(),
+            amount,
+            assets,
+            features: None,
+            unlocks: None,
+            storage_deposit: None,
+        };
+        let mut prepared = account_handle.prepare_output(options.clone(), None).await?;
+
+        if prepared
+            .unlock_conditions()
+            .and_then(|unlock_conditions| unlock_conditions.storage_deposit_return())
+            .is_some()
+        {
+            options.unlocks = Some(Unlocks {
+                expiration_unix_time: Some(expiration_unix_time),
+                timelock_unix_time: None,
+            });
+            prepared = account_handle.prepare_output(options, None).await?;
+        }
+
+        outputs.push(prepared);
+    }
+
+    let required = outputs.iter().map(Output::amount).sum::<u64>();
+    let balance = account_handle.balance().await?;
+
+    if required > balance.base_coin.available {
+        return Err(Error::Miscellaneous(format!(
+            "the batch needs {} including storage deposits but only {} is available",
+            format_amount(required, coin_type),
+            format_amount(balance.base_coin.available, coin_type)
+        )));
+    }
+    for (token_id, amount) in &native_tokens {
+        let available = balance
+            .native_tokens
+            .iter()
+            .find(|native_token| native_token.token_id == *token_id)
+            .map_or_else(U256::zero, |native_token| native_token.available);
+
+        if *amount > available {
+            return Err(Error::Miscellaneous(format!(
+                "the batch sends {amount} of native token {token_id} but only {available} is available"
+            )));
+        }
+    }
+
+    let mut summary = String::new();
+
+    for (index, (address, amount, token_id)) in recipients.iter().enumerate() {
+        let amount = match token_id {
+            Some(token_id) => format!("{amount} of {token_id}"),
+            None => format_amount(amount.as_u64(), coin_type),
+        };
+        summary = format!("{summary}\n{:>4}  {address}  {amount}", index + 1);
+    }
+    summary = format!("{summary}\nTotal: {}", format_amount(base_coin, coin_type));
+    for (token_id, amount) in &native_tokens {
+        summary = format!("{summary}, {amount} of {token_id}");
+    }
+    if required > base_coin {
+        summary = format!(
+            "{summary}\nStorage deposits: {}, returned when claimed or after a day",
+            format_amount(required - base_coin, coin_type)
+        );
+    }
+
+    log::info!("Recipients of \"{path}\":{summary}");
+
+    if !yes
+        && !untimed(|| {
+            Confirm::new()
+                .with_prompt(format!(
+                    "Send to these {} recipients in one transaction?",
+                    recipients.len()
+                ))
+                .interact()
+        })?
+    {
+        log::info!("Batch not sent.");
+        return Ok(());
+    }
+
+    let transaction = account_handle.send(outputs, None).await?;
+
+    transaction_sent(
+        account_handle,
+        "Batch transaction",
+        &transaction,
+        &WaitParameters::default(),
+        output,
+    )
+    .await
+}
+
+// A row of a batch file, as written, with its line or entry number.
+struct BatchRow {
+    line: usize,
+    address: String,
+    amount: String,
+    token_id: Option<String>,
+}
+
+// Reads the rows of a batch, from a JSON array or CSV lines.
+fn read_batch(path: &str) -> Result<Vec<BatchRow>, Error> {
+    let content = std::fs::read_to_string(path)?;
+
+    if path.ends_with(".json") {
+        let rows = serde_json::from_str::<Vec<serde_json::Value>>(&content)?;
+
+        return rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let amount = match &row["amount"] {
+                    serde_json::Value::String(amount) => Some(amount.clone()),
+                    serde_json::Value::Number(amount) => Some(amount.to_string()),
+                    _ => None,
+                };
+
+                match (row["address"].as_str(), amount) {
+                    (Some(address), Some(amount)) => Ok(BatchRow {
+                        line: index + 1,
+                        address: address.to_string(),
+                        amount,
+                        token_id: row["tokenId"].as_str().map(str::to_string),
+                    }),
+                    _ => Err(Error::Miscellaneous(format!(
+                        "entry {}: an address and an amount are needed",
+                        index + 1
+                    ))),
+                }
+            })
+            .collect();
+    }
+
+    let mut rows = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+
+        // Empty lines and a header are skipped.
+        if line.trim().is_empty() || (index == 0 && fields[0].eq_ignore_ascii_case("address")) {
+            continue;
+        }
+
+        match fields[..] {
+            [address, amount] => rows.push(BatchRow {
+                line: index + 1,
+                address: address.to_string(),
+                amount: amount.to_string(),
+                token_id: None,
+            }),
+            [address, amount, token_id] => rows.push(BatchRow {
+                line: index + 1,
+                address: address.to_string(),
+                amount: amount.to_string(),
+                token_id: Some(token_id.to_string()).filter(|token_id| !token_id.is_empty()),
+            }),
+            _ => {
+                return Err(Error::Miscellaneous(format!(
+                    "line {}: expected `address,amount[,token_id]`",
+                    index + 1
+                )));
+            }
+        }
+    }
+
+    Ok(rows)
+}
+
+// `send-micro` command
+pub async fn send_micro_command(
+    account_handle: &AccountHandle,
+    address: String,
+    amount: u64,
+    options: SendOptions,
+    dry_run: bool,
+    wait: WaitParameters,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    check_network(account_handle, &address).await?;
+
+    // With the default options, the output is the one of `send_micro_transaction`.
+    if dry_run {
+        let outputs = vec![send_output(account_handle, &address, amount, &options, true).await?];
+
+        return dry_run_transaction(account_handle, outputs, output).await;
+    }
+
+    let transaction = if options == SendOptions::default() {
+        let outputs = vec![AddressWithMicroAmount {
+            address,
+            amount,
+            return_address: None,
+            expiration: None,
+        }];
+
+        account_handle.send_micro_transaction(outputs, None).await?
+    } else {
+        let outputs = vec![send_output(account_handle, &address, amount, &options, true).await?];
+
+        account_handle.send(outputs, None).await?
+    };
+
+    transaction_sent(account_handle, "Micro transaction", &transaction, &wait, output).await
+}
+
+// Builds the output of `send` or `send-micro` with the unlock conditions and features of the options. A micro output
+// holds the storage deposit on top of the amount, to be returned to the sender by the recipient, and expires after a
+// day unless told otherwise, like the ones of `send_micro_transaction`.
+async fn send_output(
+    account_handle: &AccountHandle,
+    address: &str,
+    amount: u64,
+    options: &SendOptions,
+    micro: bool,
+) -> Result<Output, Error> {
+    let recipient = Address::try_from_bech32(address)?.1;
+    let (return_address, coin_type) = {
+        let account = account_handle.read().await;
+        let first_address = *account
+            .public_addresses()
+            .first()
+            .ok_or_else(|| Error::Miscellaneous("the account has no address".to_string()))?
+            .address()
+            .as_ref();
+
+        (first_address, *account.coin_type())
+    };
+    let return_address = match &options.return_address {
+        Some(return_address) => {
+            check_network(account_handle, return_address).await?;
+            Address::try_from_bech32(return_address)?.1
+        }
+        None => return_address,
+    };
+    let now = u64::from(account_handle.client().get_time_checked()?);
+    let unix_time = |timestamp: u64| {
+        u32::try_from(timestamp)
+            .map_err(|_| Error::Miscellaneous(format!("{} is too far in the future", format_timestamp(timestamp))))
+    };
+    let expiration = match &options.expiration {
+        Some(expiration) => Some(now + parse_duration(expiration)?),
+        None if micro => Some(now + 24 * 60 * 60),
+        None if options.return_address.is_some() => {
+            return Err(Error::Miscellaneous(
+                "`--return-address` needs `--expiration` for outputs holding their whole storage deposit".to_string(),
+            ));
+        }
+        None => None,
+    };
+    let rent_structure = account_handle.client().get_rent_structure()?;
+    let token_supply = account_handle.client().get_token_supply()?;
+    let mut builder = BasicOutputBuilder::new_with_minimum_storage_deposit(rent_structure.clone())?
+        .add_unlock_condition(UnlockCondition::Address(AddressUnlockCondition::new(recipient)));
+
+    if let Some(expiration) = expiration {
+        builder = builder.add_unlock_condition(UnlockCondition::Expiration(ExpirationUnlockCondition::new(
+            return_address,
+            unix_time(expiration)?,
+        )?));
+    }
+
+    if let Some(timelock) = &options.timelock {
+        let timestamp = if timelock.contains('-') {
+            parse_date(timelock)?
+        } else if let Ok(timestamp) = timelock.parse::<u64>() {
+            timestamp
+        } else {
+            now + parse_duration(timelock)?
+        };
+
+        if timestamp <= now {
+            return Err(Error::Miscellaneous(format!(
+                "the timelock, {}, is already over",
+                format_timestamp(timestamp)
+            )));
+        }
+
+        builder = builder.add_unlock_condition(UnlockCondition::Timelock(TimelockUnlockCondition::new(unix_time(
+            timestamp,
+        )?)?));
+    }
+
+    if let Some(tag) = &options.tag {
+        let tag = if tag.starts_with("0x") {
+            prefix_hex::decode(tag).map_err(|e| Error::Miscellaneous(format!("invalid tag: {e}")))?
+        } else {
+            tag.as_bytes().to_vec()
+        };
+
+        builder = builder.add_feature(Feature::Tag(TagFeature::new(tag)?));
+    }
+
+    if let Some(metadata) = &options.metadata {
+        let metadata = prefix_hex::decode::<Vec<u8>>(metadata)
+            .map_err(|e| Error::Miscellaneous(format!("invalid metadata, expected 0x prefixed hex: {e}")))?;
+
+        builder = builder.add_feature(Feature::Metadata(MetadataFeature::new(metadata)?));
+    }
+
+    if micro {
+        // The return amount doesn't change the size of the output, so a placeholder gives its storage deposit.
+        let storage_deposit = builder
+            .clone()
+            .add_unlock_condition(UnlockCondition::StorageDepositReturn(
+                StorageDepositReturnUnlockCondition::new(return_address, 1, token_supply)?,
+            ))
+            .finish_output(token_supply)?
+            .amount();
+
+        return Ok(builder
+            .with_amount(amount + storage_deposit)?
+            .add_unlock_condition(UnlockCondition::StorageDepositReturn(
+                StorageDepositReturnUnlockCondition::new(return_address, storage_deposit, token_supply)?,
+            ))
+            .finish_output(token_supply)?);
+    }
+
+    let minimum_amount = builder.clone().finish_output(token_supply)?.amount();
+
+    if amount < minimum_amount {
+        return Err(Error::Miscellaneous(format!(
+            "{} is below the minimum storage deposit of {} for this output, use `send-micro` instead",
+            format_amount(amount, coin_type),
+            format_amount(minimum_amount, coin_type)
+        )));
+    }
+
+    Ok(builder.with_amount(amount)?.finish_output(token_supply)?)
+}
+
+// `send-native-token` command
+#[allow(clippy::too_many_arguments)]
+pub async fn send_native_token_command(
+    account_handle: &AccountHandle,
+    address: String,
+    token_id: String,
+    amount: String,
+    gift_storage_deposit: Option<bool>,
+    dry_run: bool,
+    wait: WaitParameters,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    check_network(account_handle, &address).await?;
+
+    let transaction = if gift_storage_deposit.unwrap_or(false) {
+        // Send native tokens together with the required storage deposit
+        let rent_structure = account_handle.client().get_rent_structure()?;
+        let token_supply = account_handle.client().get_token_supply()?;
+
+        let outputs = vec![
+            BasicOutputBuilder::new_with_minimum_storage_deposit(rent_structure)?
+                .add_unlock_condition(UnlockCondition::Address(AddressUnlockCondition::new(
+                    Address::try_from_bech32(address)?.1,
+                )))
+                .with_native_tokens(vec![NativeToken::new(
+                    TokenId::from_str(&token_id)?,
+                    U256::from_dec_str(&amount).map_err(|e| Error::Miscellaneous(e.to_string()))?,
+                )?])
+                .finish_output(token_supply)?,
+        ];
+
+        if dry_run {
+            return dry_run_transaction(account_handle, outputs, output).await;
+        }
+
+        account_handle.send(outputs, None).await?
+    } else if dry_run {
+        // The output of `send_native_tokens`, whose storage deposit is returned, or reclaimed after a day.
+        let outputs = vec![
+            account_handle
+                .prepare_output(
+                    OutputOptions {
+                        recipient_address: address,
+                        amount: 0,
+                        assets: Some(Assets {
+                            native_tokens: Some(vec![NativeToken::new(
+                                TokenId::from_str(&token_id)?,
+                                U256::from_dec_str(&amount).map_err(|e| Error::Miscellaneous(e.to_string()))?,
+                            )?]),
+                            nft_id: None,
+                        }),
+                        features: None,
+                        unlocks: Some(Unlocks {
+                            expiration_unix_time: Some(account_handle.client().get_time_checked()? + 24 * 60 * 60),
+                            timelock_unix_time: None,
+                        }),
+                        storage_deposit: None,
+                    },
+                    None,
+                )
+                .await?,
+        ];
+
+        return dry_run_transaction(account_handle, outputs, output).await;
+    } else {
+        // Send native tokens with storage deposit return and expiration
+        let outputs = vec![AddressNativeTokens {
+            address,
+            native_tokens: vec![(
+                TokenId::from_str(&token_id)?,
+                U256::from_dec_str(&amount).map_err(|e| Error::Miscellaneous(e.to_string()))?,
+            )],
+            ..Default::default()
+        }];
+        account_handle.send_native_tokens(outputs, None).await?
+    };
+
+    transaction_sent(account_handle, "Native token transaction", &transaction, &wait, output).await
+}
+
+// `send-nft` command
+pub async fn send_nft_command(
+    account_handle: &AccountHandle,
+    address: String,
+    nft_id: String,
+    dry_run: bool,
+    wait: WaitParameters,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    check_network(account_handle, &address).await?;
+
+    if dry_run {
+        let nft_id = NftId::from_str(&nft_id)?;
+        // The NFT output of `send_nft`, with only the unlock condition changed.
+        let nft_output = account_handle
+            .unspent_outputs(None)
+            .await?
+            .into_iter()
+            .find_map(|output_data| match output_data.output {
+                Output::Nft(nft) if nft.nft_id().or_from_output_id(output_data.output_id) == nft_id => Some(nft),
+                _ => None,
+            })
+            .ok_or_else(|| Error::Miscellaneous(format!("the account doesn't own NFT {nft_id}")))?;
+        let outputs = vec![NftOutputBuilder::from(&nft_output)
+            .with_nft_id(nft_id)
+            .with_unlock_conditions(vec![UnlockCondition::Address(AddressUnlockCondition::new(
+                Address::try_from_bech32(&address)?.1,
+            ))])
+            .finish_output(account_handle.client().get_token_supply()?)?];
+
+        return dry_run_transaction(account_handle, outputs, output).await;
+    }
+
+    let outputs = vec![AddressAndNftId {
+        address,
+        nft_id: NftId::from_str(&nft_id)?,
+    }];
+    let transaction = account_handle.send_nft(outputs, None).await?;
+
+    transaction_sent(account_handle, "Nft transaction", &transaction, &wait, output).await
+}
+
+// `sync` command
+pub async fn sync_command(account_handle: &AccountHandle, output: OutputFormat) -> Result<(), Error> {
+    let before = SyncState::new(account_handle).await;
+    let balance = timed(Phase::Node, account_handle.sync(None)).await?;
+    let after = SyncState::new(account_handle).await;
+
+    record_balance(account_handle).await?;
+
+    let participation_transaction = maintain_participation(account_handle).await?;
+    let new_outputs = after.outputs.difference(&before.outputs).count();
+    let spent_outputs = before.unspent_outputs.difference(&after.unspent_outputs).count();
+    let confirmed_transactions = before
+        .pending_transactions
+        .iter()
+        .filter(|transaction_id| after.confirmed_transactions.contains(transaction_id))
+        .count();
+    let used_addresses = after.used_addresses.difference(&before.used_addresses).count();
+    let new_addresses = after.addresses.saturating_sub(before.addresses);
+
+    if output == OutputFormat::Json {
+        print_json(&serde_json::json!({
+            "newOutputs": new_outputs,
+            "spentOutputs": spent_outputs,
+            "confirmedTransactions": confirmed_transactions,
+            "newlyUsedAddresses": used_addresses,
+            "newAddresses": new_addresses,
+            "balance": {
+                "total": balance.base_coin.total,
+                "available": balance.base_coin.available,
+            },
+            "participationTransaction": participation_transaction.as_ref().map(|transaction| serde_json::json!({
+                "transactionId": transaction.transaction_id.to_string(),
+                "blockId": transaction.block_id.map(|block_id| block_id.to_string()),
+            })),
+        }))?;
+    } else {
+        log::info!(
+            "Synced: {new_outputs} new output(s), {spent_outputs} output(s) spent, {confirmed_transactions} \
+             transaction(s) confirmed, {used_addresses} address(es) newly used, {new_addresses} new address(es).\n\
+             Balance: {} total, {} available",
+            format_amount(balance.base_coin.total, after.coin_type),
+            format_amount(balance.base_coin.available, after.coin_type)
+        );
+    }
+
+    print_expiration_warnings(account_handle).await?;
+    report_deposits(account_handle).await?;
+    report_invoices(account_handle).await?;
+
+    if let Some(transaction) = participation_transaction {
+        log::info!(
+            "Participation transaction sent:\ntransaction id: {}\n{:?}",
+            transaction.transaction_id,
+            transaction.block_id
+        );
+    }
+
+    Ok(())
+}
+
+// What a sync can change in an account.
+struct SyncState {
+    coin_type: u32,
+    addresses: usize,
+    used_addresses: HashSet<Address>,
+    outputs: HashSet<OutputId>,
+    unspent_outputs: HashSet<OutputId>,
+    pending_transactions: HashSet<TransactionId>,
+    confirmed_transactions: HashSet<TransactionId>,
+}
+
+impl SyncState {
+    async fn new(account_handle: &AccountHandle) -> Self {
+        let account = account_handle.read().await;
+        let addresses = account.public_addresses().iter().chain(account.internal_addresses());
+
+        Self {
+            coin_type: *account.coin_type(),
+            addresses: addresses.clone().count(),
+            used_addresses: addresses
+                .filter(|address| *address.used())
+                .map(|address| *address.address().as_ref())
+                .collect(),
+            outputs: account.outputs().keys().copied().collect(),
+            unspent_outputs: account.unspent_outputs().keys().copied().collect(),
+            pending_transactions: account.pending_transactions().clone(),
+            confirmed_transactions: account
+                .transactions()
+                .values()
+                .filter(|transaction| transaction.inclusion_state == InclusionState::Confirmed)
+                .map(|transaction| transaction.transaction_id)
+                .collect(),
+        }
+    }
+}
+
+/// `transaction` command
+pub async fn transaction_command(account_handle: &AccountHandle, transaction_id: String) -> Result<(), Error> {
+    let transaction_id = TransactionId::from_str(&transaction_id)?;
+
+    let Some(transaction) = account_handle.get_transaction(&transaction_id).await else {
+        log::info!("Transaction not found");
+        return Ok(());
+    };
+
+    let (summary, _, _) = format_transaction(account_handle, &transaction).await?;
+    // The inputs of incoming transactions aren't outputs of the account, they're kept with the transaction if not
+    // pruned.
+    let incoming_inputs = account_handle
+        .get_incoming_transaction_data(&transaction_id)
+        .await
+        .map(|(_, inputs)| inputs)
+        .unwrap_or_default();
+    let context = output_context(account_handle).await?;
+    let account = account_handle.read().await;
+    let TransactionEssence::Regular(essence) = transaction.payload.essence();
+    let mut log = format!("{summary}\n  Inputs:");
+
+    for input in essence.inputs() {
+        let Input::Utxo(input) = input else {
+            continue;
+        };
+        let output_id = input.output_id();
+        let output = account
+            .outputs()
+            .get(output_id)
+            .map(|output_data| output_data.output.clone())
+            .or_else(|| {
+                incoming_inputs
+                    .iter()
+                    .find(|input| {
+                        input.metadata.transaction_id == output_id.transaction_id().to_string()
+                            && input.metadata.output_index == output_id.index()
+                    })
+                    .and_then(|input| Output::try_from_dto_unverified(&input.output).ok())
+            });
+
+        log = match output {
+            Some(output) => format!("{log}\n{}", format_output(output_id, &output, &context)),
+            None => format!("{log}\n    {output_id}: unknown, not an output of the account or pruned"),
+        };
+    }
+
+    log = format!("{log}\n  Outputs:");
+
+    for (index, output) in essence.outputs().iter().enumerate() {
+        let output_id = OutputId::new(transaction_id, index as u16)?;
+        log = format!("{log}\n{}", format_output(&output_id, output, &context));
+    }
+
+    if let Some(Payload::TaggedData(tagged_data)) = essence.payload() {
+        log = format!(
+            "{log}\n  Tag: {}\n  Data: {}",
+            format_bytes(tagged_data.tag()),
+            format_bytes(tagged_data.data())
+        );
+    }
+
+    print_paged(&log);
+
+    Ok(())
+}
+
+/// `transactions` command
+pub async fn transactions_command(
+    account_handle: &AccountHandle,
+    tui: bool,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    let transactions = account_handle.transactions().await?;
+
+    if output == OutputFormat::Json {
+        let mut json = Vec::new();
+
+        for transaction in &transactions {
+            let changes = transaction_changes(account_handle, transaction).await?;
+            let transfer = changes.transfer();
+
+            json.push(serde_json::json!({
+                "transactionId": transaction.transaction_id.to_string(),
+                "blockId": transaction.block_id.map(|block_id| block_id.to_string()),
+                "inclusionState": format!("{:?}", transaction.inclusion_state),
+                "timestamp": (transaction.timestamp / 1000) as u64,
+                "direction": changes.direction.as_str(),
+                // Beyond the range of JSON numbers in many parsers, as strings like the native token amounts.
+                "netChange": changes.net.to_string(),
+                "transfer": transfer.to_string(),
+                "counterparts": changes.counterparts,
+                "nativeTokens": changes
+                    .native_tokens
+                    .iter()
+                    .map(|(token_id, (received, sent))| serde_json::json!({
+                        "tokenId": token_id.to_string(),
+                        "received": received.to_string(),
+                        "sent": sent.to_string(),
+                    }))
+                    .collect::<Vec<_>>(),
+                "nftsReceived": changes.nfts_in.difference(&changes.nfts_out).map(ToString::to_string).collect::<Vec<_>>(),
+                "nftsSent": changes.nfts_out.difference(&changes.nfts_in).map(ToString::to_string).collect::<Vec<_>>(),
+                "note": transaction.note,
+            }));
+        }
+
+        print_json(&serde_json::Value::from(json))?;
+    } else if transactions.is_empty() {
+        log::info!("No transactions found");
+    } else if tui {
+        let mut rows = Vec::new();
+
+        for tx in transactions {
+            let (details, net, direction) = format_transaction(account_handle, &tx).await?;
+
+            rows.push(TransactionRow {
+                timestamp: (tx.timestamp / 1000) as u64,
+                direction,
+                state: format!("{:?}", tx.inclusion_state),
+                net,
+                transaction_id: tx.transaction_id.to_string(),
+                details,
+            });
+        }
+
+        transactions_table(rows)?;
+    } else {
+        let mut log = Vec::new();
+
+        for tx in transactions {
+            log.push(format_transaction(account_handle, &tx).await?.0);
+        }
+
+        print_paged(&log.join("\n"));
+    }
+
+    Ok(())
+}
+
+/// `unspent-outputs` command
+pub async fn unspent_outputs_command(account_handle: &AccountHandle) -> Result<(), Error> {
+    let outputs = account_handle.unspent_outputs(None).await?;
+
+    if outputs.is_empty() {
+        log::info!("No outputs found");
+    } else {
+        print_paged(&format!(
+            "Unspent outputs:{}",
+            format_output_list(account_handle, &outputs).await?
+        ));
+    }
+
+    Ok(())
+}
+
+// `verify-address` command
+pub async fn verify_address_command(
+    manager: &AccountManager,
+    account_handle: &AccountHandle,
+    address: String,
+    gap: u32,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    check_network(account_handle, &address).await?;
+
+    let ed25519_address = Address::try_from_bech32(&address)?.1;
+
+    if !matches!(ed25519_address, Address::Ed25519(_)) {
+        return Err(Error::Miscellaneous(format!(
+            "{address} isn't an Ed25519 address, the only kind derived from the keys of an account"
+        )));
+    }
+
+    let (coin_type, account_index, generated, next_indexes) = {
+        let account = account_handle.read().await;
+        let next_index = |addresses: &[AccountAddress]| {
+            addresses
+                .iter()
+                .map(|address| address.key_index() + 1)
+                .max()
+                .unwrap_or_default()
+        };
+        let generated = account
+            .public_addresses()
+            .iter()
+            .chain(account.internal_addresses())
+            .find(|account_address| *account_address.address().as_ref() == ed25519_address)
+            .map(|account_address| {
+                (
+                    *account_address.key_index(),
+                    *account_address.internal(),
+                    *account_address.used(),
+                )
+            });
+
+        (
+            *account.coin_type(),
+            *account.index(),
+            generated,
+            [
+                (false, next_index(account.public_addresses())),
+                (true, next_index(account.internal_addresses())),
+            ],
+        )
+    };
+
+    let (key_index, internal, used) = match generated {
+        Some((key_index, internal, used)) => (key_index, internal, Some(used)),
+        None => {
+            let mut derived = None;
+
+            // Addresses the wallet hasn't generated yet, e.g. handed out by another wallet sharing the mnemonic.
+            for (internal, next_index) in next_indexes {
+                let addresses = timed(
+                    Phase::SecretManager,
+                    manager.get_secret_manager().read().await.generate_addresses(
+                        coin_type,
+                        account_index,
+                        next_index..next_index + gap,
+                        internal,
+                        None,
+                    ),
+                )
+                .await?;
+
+                if let Some(position) = addresses.iter().position(|derived| *derived == ed25519_address) {
+                    derived = Some((next_index + position as u32, internal, None));
+                    break;
+                }
+            }
+
+            derived.ok_or_else(|| {
+                Error::Miscellaneous(format!(
+                    "{address} isn't an address of the account, neither a generated one nor one of the next {gap} \
+                     public and change addresses"
+                ))
+            })?
+        }
+    };
+
+    match output {
+        OutputFormat::Text => {
+            log::info!(
+                "{address} belongs to the account: {} address {key_index}{}",
+                if internal { "change" } else { "public" },
+                match used {
+                    Some(true) => ", used",
+                    Some(false) => ", unused",
+                    None => ", not generated by the wallet yet",
+                }
+            );
+
+            Ok(())
+        }
+        OutputFormat::Json => print_json(&serde_json::json!({
+            "address": address,
+            "keyIndex": key_index,
+            "internal": internal,
+            "generated": used.is_some(),
+            "used": used.unwrap_or_default(),
+        })),
+    }
+}
+
+/// `vest` command
+pub async fn vest_command(
+    account_handle: &AccountHandle,
+    address: String,
+    total: u64,
+    installments: u32,
+    interval: String,
+) -> Result<(), Error> {
+    check_network(account_handle, &address).await?;
+    check_no_job(*account_handle.read().await.index())?;
+
+    if installments == 0 {
+        return Err(Error::Miscellaneous("at least one installment is needed".to_string()));
+    }
+
+    let interval = parse_duration(&interval)?;
+    let coin_type = *account_handle.read().await.coin_type();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let installment = total / u64::from(installments);
+    let mut schedule = Vec::new();
+    let mut table = format!("{:>4}  {:<23}  {:>20}  {:>20}", "#", "Unlocks on", "Amount", "Vested");
+    let mut vested = 0;
+
+    for index in 1..=installments {
+        // The last installment takes what the division leaves.
+        let amount = if index == installments {
+            total - vested
+        } else {
+            installment
+        };
+        let timestamp = now + interval * u64::from(index);
+
+        // Checked before sending anything.
+        vesting_output(account_handle, &address, amount, timestamp).await?;
+
+        schedule.push((amount, timestamp));
+        vested += amount;
+        table = format!(
+            "{table}\n{index:>4}  {:<23}  {:>20}  {:>20}",
+            format_timestamp(timestamp),
+            format_amount(amount, coin_type),
+            format_amount(vested, coin_type)
+        );
+    }
+
+    log::info!("Vesting schedule to {address}:\n{table}");
+
+    send_vesting(account_handle, address, schedule, None).await
+}
+
+// Builds a vesting installment, a timelocked output with the first address of the account as sender.
+async fn vesting_output(
+    account_handle: &AccountHandle,
+    address: &str,
+    amount: u64,
+    timestamp: u64,
+) -> Result<Output, Error> {
+    let recipient = Address::try_from_bech32(address)?.1;
+    let (sender, coin_type) = {
+        let account = account_handle.read().await;
+        let sender = *account
+            .public_addresses()
+            .first()
+            .ok_or_else(|| Error::Miscellaneous("the account has no address".to_string()))?
+            .address()
+            .as_ref();
+
+        (sender, *account.coin_type())
+    };
+    let rent_structure = account_handle.client().get_rent_structure()?;
+    let token_supply = account_handle.client().get_token_supply()?;
+    let builder = BasicOutputBuilder::new_with_minimum_storage_deposit(rent_structure)?
+        .add_unlock_condition(UnlockCondition::Address(AddressUnlockCondition::new(recipient)))
+        .add_unlock_condition(UnlockCondition::Timelock(TimelockUnlockCondition::new(
+            u32::try_from(timestamp).map_err(|_| {
+                Error::Miscellaneous(format!(
+                    "an installment unlocks too far in the future, on {}",
+                    format_timestamp(timestamp)
+                ))
+            })?,
+        )?))
+        .add_feature(Feature::Sender(SenderFeature::new(sender)));
+    let minimum_amount = builder.clone().finish_output(token_supply)?.amount();
+
+    if amount < minimum_amount {
+        return Err(Error::Miscellaneous(format!(
+            "installments of {} are below the minimum storage deposit of {}, use fewer installments",
+            format_amount(amount, coin_type),
+            format_amount(minimum_amount, coin_type)
+        )));
+    }
+
+    Ok(builder.with_amount(amount)?.finish_output(token_supply)?)
+}
+
+// Sends vesting installments in as many transactions as needed, once the transaction of the pending block is
+// confirmed, recording the progress to resume it if interrupted.
+async fn send_vesting(
+    account_handle: &AccountHandle,
+    address: String,
+    mut installments: Vec<(u64, u64)>,
+    mut pending_block_id: Option<String>,
+) -> Result<(), Error> {
+    let account_index = *account_handle.read().await.index();
+
+    while !installments.is_empty() {
+        set_job(
+            account_index,
+            Some(&Job::Vest {
+                address: address.clone(),
+                installments: installments.clone(),
+                pending_block_id: pending_block_id.clone(),
+            }),
+        )?;
+
+        // The next transaction can only spend the remainder of the previous one once it is confirmed.
+        if let Some(block_id) = pending_block_id.take() {
+            let block_id = BlockId::from_str(&block_id)?;
+
+            timed(Phase::Node, account_handle.retry_until_included(&block_id, None, None)).await?;
+            timed(Phase::Node, account_handle.sync(None)).await?;
+        }
+
+        if interrupted() {
+            log::warn!(
+                "Vesting interrupted, {} installment(s) left to send, run `resume` to continue.",
+                installments.len()
+            );
+            return Ok(());
+        }
+
+        // Leaves room for the remainder and other outputs below the maximum of 128 outputs of a transaction.
+        let chunk = installments.drain(..installments.len().min(100)).collect::<Vec<_>>();
+        let mut outputs = Vec::new();
+
+        for (amount, timestamp) in chunk {
+            outputs.push(vesting_output(account_handle, &address, amount, timestamp).await?);
+        }
+
+        let transaction = account_handle.send(outputs, None).await?;
+
+        log::info!(
+            "Vesting transaction sent:\ntransaction id: {}\n{:?}",
+            transaction.transaction_id,
+            transaction.block_id
+        );
+
+        pending_block_id = transaction.block_id.map(|block_id| block_id.to_string());
+    }
+
+    set_job(account_index, None)
+}
+
+/// `vesting` command
+pub async fn vesting_command(account_handle: &AccountHandle, command: VestingCommand) -> Result<(), Error> {
+    match command {
+        VestingCommand::Status => vesting_status(account_handle).await,
+    }
+}
+
+// Timelocked outputs of the account, grouped by sender as vesting schedules.
+async fn vesting_status(account_handle: &AccountHandle) -> Result<(), Error> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs()) as u32;
+    let (bech32_hrp, coin_type) = {
+        let account = account_handle.read().await;
+        (
+            account
+                .public_addresses()
+                .first()
+                .map(|address| address.address().bech32_hrp().to_string())
+                .unwrap_or_default(),
+            *account.coin_type(),
+        )
+    };
+    let mut schedules = BTreeMap::<String, Vec<(u32, u64, OutputId)>>::new();
+
+    for output_data in account_handle.unspent_outputs(None).await? {
+        let Some(timelock) = output_data
+            .output
+            .unlock_conditions()
+            .and_then(|unlock_conditions| unlock_conditions.timelock())
+        else {
+            continue;
+        };
+        let sender = output_data
+            .output
+            .features()
+            .and_then(|features| features.sender())
+            .map_or_else(
+                || "unknown sender".to_string(),
+                |sender| sender.address().to_bech32(&bech32_hrp),
+            );
+
+        schedules.entry(sender).or_default().push((
+            timelock.timestamp(),
+            output_data.output.amount(),
+            output_data.output_id,
+        ));
+    }
+
+    if schedules.is_empty() {
+        log::info!("No vesting schedules, the account has no timelocked outputs.");
+        return Ok(());
+    }
+
+    for (sender, mut installments) in schedules {
+        installments.sort();
+
+        let total = installments.iter().map(|(_, amount, _)| amount).sum::<u64>();
+        let vested = installments
+            .iter()
+            .filter(|(timestamp, _, _)| *timestamp <= now)
+            .map(|(_, amount, _)| amount)
+            .sum::<u64>();
+        let mut log = format!(
+            "From {sender}: {} vested of {}",
+            format_amount(vested, coin_type),
+            format_amount(total, coin_type)
+        );
+
+        if let Some((timestamp, amount, _)) = installments.iter().find(|(timestamp, _, _)| *timestamp > now) {
+            log = format!(
+                "{log}, next {} on {}",
+                format_amount(*amount, coin_type),
+                format_timestamp((*timestamp).into())
+            );
+        }
+
+        for (timestamp, amount, output_id) in &installments {
+            let state = if *timestamp <= now {
+                "vested, to claim"
+            } else {
+                "locked"
+            };
+
+            log = format!(
+                "{log}\n  {:<23}  {:>20}  {state:<16}  {output_id}",
+                format_timestamp((*timestamp).into()),
+                format_amount(*amount, coin_type)
+            );
+        }
+
+        log::info!("{log}");
+    }
+
+    log::info!("Vested installments are kept with the `claim --all` command.");
+
+    Ok(())
+}
+
+/// `voting-power` command
+pub async fn voting_power_command(account_handle: &AccountHandle, command: VotingPowerCommand) -> Result<(), Error> {
+    let coin_type = *account_handle.read().await.coin_type();
+    let (transaction, voting_power) = match command {
+        VotingPowerCommand::Decrease { amount } => decrease_voting_power(account_handle, amount).await?,
+        VotingPowerCommand::Increase { amount } => increase_voting_power(account_handle, amount).await?,
+    };
+
+    log::info!(
+        "Voting power transaction sent:\ntransaction id: {}\n{:?}\nVoting power: {} once confirmed, {} currently",
+        transaction.transaction_id,
+        transaction.block_id,
+        format_amount(voting_power, coin_type),
+        format_amount(current_voting_power(account_handle).await?, coin_type)
+    );
+
+    Ok(())
+}
+
+/// `why-failed` command
+pub async fn why_failed_command(account_handle: &AccountHandle, transaction_id: String) -> Result<(), Error> {
+    let transaction_id = TransactionId::from_str(&transaction_id)?;
+
+    let Some(transaction) = account_handle.get_transaction(&transaction_id).await else {
+        log::info!("Transaction not found");
+        return Ok(());
+    };
+
+    if transaction.inclusion_state == InclusionState::Confirmed {
+        log::info!("Transaction {transaction_id} was confirmed, it didn't fail.");
+        return Ok(());
+    }
+
+    let client = account_handle.client();
+    let mut reasons = Vec::new();
+
+    // What the node knows of the block carrying the transaction.
+    match transaction.block_id {
+        Some(block_id) => match client.get_block_metadata(&block_id).await {
+            Ok(metadata) => {
+                let milestone = metadata.referenced_by_milestone_index;
+
+                if let Some(reason) = metadata.conflict_reason.filter(|reason| *reason != 0) {
+                    reasons.push(format!(
+                        "its block {block_id} was referenced by milestone {} but the transaction failed the ledger \
+                         checks: {}",
+                        milestone.map_or_else(|| "?".to_string(), |milestone| milestone.to_string()),
+                        conflict_reason_explanation(reason)
+                    ));
+                } else if metadata.should_reattach == Some(true) {
+                    reasons.push(format!(
+                        "its block {block_id} is below max depth, its parents are too old for a milestone to reference \
+                         it, the transaction must be reattached in a new block"
+                    ));
+                } else if milestone.is_none() {
+                    reasons.push(format!(
+                        "its block {block_id} isn't referenced by a milestone yet{}",
+                        if metadata.should_promote == Some(true) {
+                            ", it should be promoted"
+                        } else {
+                            ""
+                        }
+                    ));
+                }
+            }
+            Err(ClientError::NotFound(_)) => reasons.push(format!(
+                "the node doesn't know its block {block_id}, it was never propagated or has been pruned"
+            )),
+            Err(e) => return Err(e.into()),
+        },
+        None => reasons.push("it was never sent in a block".to_string()),
+    }
+
+    // Inputs spent by other transactions are the usual cause of conflicts, double spends or concurrent sends.
+    let TransactionEssence::Regular(essence) = transaction.payload.essence();
+    let local_transactions = account_handle.transactions().await?;
+
+    for input in essence.inputs() {
+        let Input::Utxo(input) = input else {
+            continue;
+        };
+        let output_id = input.output_id();
+
+        match client.get_output_metadata(output_id).await {
+            Ok(metadata) if metadata.is_spent => {
+                let spent_by = metadata.transaction_id_spent.unwrap_or_default();
+
+                if spent_by != transaction_id.to_string() {
+                    let of_account = local_transactions
+                        .iter()
+                        .any(|transaction| transaction.transaction_id.to_string() == spent_by);
+
+                    reasons.push(format!(
+                        "its input {output_id} was already spent by transaction {spent_by}{}",
+                        if of_account { " of this account" } else { "" }
+                    ));
+                }
+            }
+            Ok(_) => {}
+            Err(ClientError::NotFound(_)) => reasons.push(format!(
+                "its input {output_id} isn't on the ledger, it never existed or was spent and pruned"
+            )),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    if reasons.is_empty() {
+        reasons.push(
+            "no cause was found, its inputs are unspent and its block is known, it may still be included".to_string(),
+        );
+    }
+
+    log::info!(
+        "Transaction {transaction_id} is {:?}:\n{}",
+        transaction.inclusion_state,
+        reasons
+            .iter()
+            .map(|reason| format!("- {reason}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+
+    Ok(())
+}
+
+// Explains the conflict reason of a block, as reported by the node.
+fn conflict_reason_explanation(reason: u8) -> &'static str {
+    match ConflictReason::try_from(reason) {
+        Ok(ConflictReason::None) => "none",
+        Ok(ConflictReason::InputUtxoAlreadySpent) => "an input was already spent",
+        Ok(ConflictReason::InputUtxoAlreadySpentInThisMilestone) => {
+            "an input was spent by another transaction confirmed by the same milestone"
+        }
+        Ok(ConflictReason::InputUtxoNotFound) => "an input doesn't exist",
+        Ok(ConflictReason::CreatedConsumedAmountMismatch) => "the amounts of the inputs and outputs don't match",
+        Ok(ConflictReason::InvalidSignature) => "a signature is invalid",
+        Ok(ConflictReason::TimelockNotExpired) => "an input is still timelocked",
+        Ok(ConflictReason::InvalidNativeTokens) => "the native tokens of the inputs and outputs don't match",
+        Ok(ConflictReason::StorageDepositReturnUnfulfilled) => "a storage deposit return wasn't fulfilled",
+        Ok(ConflictReason::InvalidUnlock) => "an input was unlocked incorrectly",
+        Ok(ConflictReason::InputsCommitmentsMismatch) => "the inputs commitment doesn't match the inputs",
+        Ok(ConflictReason::UnverifiedSender) => "a sender feature isn't unlocked by the transaction",
+        Ok(ConflictReason::InvalidChainStateTransition) => {
+            "an alias, foundry or NFT output was transitioned incorrectly"
+        }
+        Ok(ConflictReason::SemanticValidationFailed) | Err(_) => "the semantic validation failed",
+    }
+}
+
+// Aliases of the account, with the address controlling each one.
+async fn owned_aliases(account_handle: &AccountHandle) -> Result<Vec<(AliasId, Address)>, Error> {
+    Ok(account_handle
+        .unspent_outputs(None)
+        .await?
+        .into_iter()
+        .filter_map(|output_data| match &output_data.output {
+            Output::Alias(alias) => Some((
+                alias.alias_id().or_from_output_id(output_data.output_id),
+                output_data.address,
+            )),
+            _ => None,
+        })
+        .collect())
+}
+
+// Parses an alias given as an alias id or as a bech32 alias address.
+fn parse_alias_id(alias: &str) -> Result<AliasId, Error> {
+    if alias.starts_with("0x") {
+        return Ok(AliasId::from_str(alias)?);
+    }
+
+    match Address::try_from_bech32(alias)?.1 {
+        Address::Alias(alias_address) => Ok(*alias_address.alias_id()),
+        _ => Err(Error::Miscellaneous(format!("\"{alias}\" isn't an alias address"))),
+    }
+}
+
+/// Prints the JSON output of a command as is, without the log prefix, to be parsed by scripts.
+pub fn print_json(json: &serde_json::Value) -> Result<(), Error> {
+    println!("{}", serde_json::to_string_pretty(json)?);
+
+    Ok(())
+}
+
+/// Shows the error of a command, as `{"error": "..."}` in JSON.
+pub fn print_error(error: &Error, output: OutputFormat) {
+    match output {
+        OutputFormat::Text => log::error!("{error}"),
+        OutputFormat::Json => println!("{}", serde_json::json!({ "error": error.to_string() })),
+    }
+}
+
+// Shows a sent transaction, and once included when waiting for it.
+async fn transaction_sent(
+    account_handle: &AccountHandle,
+    kind: &str,
+    transaction: &Transaction,
+    wait: &WaitParameters,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    if output == OutputFormat::Text {
+        log::info!(
+            "{kind} sent:\ntransaction id: {}\n{:?}",
+            transaction.transaction_id,
+            transaction.block_id
+        );
+    }
+
+    let inclusion = wait_for_inclusion(account_handle, transaction, wait).await?;
+
+    match output {
+        OutputFormat::Text => {
+            log_inclusion(transaction, inclusion);
+
+            Ok(())
+        }
+        OutputFormat::Json => print_json(&serde_json::json!({
+            "transactionId": transaction.transaction_id.to_string(),
+            "blockId": inclusion.map_or(transaction.block_id, |(block_id, _)| Some(block_id)).map(|block_id| block_id.to_string()),
+            "inclusionState": inclusion.map(|(_, inclusion_state)| format!("{inclusion_state:?}")),
+        })),
+    }
+}
+
+// Releases the inputs locked by the selection of a transaction that isn't sent. The library doesn't expose the locked
+// outputs mutably, so they're removed from the serialized account, as stored in the database.
+async fn unlock_inputs(account_handle: &AccountHandle, inputs: &[InputSigningData]) -> Result<(), Error> {
+    let inputs = inputs
+        .iter()
+        .map(|input| Ok(serde_json::to_value(input.output_id()?)?))
+        .collect::<Result<Vec<_>, Error>>()?;
+    let mut account = account_handle.write().await;
+    let mut serialized = serde_json::to_value(&*account)?;
+
+    if let Some(locked_outputs) = serialized["lockedOutputs"].as_array_mut() {
+        locked_outputs.retain(|output_id| !inputs.contains(output_id));
+    }
+    *account = serde_json::from_value(serialized)?;
+
+    Ok(())
+}
+
+// Shows the transaction that sending the outputs would make, with the inputs selected for it, the storage deposit of
+// each output and the remainder, without signing or sending it.
+async fn dry_run_transaction(
+    account_handle: &AccountHandle,
+    outputs: Vec<Output>,
+    output: OutputFormat,
+) -> Result<(), Error> {
+    let prepared_transaction_data = account_handle.prepare_transaction(outputs, None).await?;
+    // Selecting the inputs locks them until the transaction is sent, which it won't be.
+    unlock_inputs(account_handle, &prepared_transaction_data.inputs_data).await?;
+
+    let rent_structure = account_handle.client().get_rent_structure()?;
+    let bech32_hrp = account_handle.client().get_bech32_hrp()?;
+    let coin_type = *account_handle.read().await.coin_type();
+    let TransactionEssence::Regular(essence) = &prepared_transaction_data.essence;
+    let remainder = prepared_transaction_data
+        .remainder
+        .as_ref()
+        .map(|remainder| &remainder.output);
+    let storage_deposit = essence
+        .outputs()
+        .iter()
+        .filter(|output| Some(*output) != remainder)
+        .map(|output| output.rent_cost(&rent_structure))
+        .sum::<u64>();
+    let owner = |output: &Output| output_owner(output).map(|owner| owner.to_bech32(&bech32_hrp));
+
+    if output == OutputFormat::Json {
+        return print_json(&serde_json::json!({
+            "inputs": prepared_transaction_data
+                .inputs_data
+                .iter()
+                .map(|input| Ok(serde_json::json!({
+                    "outputId": input.output_id()?.to_string(),
+                    "amount": input.output.amount(),
+                    "nativeTokens": input.output.native_tokens().map_or(0, |native_tokens| native_tokens.len()),
+                })))
+                .collect::<Result<Vec<_>, Error>>()?,
+            "outputs": essence
+                .outputs()
+                .iter()
+                .map(|output| serde_json::json!({
+                    "kind": output_kind(output),
+                    "address": owner(output),
+                    "amount": output.amount(),
+                    "storageDeposit": output.rent_cost(&rent_structure),
+                    "remainder": Some(output) == remainder,
+                }))
+                .collect::<Vec<_>>(),
+            "storageDeposit": storage_deposit,
+            "remainder": remainder.map(|remainder| serde_json::json!({
+                "address": owner(remainder),
+                "amount": remainder.amount(),
+            })),
+        }));
+    }
+
+    let mut log = "Dry run, nothing was signed or sent.\nInputs:".to_string();
+
+    for input in &prepared_transaction_data.inputs_data {
+        log = format!(
+            "{log}\n  {}: {}",
+            input.output_id()?,
+            format_amount(input.output.amount(), coin_type)
+        );
+    }
+
+    log = format!("{log}\nOutputs:");
+
+    for output in essence.outputs().iter() {
+        log = format!(
+            "{log}\n  {} output of {} to {}, storage deposit {}{}",
+            output_kind(output),
+            format_amount(output.amount(), coin_type),
+            owner(output).unwrap_or_else(|| "an unknown address".to_string()),
+            format_amount(output.rent_cost(&rent_structure), coin_type),
+            if Some(output) == remainder { " (remainder)" } else { "" }
+        );
+    }
+
+    log = format!(
+        "{log}\nRequired storage deposit: {}",
+        format_amount(storage_deposit, coin_type)
+    );
+
+    log::info!(
+        "{log}\n{}",
+        remainder.map_or_else(
+            || "No remainder".to_string(),
+            |remainder| format!("Remainder: {}", format_amount(remainder.amount(), coin_type))
+        )
+    );
+
+    Ok(())
+}
+
+// Waits for a sent transaction to be included when asked to, returning the block that got it included and its
+// inclusion state, or failing on timeout or conflict.
+async fn wait_for_inclusion(
+    account_handle: &AccountHandle,
+    transaction: &Transaction,
+    wait: &WaitParameters,
+) -> Result<Option<(BlockId, InclusionState)>, Error> {
+    // Seconds between two checks of the block.
+    const INTERVAL: u64 = 5;
+
+    if !wait.wait {
+        return Ok(None);
+    }
+
+    let timeout = parse_duration(wait.timeout.as_deref().unwrap_or("5m"))?;
+    let block_id = transaction
+        .block_id
+        .ok_or_else(|| Error::Miscellaneous("the transaction has no block to wait for".to_string()))?;
+
+    log::info!("Waiting for transaction {} to be included.", transaction.transaction_id);
+
+    let blocks = timed(
+        Phase::Node,
+        account_handle.retry_until_included(&block_id, Some(INTERVAL), Some((timeout / INTERVAL).max(1))),
+    )
+    .await
+    .map_err(|e| {
+        Error::Miscellaneous(format!(
+            "transaction {} wasn't included within {}: {e}",
+            transaction.transaction_id,
+            wait.timeout.as_deref().unwrap_or("5m")
+        ))
+    })?;
+    // The block got included, or a reattachment of it.
+    let included_block_id = blocks.first().map_or(block_id, |(block_id, _)| *block_id);
+
+    timed(
+        Phase::Node,
+        account_handle.sync(Some(SyncOptions {
+            force_syncing: true,
+            ..Default::default()
+        })),
+    )
+    .await?;
+
+    let inclusion_state = account_handle
+        .get_transaction(&transaction.transaction_id)
+        .await
+        .map_or(InclusionState::Pending, |transaction| transaction.inclusion_state);
+
+    if inclusion_state == InclusionState::Conflicting {
+        return Err(Error::Miscellaneous(format!(
+            "transaction {} conflicts, run `why-failed {}` for the reason",
+            transaction.transaction_id, transaction.transaction_id
+        )));
+    }
+
+    Ok(Some((included_block_id, inclusion_state)))
+}
+
+// Logs the inclusion of a transaction waited for.
+fn log_inclusion(transaction: &Transaction, inclusion: Option<(BlockId, InclusionState)>) {
+    if let Some((block_id, inclusion_state)) = inclusion {
+        log::info!(
+            "Transaction {} included in block {block_id}: {inclusion_state:?}",
+            transaction.transaction_id
+        );
+    }
+}
+
+pub fn format_address(
+    address: &AccountAddress,
+    addresses_with_unspent_outputs: &[AddressWithUnspentOutputs],
+) -> String {
+    let mut log = format!("Address {}: {}", address.key_index(), address.address().to_bech32());
+
+    if *address.internal() {
+        log = format!("{log}\nChange address");
+    }
+
+    if let Ok(index) = addresses_with_unspent_outputs
+        .binary_search_by_key(&(address.key_index(), address.internal()), |a| {
+            (a.key_index(), a.internal())
+        })
+    {
+        log = format!(
+            "{log}\nOutputs: {:#?}",
+            addresses_with_unspent_outputs[index].output_ids()
+        );
+    }
+
+    log
+}
+
+/// Whether a transaction received funds from others, sent funds to others, or only moved funds between the addresses of
+/// the account.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TransactionDirection {
+    #[default]
+    Received,
+    Sent,
+    SelfTransfer,
+}
+
+impl TransactionDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Received => "received",
+            Self::Sent => "sent",
+            Self::SelfTransfer => "self-transfer",
+        }
+    }
+}
+
+/// What a transaction changed for the account.
+#[derive(Debug, Default)]
+pub struct TransactionChanges {
+    pub direction: TransactionDirection,
+    /// Change to the base coin balance.
+    pub net: i128,
+    /// Storage deposit to be returned by the receivers.
+    pub returnable: u64,
+    /// Storage deposit to return to the senders when claiming.
+    pub owed: u64,
+    /// Storage deposit locked in and released from NFT, alias, foundry and native token outputs.
+    pub locked: u64,
+    pub released: u64,
+    /// Amounts of each native token received and sent.
+    pub native_tokens: HashMap<TokenId, (U256, U256)>,
+    pub nfts_in: HashSet<NftId>,
+    pub nfts_out: HashSet<NftId>,
+    /// Receivers of outgoing transactions, senders of incoming ones.
+    pub counterparts: Vec<String>,
+}
+
+impl TransactionChanges {
+    /// Base coins transferred, without the storage deposits returned by receivers or to be returned to senders.
+    pub fn transfer(&self) -> i128 {
+        self.net + i128::from(self.returnable) - i128::from(self.owed)
+    }
+}
+
+/// Computes what a transaction changed for the account.
+pub async fn transaction_changes(
+    account_handle: &AccountHandle,
+    transaction: &Transaction,
+) -> Result<TransactionChanges, Error> {
+    let account = account_handle.read().await;
+    let addresses = account
+        .public_addresses()
+        .iter()
+        .chain(account.internal_addresses())
+        .map(|address| *address.address().as_ref())
+        .collect::<HashSet<_>>();
+    let bech32_hrp = account
+        .public_addresses()
+        .first()
+        .map(|address| address.address().bech32_hrp().to_string())
+        .unwrap_or_default();
+    let TransactionEssence::Regular(essence) = transaction.payload.essence();
+    let mut changes = TransactionChanges::default();
+    let mut sent_to_others = false;
+
+    for input in essence.inputs() {
+        if let Input::Utxo(input) = input {
+            if let Some(output_data) = account.outputs().get(input.output_id()) {
+                changes.net -= i128::from(output_data.output.amount());
+
+                if holds_storage_deposit(&output_data.output) {
+                    changes.released += output_data.output.amount();
+                }
+                for native_token in output_data
+                    .output
+                    .native_tokens()
+                    .into_iter()
+                    .flat_map(|native_tokens| native_tokens.iter())
+                {
+                    changes.native_tokens.entry(*native_token.token_id()).or_default().1 += native_token.amount();
+                }
+                if let Output::Nft(output) = &output_data.output {
+                    changes
+                        .nfts_out
+                        .insert(output.nft_id().or_from_output_id(output_data.output_id));
+                }
+            }
+        }
+    }
+
+    for (index, output) in essence.outputs().iter().enumerate() {
+        let storage_deposit_return = output
+            .unlock_conditions()
+            .and_then(|unlock_conditions| unlock_conditions.storage_deposit_return());
+
+        match output_owner(output) {
+            Some(owner) if addresses.contains(owner) => {
+                changes.net += i128::from(output.amount());
+
+                if holds_storage_deposit(output) {
+                    changes.locked += output.amount();
+                }
+                if let Some(storage_deposit_return) = storage_deposit_return
+                    .filter(|unlock_condition| !addresses.contains(unlock_condition.return_address()))
+                {
+                    changes.owed += storage_deposit_return.amount();
+                }
+                for native_token in output
+                    .native_tokens()
+                    .into_iter()
+                    .flat_map(|native_tokens| native_tokens.iter())
+                {
+                    changes.native_tokens.entry(*native_token.token_id()).or_default().0 += native_token.amount();
+                }
+                if let Output::Nft(output) = output {
+                    let output_id = OutputId::new(transaction.transaction_id, index as u16)?;
+                    changes.nfts_in.insert(output.nft_id().or_from_output_id(output_id));
+                }
+            }
+            owner => {
+                sent_to_others = true;
+
+                if let Some(storage_deposit_return) = storage_deposit_return
+                    .filter(|unlock_condition| addresses.contains(unlock_condition.return_address()))
+                {
+                    changes.returnable += storage_deposit_return.amount();
+                }
+                if let Some(owner) = owner {
+                    changes.counterparts.push(owner.to_bech32(&bech32_hrp));
+                }
+            }
+        }
+    }
+
+    // Transactions of the account that don't leave it only move funds between its addresses.
+    changes.direction = if transaction.incoming {
+        TransactionDirection::Received
+    } else if sent_to_others {
+        TransactionDirection::Sent
+    } else {
+        TransactionDirection::SelfTransfer
+    };
+
+    // The senders of incoming transactions are the owners of the inputs, kept with the transaction if not pruned.
+    if transaction.incoming {
+        changes.counterparts.clear();
+
+        if let Some((_, inputs)) = account.incoming_transactions().get(&transaction.transaction_id) {
+            for input in inputs {
+                if let Ok(output) = Output::try_from_dto_unverified(&input.output) {
+                    if let Some(owner) = output_owner(&output) {
+                        changes.counterparts.push(owner.to_bech32(&bech32_hrp));
+                    }
+                }
+            }
+        }
+    }
+
+    changes.counterparts.sort();
+    changes.counterparts.dedup();
+
+    Ok(changes)
+}
+
+/// Formats a transaction, also returning its net change to the balance of the account and its direction.
+pub async fn format_transaction(
+    account_handle: &AccountHandle,
+    transaction: &Transaction,
+) -> Result<(String, i128, TransactionDirection), Error> {
+    let changes = transaction_changes(account_handle, transaction).await?;
+    // Storage deposits returned by receivers, or to be returned to senders, aren't part of the transfer itself.
+    let transfer = changes.transfer();
+    let TransactionChanges {
+        direction,
+        net,
+        returnable,
+        owed,
+        locked,
+        released,
+        native_tokens,
+        nfts_in,
+        nfts_out,
+        counterparts,
+    } = changes;
+    let mut log = format!(
+        "Transaction {} ({})\n  Date: {}\n  State: {:?}",
+        transaction.transaction_id,
+        direction.as_str(),
+        format_timestamp((transaction.timestamp / 1000) as u64),
+        transaction.inclusion_state
+    );
+
+    if let Some(block_id) = transaction.block_id {
+        log = format!("{log}\n  Block: {block_id}");
+    }
+
+    log = format!("{log}\n  Net change: {net:+}, of which transfer: {transfer:+}");
+
+    // Only cached prices, fetched by `report`, so that listing transactions doesn't wait for the network.
+    let price_config = price_config()?;
+
+    if let Some(price) = cached_price(
+        &price_config,
+        *account_handle.read().await.coin_type(),
+        (transaction.timestamp / 1000) as u64,
+    ) {
+        log = format!(
+            "{log}\n  Value: {:.2} {} at the price of the day",
+            transfer.unsigned_abs() as f64 / 1_000_000.0 * price,
+            price_config.currency.to_uppercase()
+        );
+    }
+
+    if returnable > 0 {
+        log = format!("{log}\n    Storage deposit to be returned by the receivers: {returnable}");
+    }
+    if owed > 0 {
+        log = format!("{log}\n    Storage deposit to return to the senders when claiming: {owed}");
+    }
+    if locked > 0 || released > 0 {
+        log = format!(
+            "{log}\n    Storage deposit locked in NFT, alias, foundry and native token outputs: {locked}, released: \
+             {released}"
+        );
+    }
+    if !counterparts.is_empty() {
+        let direction = if transaction.incoming { "From" } else { "To" };
+        log = format!("{log}\n  {direction}: {}", counterparts.join(", "));
+    }
+
+    for (token_id, (received, sent)) in native_tokens {
+        if received > sent {
+            log = format!("{log}\n  Native token {token_id}: +{}", received - sent);
+        } else if sent > received {
+            log = format!("{log}\n  Native token {token_id}: -{}", sent - received);
+        }
+    }
+    for nft_id in nfts_in.difference(&nfts_out) {
+        log = format!("{log}\n  NFT received: {nft_id}");
+    }
+    for nft_id in nfts_out.difference(&nfts_in) {
+        log = format!("{log}\n  NFT sent: {nft_id}");
+    }
+    if let Some(note) = &transaction.note {
+        log = format!("{log}\n  Note: {note}");
+    }
+
+    Ok((log, net, direction))
+}
+
+// What an output is shown against: the addresses of the account, their network and coin.
+struct OutputContext {
+    addresses: HashSet<Address>,
+    bech32_hrp: String,
+    coin_type: u32,
+}
+
+async fn output_context(account_handle: &AccountHandle) -> Result<OutputContext, Error> {
+    let account = account_handle.read().await;
+
+    Ok(OutputContext {
+        addresses: account
+            .public_addresses()
+            .iter()
+            .chain(account.internal_addresses())
+            .map(|address| *address.address().as_ref())
+            .collect(),
+        bech32_hrp: account
+            .public_addresses()
+            .first()
+            .map(|address| address.address().bech32_hrp().to_string())
+            .unwrap_or_default(),
+        coin_type: *account.coin_type(),
+    })
+}
+
+// Lists outputs of the account, one per line with their kind, amount, native tokens and state.
+async fn format_output_list(account_handle: &AccountHandle, outputs: &[OutputData]) -> Result<String, Error> {
+    let context = output_context(account_handle).await?;
+    let mut log = format!(
+        "\n{:<70}  {:<7}  {:>20}  {:>13}  State",
+        "Output ID", "Kind", "Amount", "Native tokens"
+    );
+
+    for output_data in outputs {
+        log = format!(
+            "{log}\n{:<70}  {:<7}  {:>20}  {:>13}  {}",
+            output_data.output_id.to_string(),
+            output_kind(&output_data.output),
+            format_amount(output_data.output.amount(), context.coin_type),
+            output_data
+                .output
+                .native_tokens()
+                .map_or(0, |native_tokens| native_tokens.len()),
+            output_state(account_handle, output_data, &context).await
+        );
+    }
+
+    Ok(log)
+}
+
+// Whether an output of the account can be spent and, if not, why or until when.
+async fn output_state(account_handle: &AccountHandle, output_data: &OutputData, context: &OutputContext) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs()) as u32;
+    let unlock_conditions = output_data.output.unlock_conditions();
+
+    if output_data.is_spent {
+        return "spent".to_string();
+    }
+    if account_handle
+        .read()
+        .await
+        .locked_outputs()
+        .contains(&output_data.output_id)
+    {
+        return "used by a pending transaction".to_string();
+    }
+    if let Some(timelock) = unlock_conditions
+        .and_then(|unlock_conditions| unlock_conditions.timelock())
+        .filter(|timelock| timelock.timestamp() > now)
+    {
+        return format!("timelocked until {}", format_timestamp(timelock.timestamp().into()));
+    }
+    if let Some(expiration) = unlock_conditions
+        .and_then(|unlock_conditions| unlock_conditions.expiration())
+        .filter(|expiration| expiration.timestamp() > now)
+    {
+        return if context.addresses.contains(expiration.return_address()) {
+            format!(
+                "sent, back on {} unless claimed",
+                format_timestamp(expiration.timestamp().into())
+            )
+        } else {
+            format!("to claim before {}", format_timestamp(expiration.timestamp().into()))
+        };
+    }
+    if unlock_conditions
+        .and_then(|unlock_conditions| unlock_conditions.storage_deposit_return())
+        .is_some_and(|storage_deposit_return| !context.addresses.contains(storage_deposit_return.return_address()))
+    {
+        return "to claim".to_string();
+    }
+
+    "available".to_string()
+}
+
+fn output_kind(output: &Output) -> &'static str {
+    match output {
+        Output::Treasury(_) => "treasury",
+        Output::Basic(_) => "basic",
+        Output::Alias(_) => "alias",
+        Output::Foundry(_) => "foundry",
+        Output::Nft(_) => "NFT",
+    }
+}
+
+// Formats an output with its owner, assets, unlock conditions and features, one per line.
+fn format_output(output_id: &OutputId, output: &Output, context: &OutputContext) -> String {
+    let address = |address: &Address| {
+        let bech32 = address.to_bech32(&context.bech32_hrp);
+
+        if context.addresses.contains(address) {
+            format!("{bech32} (this account)")
+        } else {
+            bech32
+        }
+    };
+    let mut log = format!(
+        "    {output_id}: {} output of {}",
+        output_kind(output),
+        format_amount(output.amount(), context.coin_type)
+    );
+
+    if let Some(owner) = output_owner(output) {
+        log = format!("{log}\n      Owner: {}", address(owner));
+    }
+
+    match output {
+        Output::Alias(alias) => {
+            log = format!(
+                "{log}\n      Alias id: {}",
+                alias.alias_id().or_from_output_id(*output_id)
+            );
+        }
+        Output::Foundry(foundry) => log = format!("{log}\n      Foundry id: {}", foundry.id()),
+        Output::Nft(nft) => log = format!("{log}\n      NFT id: {}", nft.nft_id().or_from_output_id(*output_id)),
+        _ => {}
+    }
+
+    for native_token in output
+        .native_tokens()
+        .into_iter()
+        .flat_map(|native_tokens| native_tokens.iter())
+    {
+        log = format!(
+            "{log}\n      Native token {}: {}",
+            native_token.token_id(),
+            native_token.amount()
+        );
+    }
+
+    if let Some(unlock_conditions) = output.unlock_conditions() {
+        if let Some(storage_deposit_return) = unlock_conditions.storage_deposit_return() {
+            log = format!(
+                "{log}\n      Storage deposit of {} to return to {}",
+                format_amount(storage_deposit_return.amount(), context.coin_type),
+                address(storage_deposit_return.return_address())
+            );
+        }
+        if let Some(timelock) = unlock_conditions.timelock() {
+            log = format!(
+                "{log}\n      Timelocked until {}",
+                format_timestamp(timelock.timestamp().into())
+            );
+        }
+        if let Some(expiration) = unlock_conditions.expiration() {
+            log = format!(
+                "{log}\n      Expires on {}, then owned by {}",
+                format_timestamp(expiration.timestamp().into()),
+                address(expiration.return_address())
+            );
+        }
+    }
+
+    let features = output.features().into_iter().flat_map(|features| features.iter());
+    let immutable_features = output
+        .immutable_features()
+        .into_iter()
+        .flat_map(|features| features.iter());
+
+    for (immutable, feature) in features
+        .map(|feature| (false, feature))
+        .chain(immutable_features.map(|feature| (true, feature)))
+    {
+        let feature = match feature {
+            Feature::Sender(sender) => format!("Sender: {}", address(sender.address())),
+            Feature::Issuer(issuer) => format!("Issuer: {}", address(issuer.address())),
+            Feature::Metadata(metadata) => format!("Metadata: {}", format_bytes(metadata.data())),
+            Feature::Tag(tag) => format!("Tag: {}", format_bytes(tag.tag())),
+        };
+
+        log = format!("{log}\n      {}{feature}", if immutable { "Immutable " } else { "" });
+    }
+
+    log
+}
+
+// Shows bytes as text when they're printable UTF-8, hex encoded otherwise.
+fn format_bytes(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) if !text.chars().any(char::is_control) => format!("\"{text}\""),
+        _ => prefix_hex::encode(bytes),
+    }
+}
+
+/// Address owning an output, the state controller for aliases and the controlling alias for foundries.
+pub fn output_owner(output: &Output) -> Option<&Address> {
+    let unlock_conditions = output.unlock_conditions()?;
+
+    unlock_conditions
+        .address()
+        .map(|unlock_condition| unlock_condition.address())
+        .or_else(|| {
+            unlock_conditions
+                .state_controller_address()
+                .map(|unlock_condition| unlock_condition.address())
+        })
+        .or_else(|| {
+            unlock_conditions
+                .immutable_alias_address()
+                .map(|unlock_condition| unlock_condition.address())
+        })
+}
+
+// Outputs whose amount is tied up as a storage deposit rather than freely spendable.
+fn holds_storage_deposit(output: &Output) -> bool {
+    match output {
+        Output::Basic(output) => !output.native_tokens().is_empty(),
+        _ => true,
+    }
+}
+
+// Warns about the received outputs that will soon return to their sender if not claimed.
+async fn print_expiration_warnings(account_handle: &AccountHandle) -> Result<(), Error> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs()) as u32;
+    let addresses = account_handle
+        .addresses()
+        .await?
+        .iter()
+        .map(|address| *address.address().as_ref())
+        .collect::<HashSet<_>>();
+    let mut total = 0;
+
+    for output_data in account_handle.unspent_outputs(None).await? {
+        if let Some(expiration) = output_data
+            .output
+            .unlock_conditions()
+            .and_then(|unlock_conditions| unlock_conditions.expiration())
+        {
+            // Outputs sent by the account itself come back to it on expiration.
+            if expiration.timestamp() > now
+                && expiration.timestamp() - now <= EXPIRATION_WARNING_PERIOD
+                && !addresses.contains(expiration.return_address())
+            {
+                total += output_data.output.amount();
+                log::warn!(
+                    "Output {} of {} returns to its sender on {} unless claimed.",
+                    output_data.output_id,
+                    output_data.output.amount(),
+                    format_timestamp(expiration.timestamp().into())
+                );
+            }
+        }
+    }
+
+    if total > 0 {
+        log::warn!("{total} expire within a week, use the `claim --all` command to keep them.");
+    }
+
+    Ok(())
+}
+
+// Renders `(timestamp, value)` points, sorted by timestamp, as a step chart where each column covers the same duration.
+fn render_chart(points: &[(u64, u64)]) -> String {
+    const WIDTH: u64 = 60;
+    const HEIGHT: u64 = 8;
+    const BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let (start, end) = (points[0].0, points[points.len() - 1].0);
+    let min = points.iter().map(|(_, value)| *value).min().unwrap_or_default();
+    let max = points.iter().map(|(_, value)| *value).max().unwrap_or_default();
+    // Value of each column, the last one recorded at the end of the duration it covers.
+    let columns = (1..=WIDTH)
+        .map(|column| {
+            let time = start + (end - start) * column / WIDTH;
+            points
+                .iter()
+                .take_while(|(timestamp, _)| *timestamp <= time)
+                .last()
+                .map_or(min, |(_, value)| *value)
+        })
+        .collect::<Vec<_>>();
+    // Eighths of a row, a flat history being drawn at half height.
+    let levels = columns
+        .iter()
+        .map(|value| {
+            if max == min {
+                HEIGHT * 4
+            } else {
+                ((u128::from(value - min) * u128::from(HEIGHT * 8 - 1)) / u128::from(max - min)) as u64 + 1
+            }
+        })
+        .collect::<Vec<_>>();
+    let label_width = max.to_string().len();
+    let mut chart = String::new();
+
+    for row in (0..HEIGHT).rev() {
+        let label = match row {
+            _ if row == HEIGHT - 1 => max.to_string(),
+            0 => min.to_string(),
+            _ => String::new(),
+        };
+        let line = levels
+            .iter()
+            .map(|level| BLOCKS[level.saturating_sub(row * 8).min(8) as usize])
+            .collect::<String>();
+
+        chart = format!("{chart}{label:>label_width$} |{line}\n");
+    }
+
+    let (start, end) = (format_timestamp(start), format_timestamp(end));
+
+    format!(
+        "{chart}{:label_width$}  {start}{end:>width$}",
+        "",
+        width = (WIDTH as usize).saturating_sub(start.len())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn command_names_match_subcommands() {
+        let mut named = 0;
+
+        for subcommand in AccountCli::command().get_subcommands() {
+            if let Ok(account_cli) = AccountCli::try_parse_from(["Account:", subcommand.get_name()]) {
+                assert_eq!(account_cli.command.name(), subcommand.get_name());
+                named += 1;
+            }
+        }
+        for command in [
+            vec!["send-native-token", "rms1", "0x00", "10"],
+            vec!["invoice", "status", "1"],
+            vec!["create-alias"],
+            vec!["deposit-l2", "rms1", "0x00", "10"],
+        ] {
+            let account_cli =
+                AccountCli::try_parse_from(std::iter::once("Account:").chain(command.iter().copied())).unwrap();
+            let expected = if command[0] == "create-alias" {
+                "create-alias-output"
+            } else {
+                command[0]
+            };
+            assert_eq!(account_cli.command.name(), expected);
+            named += 1;
+        }
+
+        assert!(named > 20, "only {named} commands checked");
+    }
+}
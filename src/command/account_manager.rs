@@ -1,17 +1,72 @@
 // Copyright 2020-2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{fs::File, io::prelude::*};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::prelude::*,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use clap::{Args, Parser, Subcommand};
+use dialoguer::{Confirm, Password};
+use iota_stronghold::{KeyProvider, Location, SnapshotPath, Stronghold};
 use iota_wallet::{
+    account::{AccountHandle, AddressGenerationOptions, SyncOptions},
     account_manager::AccountManager,
-    iota_client::{constants::SHIMMER_COIN_TYPE, secret::SecretManager, utils::generate_mnemonic},
+    events::types::{WalletEvent, WalletEventType},
+    iota_client::{
+        api::{PreparedTransactionData, PreparedTransactionDataDto},
+        block::{
+            address::{Address, AliasAddress},
+            output::{Output, OutputId},
+            payload::transaction::TransactionEssence,
+            unlock::dto::UnlockDto,
+        },
+        constants::{IOTA_BECH32_HRP, IOTA_COIN_TYPE, IOTA_TESTNET_BECH32_HRP, SHIMMER_BECH32_HRP, SHIMMER_COIN_TYPE},
+        crypto::{
+            hashes::{blake2b::Blake2b256, sha::Sha256, Digest},
+            keys::{bip39::wordlist, pbkdf::PBKDF2_HMAC_SHA512},
+        },
+        db::DatabaseProvider,
+        node_api::indexer::query_parameters::QueryParameter,
+        node_manager::{
+            builder::validate_url,
+            node::{Node, NodeDto},
+        },
+        secret::{
+            mnemonic::MnemonicSecretManager, stronghold::StrongholdSecretManager, SecretManage, SecretManageExt,
+            SecretManager,
+        },
+        utils::generate_mnemonic,
+        Client, Url,
+    },
     ClientOptions,
 };
 use log::LevelFilter;
+use rocksdb::{DBCompressionType, Options, DB};
+use rusqlite::{params, Connection};
+use sharks::{Share, Sharks};
 
-use crate::{error::Error, helper::get_password};
+use crate::{
+    command::account::{output_owner, report_invoices, transaction_changes, AccountCommand, OutputFormat},
+    config::{config, config_get, config_set},
+    deposit::report_deposits,
+    error::Error,
+    helper::{
+        decrypt_with_passphrase, encrypt_with_passphrase, format_amount, format_timestamp, get_password, parse_date,
+        parse_duration, record_balance, storage_path, write_private_file,
+    },
+    job::{interruption, Running},
+    participation::maintain_participation,
+    plugin::node_urls,
+    price::{price_config, set_price_config, PriceConfig},
+    qr::{read_qr_parts, show_qr_parts},
+    signer::ExternalSigner,
+    timings::{timed, Phase},
+};
 
 #[derive(Debug, Clone, Parser)]
 #[clap(version, long_about = None)]
@@ -22,42 +77,557 @@ pub struct AccountManagerCli {
     pub account: Option<String>,
     #[clap(short, long)]
     pub log_level: Option<LevelFilter>,
+    /// Number of days after which a reminder to back up the wallet is shown.
+    #[clap(long, default_value = "30")]
+    pub backup_max_age: u64,
+    /// Where the keys are held: `stronghold` (default), `external:<command>`, a program signing the transactions of
+    /// `send` and giving the addresses of `new`, or `qr`, an air-gapped wallet running `sign-qr`.
+    #[clap(long)]
+    pub secret_manager: Option<String>,
+    /// Path of the Stronghold snapshot, can also be set with the `WALLET_STRONGHOLD_PATH` environment variable.
+    #[clap(long)]
+    pub stronghold_path: Option<String>,
+    /// Clear the Stronghold password from memory after this long without a command at the account prompt, e.g. `10m`,
+    /// asking for it again before the next one.
+    #[clap(long)]
+    pub password_timeout: Option<String>,
+    /// Report how long each command took, broken down into secret manager, node API, PoW and storage phases.
+    #[clap(long)]
+    pub timings: bool,
+    /// Output of `balance`, `addresses`, `transactions`, `sync` and the send and mint commands: `text` (default) or
+    /// `json`, printed on stdout without the logs to be parsed by scripts.
+    #[clap(long, arg_enum, env = "FORMAT", default_value = "text")]
+    pub output: OutputFormat,
+    /// Profile of the config file whose settings override the top-level ones, can also be set with the
+    /// `WALLET_PROFILE` environment variable.
+    #[clap(long, env = "WALLET_PROFILE")]
+    pub profile: Option<String>,
 }
 
 #[derive(Debug, Clone, Subcommand)]
 pub enum AccountManagerCommand {
+    /// Run a single account command and exit, for scripts: `account main balance`, `account main send
+    /// rms1qztwng6cty8cfm42nzvq099ev7udhrnk0rw8jt8vttf9kpqnxhpsx869vr3 1000000`
+    Account(Box<AccountParameters>),
+    /// List the accounts with their index, alias and balance, and how the wallet is backed up.
+    Accounts,
+    /// Add a node to use, the other nodes being the fallback if it isn't healthy, or the first one to use with
+    /// `--primary`: `add-node https://api.testnet.shimmer.network [--primary]`
+    AddNode {
+        url: String,
+        #[clap(long)]
+        primary: bool,
+    },
     /// Create a stronghold backup file.
     Backup { path: String },
+    /// Gather recent logs, node info, versions and an anonymized storage summary into an archive to attach to issues:
+    /// `bug-report [bug-report.tar]`
+    BugReport { path: Option<String> },
     /// Change the stronghold password.
     ChangePassword,
+    /// Compact the database.
+    Compact,
+    /// Get or set the settings of the config file, of the profile given with `--profile`: `config get`, `config set
+    /// nodes https://api.testnet.shimmer.network`, `config unset account`
+    #[clap(subcommand)]
+    Config(ConfigCommand),
+    /// Check the storage, the Stronghold, the node, the network, the clock and the versions, the first thing to run
+    /// before filing an issue.
+    Doctor,
+    /// Export the mnemonic to a file, encrypted with a passphrase: `export-mnemonic --out mnemonic.json`
+    ExportMnemonic {
+        #[clap(long)]
+        out: String,
+    },
+    /// Export the accounts, addresses, outputs and transactions to an SQLite database: `export-sqlite wallet.sqlite`
+    ExportSqlite { path: String },
     /// Parameters for the init command.
     Init(InitParameters),
+    /// Show the new outputs, spent outputs and transaction confirmations of all accounts as they are synced, with
+    /// their balance, until interrupted: `listen --interval 30s`
+    Listen {
+        /// Time between syncs, 7s by default.
+        #[clap(long)]
+        interval: Option<String>,
+    },
+    /// List the nodes with their health, and the quorum settings.
+    ListNodes,
+    /// Migrate a Chrysalis wallet from its mnemonic or Stronghold backup: `migrate-chrysalis [backup.stronghold]`
+    MigrateChrysalis(MigrateChrysalisParameters),
     /// Generate a random mnemonic.
     Mnemonic,
     /// Create a new account with an optional alias.
     New { alias: Option<String> },
+    /// Show the health, milestones and protocol parameters of the node in use.
+    NodeInfo,
+    /// Remove the records of transactions created before a date and compact the database: `prune --before 2022-12-31`
+    Prune {
+        #[clap(long)]
+        before: String,
+    },
+    /// Search for the accounts and addresses with funds derived from the mnemonic, creating the accounts found:
+    /// `recover --account-gap 5 --address-gap 50`
+    Recover {
+        /// Number of accounts to search for after the last one with funds.
+        #[clap(long, default_value = "3")]
+        account_gap: u32,
+        /// Number of addresses to search for after the last one with funds, in each account.
+        #[clap(long, default_value = "20")]
+        address_gap: u32,
+    },
+    /// Remove a node, the last one can't be removed: `remove-node https://api.testnet.shimmer.network`
+    RemoveNode { url: String },
+    /// Rebuild the local database from the node, keeping the accounts and their addresses.
+    RepairStorage,
     /// Restore accounts from a stronghold backup file.
     Restore { backup_path: String },
-    /// Set the node to use.
+    /// Re-encrypt the stronghold with a key derived from a new password: `rotate-stronghold [--keep-previous]`
+    RotateStronghold {
+        /// Keep the previous snapshot, still decryptable with the previous password, with a `.previous` suffix.
+        #[clap(long)]
+        keep_previous: bool,
+    },
+    /// Replace the wallet binary with the latest release after verifying its checksum, or only check if there is one:
+    /// `self-update [--check-only]`
+    SelfUpdate {
+        #[clap(long)]
+        check_only: bool,
+    },
+    /// Set the node to use, replacing all the nodes.
     SetNode { url: String },
+    /// Set where the fiat prices of `report` and `transactions` come from, or show it without a source:
+    /// `set-price-source coingecko`, `set-price-source url:https://prices.example.com/{coin}/{currency}/{date}`,
+    /// `set-price-source static:0.05 --currency eur`
+    SetPriceSource {
+        /// `coingecko`, `url:<url>` or `static:<rate>`.
+        source: Option<String>,
+        /// Fiat currency of the prices.
+        #[clap(long, default_value = "usd")]
+        currency: String,
+        /// Only use the cached prices, never fetching them.
+        #[clap(long)]
+        offline: bool,
+    },
+    /// Require the node responses to be the same on several nodes, or stop requiring it with `--disable`: `set-quorum
+    /// --min-size 3 --threshold 66`
+    SetQuorum {
+        #[clap(long)]
+        disable: bool,
+        /// Number of nodes to ask.
+        #[clap(long, conflicts_with = "disable")]
+        min_size: Option<usize>,
+        /// Percentage of the nodes that must agree.
+        #[clap(long, conflicts_with = "disable")]
+        threshold: Option<usize>,
+    },
+    /// On an air-gapped wallet, answer the requests of a wallet run with `--secret-manager qr`, read from pictures of
+    /// its QR codes, with QR codes of the response.
+    SignQr,
+    /// Split the mnemonic into cli-wallet specific Shamir shares (not SLIP-39), any `threshold` of them being enough
+    /// to recover it: `split-mnemonic --shares 5 --threshold 3`
+    SplitMnemonic {
+        #[clap(long)]
+        shares: u8,
+        #[clap(long)]
+        threshold: u8,
+    },
+    /// Manage Stronghold snapshots: `stronghold list`, `stronghold set-active <path>`, `stronghold verify <path>`
+    #[clap(subcommand)]
+    Stronghold(StrongholdCommand),
     /// Sync all accounts.
     Sync,
+    /// Compare the local outputs of all accounts with the ones of the node, optionally repairing them: `verify
+    /// [--repair]`
+    Verify {
+        #[clap(long)]
+        repair: bool,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ConfigCommand {
+    /// Print a setting, or all of them.
+    Get { key: Option<String> },
+    /// Set a setting, `nodes` taking comma separated URLs.
+    Set { key: String, value: String },
+    /// Remove a setting, for the built-in default to apply.
+    Unset { key: String },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum StrongholdCommand {
+    /// List the Stronghold snapshots next to the active one.
+    List,
+    /// Set the Stronghold snapshot to use when none is given with `--stronghold-path` or `WALLET_STRONGHOLD_PATH`.
+    SetActive { path: String },
+    /// Check that a Stronghold snapshot can be opened with its password.
+    Verify { path: String },
+}
+
+// Locations and key derivation used by the Stronghold secret manager of `iota-client`, the seed has to be written at
+// the same place for the addresses to be derived from it.
+const STRONGHOLD_CLIENT_PATH: &[u8] = b"iota_seed";
+const STRONGHOLD_SECRET_VAULT_PATH: &[u8] = b"iota-wallet-secret";
+const STRONGHOLD_SEED_RECORD_PATH: &[u8] = b"iota-wallet-seed";
+const STRONGHOLD_PBKDF_SALT: &[u8] = b"wallet.rs";
+const STRONGHOLD_PBKDF_ITER: usize = 100;
+// Protocol version of the blocks built by `iota-types`, not exported by the crate.
+const SUPPORTED_PROTOCOL_VERSION: u8 = 2;
+// Maximum difference between the local time and the one of the latest milestone accepted by `iota-client` when
+// building transactions.
+const MAX_CLOCK_SKEW: u64 = 5 * 60;
+// GitHub API endpoint describing the latest release of the wallet.
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/iotaledger/cli-wallet/releases/latest";
+// File holding the path of the Stronghold snapshot set with `stronghold set-active`.
+pub const ACTIVE_STRONGHOLD_FILE: &str = "./stardust-cli-wallet.active-stronghold";
+// Schema of the database created by `export-sqlite`, documented with comments kept by SQLite.
+const SQLITE_SCHEMA: &str = "
+CREATE TABLE accounts (
+    account_index INTEGER PRIMARY KEY, -- BIP44 account index
+    alias TEXT NOT NULL UNIQUE,
+    coin_type INTEGER NOT NULL -- SLIP-0044 coin type, 4218 for IOTA and 4219 for Shimmer
+);
+CREATE TABLE addresses (
+    address TEXT PRIMARY KEY, -- Bech32 encoded
+    account_index INTEGER NOT NULL REFERENCES accounts(account_index),
+    key_index INTEGER NOT NULL, -- BIP44 address index
+    internal INTEGER NOT NULL, -- 1 for remainder (change) addresses, 0 for public ones
+    used INTEGER NOT NULL -- 1 if the address ever received an output
+);
+CREATE TABLE outputs (
+    output_id TEXT PRIMARY KEY, -- Hex encoded transaction id followed by the output index
+    account_index INTEGER NOT NULL REFERENCES accounts(account_index),
+    address TEXT NOT NULL, -- Bech32 encoded address owning the output
+    kind TEXT NOT NULL, -- basic, alias, foundry, nft or treasury
+    amount INTEGER NOT NULL, -- In the smallest unit of the base coin
+    spent INTEGER NOT NULL, -- 1 if spent, 0 otherwise
+    booked_at INTEGER NOT NULL, -- Unix timestamp (seconds) of the milestone that booked the output
+    spent_by TEXT -- Hex encoded id of the transaction that spent the output, if known
+);
+CREATE TABLE transactions (
+    transaction_id TEXT PRIMARY KEY, -- Hex encoded
+    account_index INTEGER NOT NULL REFERENCES accounts(account_index),
+    block_id TEXT, -- Hex encoded id of the block holding the transaction
+    inclusion_state TEXT NOT NULL, -- Pending, Confirmed, Conflicting or UnknownPruned
+    timestamp INTEGER NOT NULL, -- Unix timestamp (milliseconds) of the creation of the transaction
+    incoming INTEGER NOT NULL, -- 1 if received, 0 if sent by the wallet
+    direction TEXT NOT NULL, -- received, sent or self-transfer
+    net_change INTEGER NOT NULL, -- Change to the base coin balance of the account
+    note TEXT
+);
+";
+// Database key prefix of the accounts, followed by their index.
+const STORAGE_ACCOUNT_KEY: &[u8] = b"iota-wallet-account-";
+// Database key of the account manager data, including the client options.
+const STORAGE_ACCOUNT_MANAGER_KEY: &[u8] = b"iota-wallet-account-manager";
+// Log file written by the logger, see `logger_init`.
+const LOG_FILE: &str = "archive.log";
+// Number of the most recent log entries included in a bug report.
+const BUG_REPORT_LOG_ENTRIES: usize = 1000;
+// Stronghold store key of the timestamp and base coin balance of the last backup.
+const LAST_BACKUP_KEY: &[u8] = b"cli-wallet-last-backup";
+// Stronghold store key telling how the secret of the wallet was provided.
+pub const SECRET_KIND_KEY: &[u8] = b"cli-wallet-secret-kind";
+
+#[derive(Debug, Clone, Args)]
+pub struct AccountParameters {
+    /// Alias or index of the account.
+    pub account: String,
+    #[clap(subcommand)]
+    pub command: AccountCommand,
 }
 
 #[derive(Debug, Clone, Args)]
 pub struct InitParameters {
-    #[clap(short, long)]
+    #[clap(short, long, conflicts_with_all = &["combine-shares", "seed", "mnemonic-file"])]
     pub mnemonic: Option<String>,
+    /// Initialise from a raw hex encoded 64 bytes seed instead of a mnemonic.
+    #[clap(long, conflicts_with_all = &["combine-shares", "mnemonic-file"])]
+    pub seed: Option<String>,
+    /// Restore the mnemonic from a file written by `export-mnemonic`, its passphrase is asked for.
+    #[clap(long, conflicts_with = "combine-shares")]
+    pub mnemonic_file: Option<String>,
+    /// Recover the mnemonic from share files created by `split-mnemonic`.
+    #[clap(long, multiple_values = true)]
+    pub combine_shares: Vec<String>,
+    /// Node to use, repeatable to have fallback nodes.
     #[clap(short, long)]
-    pub node: Option<String>,
+    pub node: Vec<String>,
     #[clap(short, long)]
     pub coin_type: Option<u32>,
 }
 
+#[derive(Debug, Clone, Args)]
+pub struct MigrateChrysalisParameters {
+    /// Chrysalis Stronghold backup to import, the mnemonic is asked for if not provided.
+    pub backup: Option<String>,
+    #[clap(short, long)]
+    pub node: Option<String>,
+    /// Number of accounts to search for after the last one with funds.
+    #[clap(long, default_value = "3")]
+    pub account_gap: u32,
+    /// Number of addresses to search for after the last one with funds, in each account.
+    #[clap(long, default_value = "20")]
+    pub address_gap: u32,
+}
+
+pub async fn accounts_command(manager: &AccountManager, backup_max_age: u64) -> Result<(), Error> {
+    let account_handles = manager.get_accounts().await?;
+
+    if account_handles.is_empty() {
+        log::info!("No accounts, create one with the `new` command.");
+    } else {
+        let mut list = format!("{:>5}  {:<20}  {:>20}", "Index", "Alias", "Balance");
+
+        for account_handle in account_handles {
+            let (index, alias, coin_type) = {
+                let account = account_handle.read().await;
+                (*account.index(), account.alias().clone(), *account.coin_type())
+            };
+            // From the last sync, the wallet isn't synced.
+            let balance = account_handle.balance().await?.base_coin.total;
+
+            list.push_str(&format!(
+                "\n{index:>5}  {alias:<20}  {:>20}",
+                format_amount(balance, coin_type)
+            ));
+        }
+
+        log::info!("Accounts, with their balance as of the last sync:\n{list}");
+    }
+
+    if let SecretManager::Stronghold(secret_manager) = &mut *manager.get_secret_manager().write().await {
+        if secret_manager.get(SECRET_KIND_KEY).await?.as_deref() == Some(b"seed") {
+            log::info!("The wallet is seed-based, it has no mnemonic and only a `backup` can restore it.");
+        }
+    }
+    if let Some(last_backup) = last_backup(manager).await? {
+        log::info!(
+            "Last backup: {}",
+            format_timestamp(last_backup["timestamp"].as_u64().unwrap_or_default())
+        );
+    }
+
+    backup_reminder(manager, backup_max_age).await
+}
+
 pub async fn backup_command(manager: &AccountManager, path: String, password: &str) -> Result<(), Error> {
     manager.backup(path.clone().into(), password.into()).await?;
+    verify_backup(manager, &path, password).await?;
+
+    log::info!("Wallet has been backed up to \"{path}\" and the backup has been verified.");
+
+    let last_backup = serde_json::json!({
+        "timestamp": SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs()),
+        "baseCoin": manager.balance().await?.base_coin.total,
+    });
+
+    if let SecretManager::Stronghold(secret_manager) = &mut *manager.get_secret_manager().write().await {
+        secret_manager
+            .insert(LAST_BACKUP_KEY, last_backup.to_string().as_bytes())
+            .await?;
+        secret_manager.write_stronghold_snapshot(None).await?;
+    }
+
+    Ok(())
+}
+
+// Reads the written backup back, checking that it holds all the accounts and the keys of their addresses.
+async fn verify_backup(manager: &AccountManager, path: &str, password: &str) -> Result<(), Error> {
+    let mut backup = StrongholdSecretManager::builder().password(password).build(path)?;
+    let accounts = backup
+        .get(b"accounts")
+        .await?
+        .map(|accounts| serde_json::from_slice::<Vec<String>>(&accounts))
+        .transpose()?
+        .unwrap_or_default();
+    let account_handles = manager.get_accounts().await?;
+
+    if accounts.len() != account_handles.len() {
+        return Err(Error::Miscellaneous(format!(
+            "the backup holds {} account(s) instead of {}, keep the previous backup",
+            accounts.len(),
+            account_handles.len()
+        )));
+    }
+
+    // A seed-based or mnemonic-based wallet alike, the backup must derive the same addresses.
+    let backup = SecretManager::Stronghold(backup);
+
+    for account_handle in account_handles {
+        let account = account_handle.read().await;
+
+        if let Some(address) = account.public_addresses().first() {
+            let derived_addresses = backup
+                .generate_addresses(
+                    *account.coin_type(),
+                    *account.index(),
+                    *address.key_index()..*address.key_index() + 1,
+                    false,
+                    None,
+                )
+                .await?;
+
+            if derived_addresses.first() != Some(address.address().as_ref()) {
+                return Err(Error::Miscellaneous(format!(
+                    "the backup doesn't derive the addresses of account \"{}\", keep the previous backup",
+                    account.alias()
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Warns if the wallet has never been backed up, if the last backup is older than `max_age_days` or if the balance
+/// changed significantly since.
+pub async fn backup_reminder(manager: &AccountManager, max_age_days: u64) -> Result<(), Error> {
+    if !matches!(
+        &*manager.get_secret_manager().read().await,
+        SecretManager::Stronghold(_)
+    ) {
+        return Ok(());
+    }
+
+    let Some(last_backup) = last_backup(manager).await? else {
+        log::warn!("The wallet has never been backed up, use the `backup` command.");
+        return Ok(());
+    };
+    let timestamp = last_backup["timestamp"].as_u64().unwrap_or_default();
+    let base_coin = last_backup["baseCoin"].as_u64().unwrap_or_default();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let age_days = now.saturating_sub(timestamp) / (24 * 60 * 60);
+
+    if age_days > max_age_days {
+        log::warn!("The last backup is {age_days} days old, use the `backup` command.");
+    }
+
+    let total = manager.balance().await?.base_coin.total;
+
+    // More than 10% up or down.
+    if total.abs_diff(base_coin) * 10 > base_coin {
+        log::warn!("The balance changed from {base_coin} to {total} since the last backup, use the `backup` command.");
+    }
+
+    Ok(())
+}
 
-    log::info!("Wallet has been backed up to \"{path}\".");
+// The timestamp and base coin balance of the last backup, recorded by `backup` in the Stronghold.
+async fn last_backup(manager: &AccountManager) -> Result<Option<serde_json::Value>, Error> {
+    let last_backup = match &mut *manager.get_secret_manager().write().await {
+        SecretManager::Stronghold(secret_manager) => secret_manager.get(LAST_BACKUP_KEY).await?,
+        _ => None,
+    };
+
+    Ok(last_backup
+        .map(|last_backup| serde_json::from_slice(&last_backup))
+        .transpose()?)
+}
+
+pub async fn bug_report_command(storage_path: &str, path: Option<String>) -> Result<(), Error> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let path = path.unwrap_or_else(|| format!("cli-wallet-bug-report-{now}.tar"));
+
+    if Path::new(&path).exists() {
+        return Err(Error::Miscellaneous(format!(
+            "\"{path}\" already exists, refusing to overwrite it"
+        )));
+    }
+
+    let versions = serde_json::json!({
+        "wallet": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+    });
+
+    // Accounts are summarized without their alias, addresses or amounts.
+    let mut client_options = None;
+    let storage_summary = match open_storage(storage_path) {
+        Ok(db) => {
+            let mut accounts = Vec::new();
+
+            client_options = stored_client_options(&db);
+
+            for entry in db.prefix_iterator(STORAGE_ACCOUNT_KEY) {
+                let (key, value) = entry.map_err(|e| Error::Miscellaneous(e.to_string()))?;
+
+                // See `prune_command`.
+                if !key.starts_with(STORAGE_ACCOUNT_KEY)
+                    || !std::str::from_utf8(&key[STORAGE_ACCOUNT_KEY.len()..])
+                        .is_ok_and(|index| index.parse::<u32>().is_ok())
+                {
+                    continue;
+                }
+
+                let account = serde_json::from_slice::<serde_json::Value>(&value).unwrap_or_default();
+                let count = |field: &str| {
+                    account[field]
+                        .as_array()
+                        .map(Vec::len)
+                        .or_else(|| account[field].as_object().map(serde_json::Map::len))
+                };
+
+                accounts.push(serde_json::json!({
+                    "index": account["index"],
+                    "coinType": account["coinType"],
+                    "publicAddresses": count("publicAddresses"),
+                    "internalAddresses": count("internalAddresses"),
+                    "outputs": count("outputs"),
+                    "unspentOutputs": count("unspentOutputs"),
+                    "transactions": count("transactions"),
+                    "pendingTransactions": count("pendingTransactions"),
+                }));
+            }
+            drop(db);
+
+            serde_json::json!({
+                "size": storage_size(storage_path).ok(),
+                "accounts": accounts,
+            })
+        }
+        Err(e) => serde_json::json!({ "error": e.to_string() }),
+    };
+
+    let node_info = match client_options {
+        Some(client_options) => match client_options.finish() {
+            Ok(client) => match client.get_info().await {
+                Ok(node_info) => serde_json::to_value(node_info)?,
+                Err(e) => serde_json::json!({ "error": Error::from(e).to_string() }),
+            },
+            Err(e) => serde_json::json!({ "error": Error::from(e).to_string() }),
+        },
+        None => serde_json::json!({ "error": "no client options found in the database" }),
+    };
+
+    let logs = match std::fs::read_to_string(LOG_FILE) {
+        Ok(logs) => redact_logs(&logs),
+        Err(e) => format!("can't read \"{LOG_FILE}\": {e}\n"),
+    };
+
+    let mut builder = tar::Builder::new(File::create(&path)?);
+
+    for (name, contents) in [
+        ("versions.json", serde_json::to_string_pretty(&versions)?),
+        ("node-info.json", serde_json::to_string_pretty(&node_info)?),
+        ("storage-summary.json", serde_json::to_string_pretty(&storage_summary)?),
+        (LOG_FILE, logs),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(now);
+        header.set_cksum();
+        builder.append_data(&mut header, name, contents.as_bytes())?;
+    }
+
+    builder.into_inner()?;
+
+    log::info!("Bug report written to \"{path}\", check its content before attaching it to an issue.");
 
     Ok(())
 }
@@ -67,6 +637,339 @@ pub async fn change_password_command(manager: &AccountManager, current: &str) ->
 
     manager.change_stronghold_password(current, &new).await?;
 
+    log::info!("The Stronghold has been re-encrypted with the new password.");
+
+    Ok(())
+}
+
+pub async fn compact_command(storage_path: &str) -> Result<(), Error> {
+    let size_before = storage_size(storage_path)?;
+
+    open_storage(storage_path)?.compact_range(None::<&[u8]>, None::<&[u8]>);
+
+    log::info!(
+        "Database has been compacted: {size_before} bytes before, {} bytes after.",
+        storage_size(storage_path)?
+    );
+
+    Ok(())
+}
+
+// Outcome of a `doctor` check.
+enum Check {
+    Pass(String),
+    Fail(String),
+    Skip(String),
+}
+
+pub async fn doctor_command(storage_path: &str, snapshot_path: &Path) -> Result<(), Error> {
+    let mut checks = Vec::new();
+
+    let storage = if !Path::new(storage_path).is_dir() {
+        Check::Fail(format!("no database at \"{storage_path}\""))
+    } else {
+        match open_storage(storage_path) {
+            Ok(_) => Check::Pass(format!("\"{storage_path}\" can be opened")),
+            Err(e) => Check::Fail(e.to_string()),
+        }
+    };
+    let storage_ok = matches!(storage, Check::Pass(_));
+    checks.push(("Storage", storage));
+
+    let mut password = None;
+    let stronghold = if !snapshot_path.is_file() {
+        Check::Fail(format!("no Stronghold snapshot at \"{}\"", snapshot_path.display()))
+    } else {
+        let input = get_password("Stronghold password", false)?;
+
+        match snapshot_holds_secret(snapshot_path, &input) {
+            Ok(true) => {
+                password = Some(input);
+                Check::Pass(format!(
+                    "\"{}\" can be read and holds a secret",
+                    snapshot_path.display()
+                ))
+            }
+            Ok(false) => Check::Fail(format!("\"{}\" holds no secret", snapshot_path.display())),
+            Err(e) => Check::Fail(e.to_string()),
+        }
+    };
+    checks.push(("Stronghold", stronghold));
+
+    let node_info = match password {
+        Some(password) if storage_ok => {
+            let secret_manager = SecretManager::Stronghold(
+                StrongholdSecretManager::builder()
+                    .password(&password)
+                    .build(snapshot_path)?,
+            );
+
+            match AccountManager::builder()
+                .with_secret_manager(secret_manager)
+                .with_storage_path(storage_path)
+                .finish()
+                .await
+            {
+                Ok(manager) => {
+                    let mut hrps = Vec::new();
+
+                    for account_handle in manager.get_accounts().await? {
+                        let account = account_handle.read().await;
+
+                        if let Some(address) = account.public_addresses().first() {
+                            hrps.push((account.alias().clone(), address.address().bech32_hrp().to_string()));
+                        }
+                    }
+
+                    manager
+                        .get_node_info()
+                        .await
+                        .map(|node_info| (node_info, hrps))
+                        .map_err(|e| Check::Fail(Error::from(e).to_string()))
+                }
+                Err(e) => Err(Check::Fail(format!("can't open the wallet: {}", Error::from(e)))),
+            }
+        }
+        _ => Err(Check::Skip("needs a readable storage and Stronghold".to_string())),
+    };
+
+    match node_info {
+        Ok((node_info, hrps)) => {
+            let info = &node_info.node_info;
+            let url = &node_info.url;
+
+            checks.push((
+                "Node",
+                if info.status.is_healthy {
+                    Check::Pass(format!("{url} is reachable and healthy"))
+                } else {
+                    Check::Fail(format!("{url} is reachable but not healthy"))
+                },
+            ));
+
+            let mismatches = hrps
+                .iter()
+                .filter(|(_, hrp)| hrp != &info.protocol.bech32_hrp)
+                .map(|(alias, hrp)| format!("\"{alias}\" has \"{hrp}\" addresses"))
+                .collect::<Vec<_>>();
+            checks.push((
+                "Network",
+                if mismatches.is_empty() {
+                    Check::Pass(format!(
+                        "the accounts and the node use \"{}\" addresses on {}",
+                        info.protocol.bech32_hrp, info.protocol.network_name
+                    ))
+                } else {
+                    Check::Fail(format!(
+                        "the node uses \"{}\" addresses on {} but {}",
+                        info.protocol.bech32_hrp,
+                        info.protocol.network_name,
+                        mismatches.join(", ")
+                    ))
+                },
+            ));
+
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs());
+            checks.push((
+                "Clock",
+                match info.status.latest_milestone.timestamp {
+                    Some(timestamp) if now.abs_diff(timestamp.into()) <= MAX_CLOCK_SKEW => Check::Pass(format!(
+                        "local time is {}s off the latest milestone",
+                        now.abs_diff(timestamp.into())
+                    )),
+                    Some(timestamp) => Check::Fail(format!(
+                        "local time is {}s off the latest milestone, more than {MAX_CLOCK_SKEW}s, either the clock or \
+                         the node is not synced",
+                        now.abs_diff(timestamp.into())
+                    )),
+                    None => Check::Skip("the node has no milestone yet".to_string()),
+                },
+            ));
+
+            let versions = format!(
+                "node {} {} with protocol {}, wallet {} with protocol {SUPPORTED_PROTOCOL_VERSION}",
+                info.name,
+                info.version,
+                info.protocol.version,
+                env!("CARGO_PKG_VERSION")
+            );
+            checks.push((
+                "Versions",
+                if info.protocol.version == SUPPORTED_PROTOCOL_VERSION {
+                    Check::Pass(versions)
+                } else {
+                    Check::Fail(versions)
+                },
+            ));
+        }
+        Err(check) => {
+            checks.push(("Node", check));
+
+            for name in ["Network", "Clock", "Versions"] {
+                checks.push((name, Check::Skip("needs a reachable node".to_string())));
+            }
+        }
+    }
+
+    let mut failures = 0;
+
+    for (name, check) in checks {
+        match check {
+            Check::Pass(details) => log::info!("[PASS] {name}: {details}"),
+            Check::Fail(details) => {
+                failures += 1;
+                log::warn!("[FAIL] {name}: {details}");
+            }
+            Check::Skip(reason) => log::info!("[SKIP] {name}: {reason}"),
+        }
+    }
+
+    if failures == 0 {
+        log::info!("All checks passed.");
+    } else {
+        log::warn!("{failures} check(s) failed, include this output when filing an issue.");
+    }
+
+    Ok(())
+}
+
+pub async fn export_mnemonic_command(manager: &AccountManager, out: String) -> Result<(), Error> {
+    if Path::new(&out).exists() {
+        return Err(Error::Miscellaneous(format!(
+            "\"{out}\" already exists, refusing to overwrite it"
+        )));
+    }
+
+    log::warn!("IMPORTANT: you are about to write your mnemonic to \"{out}\", encrypted with a passphrase.");
+    log::warn!("Anyone getting hold of both this file and its passphrase gains full control over your funds.");
+    log::warn!("Keep the file offline, never share it and don't reuse your Stronghold password as passphrase.");
+
+    if !Confirm::new()
+        .with_prompt("Do you understand the risks and want to continue?")
+        .default(false)
+        .interact()?
+    {
+        return Ok(());
+    }
+
+    let mnemonic = wallet_mnemonic_prompt(manager).await?;
+    let passphrase = get_password("Export passphrase", true)?;
+
+    write_private_file(&out, encrypt_with_passphrase(&passphrase, mnemonic.as_bytes())?)?;
+
+    log::info!("Encrypted mnemonic has been exported to \"{out}\".");
+
+    Ok(())
+}
+
+pub async fn export_sqlite_command(manager: &AccountManager, path: String) -> Result<(), Error> {
+    if Path::new(&path).exists() {
+        return Err(Error::Miscellaneous(format!(
+            "\"{path}\" already exists, refusing to overwrite it"
+        )));
+    }
+
+    let sqlite_err = |e: rusqlite::Error| Error::Miscellaneous(format!("SQLite error: {e}"));
+    let mut connection = Connection::open(&path).map_err(sqlite_err)?;
+    let transaction = connection.transaction().map_err(sqlite_err)?;
+
+    transaction.execute_batch(SQLITE_SCHEMA).map_err(sqlite_err)?;
+
+    for account_handle in manager.get_accounts().await? {
+        let mut changes = HashMap::new();
+
+        for tx in account_handle.transactions().await? {
+            changes.insert(tx.transaction_id, transaction_changes(&account_handle, &tx).await?);
+        }
+
+        let account = account_handle.read().await;
+
+        transaction
+            .execute(
+                "INSERT INTO accounts VALUES (?1, ?2, ?3)",
+                params![account.index(), account.alias(), account.coin_type()],
+            )
+            .map_err(sqlite_err)?;
+
+        for address in account.public_addresses().iter().chain(account.internal_addresses()) {
+            transaction
+                .execute(
+                    "INSERT INTO addresses VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        address.address().to_bech32(),
+                        account.index(),
+                        address.key_index(),
+                        address.internal(),
+                        address.used()
+                    ],
+                )
+                .map_err(sqlite_err)?;
+        }
+
+        let bech32_hrp = account
+            .public_addresses()
+            .first()
+            .map(|address| address.address().bech32_hrp().to_string())
+            .unwrap_or_default();
+
+        for output_data in account.outputs().values() {
+            let kind = match output_data.output {
+                Output::Alias(_) => "alias",
+                Output::Basic(_) => "basic",
+                Output::Foundry(_) => "foundry",
+                Output::Nft(_) => "nft",
+                Output::Treasury(_) => "treasury",
+            };
+
+            transaction
+                .execute(
+                    "INSERT INTO outputs VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        output_data.output_id.to_string(),
+                        account.index(),
+                        output_data.address.to_bech32(&bech32_hrp),
+                        kind,
+                        output_data.output.amount(),
+                        output_data.is_spent,
+                        output_data.metadata.milestone_timestamp_booked,
+                        output_data.metadata.transaction_id_spent
+                    ],
+                )
+                .map_err(sqlite_err)?;
+        }
+
+        for tx in account.transactions().values() {
+            let (direction, net) = changes
+                .get(&tx.transaction_id)
+                .map_or(("", 0), |changes| (changes.direction.as_str(), changes.net));
+
+            transaction
+                .execute(
+                    "INSERT INTO transactions VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    params![
+                        tx.transaction_id.to_string(),
+                        account.index(),
+                        tx.block_id.map(|block_id| block_id.to_string()),
+                        format!("{:?}", tx.inclusion_state),
+                        // Milliseconds since the Unix epoch fit in an SQLite integer for the next few million years.
+                        tx.timestamp as u64,
+                        tx.incoming,
+                        direction,
+                        // Balance changes are bounded by the token supply, far below the SQLite integer range.
+                        net as i64,
+                        tx.note
+                    ],
+                )
+                .map_err(sqlite_err)?;
+        }
+    }
+
+    transaction.commit().map_err(sqlite_err)?;
+
+    log::info!("Wallet data has been exported to \"{path}\".");
+
     Ok(())
 }
 
@@ -75,40 +978,159 @@ pub async fn init_command(
     storage_path: String,
     parameters: InitParameters,
 ) -> Result<AccountManager, Error> {
+    let seed_based = parameters.seed.is_some();
+    let external = matches!(secret_manager, SecretManager::Placeholder(_));
+    let nodes = if !parameters.node.is_empty() {
+        parameters.node.iter().map(String::as_str).collect()
+    } else if !config().nodes.is_empty() {
+        config().nodes.iter().map(String::as_str).collect()
+    } else {
+        vec!["http://localhost:14265"]
+    };
     let account_manager = AccountManager::builder()
         .with_secret_manager(secret_manager)
-        .with_client_options(
-            ClientOptions::new().with_node(parameters.node.as_deref().unwrap_or("http://localhost:14265"))?,
-        )
+        .with_client_options(ClientOptions::new().with_nodes(&nodes)?)
         .with_storage_path(&storage_path)
         .with_coin_type(parameters.coin_type.unwrap_or(SHIMMER_COIN_TYPE))
         .finish()
         .await?;
 
-    let mnemonic = match parameters.mnemonic {
-        Some(mnemonic) => mnemonic,
-        None => generate_mnemonic()?,
-    };
+    if external {
+        log::info!("The keys are held by the external signer, no mnemonic is stored.");
 
-    let mut file = File::options().create(true).append(true).open("mnemonic.txt")?;
-    // Write mnemonic with new line
-    file.write_all(format!("init_command: {mnemonic}\n").as_bytes())?;
+        return Ok(account_manager);
+    }
 
-    log::info!("IMPORTANT: mnemonic has been written to \"mnemonic.txt\", handle it safely.");
-    log::info!(
-        "It is the only way to recover your account if you ever forget your password and/or lose the stronghold file."
-    );
+    if seed_based {
+        // The seed has already been written to the Stronghold snapshot, see `store_seed`.
+        if let SecretManager::Stronghold(secret_manager) = &mut *account_manager.get_secret_manager().write().await {
+            secret_manager.insert(SECRET_KIND_KEY, b"seed").await?;
+            secret_manager.write_stronghold_snapshot(None).await?;
+        }
+        log::info!("Seed stored successfully, the wallet is seed-based and has no mnemonic.");
+
+        return Ok(account_manager);
+    }
+
+    let mnemonic = if let Some(path) = &parameters.mnemonic_file {
+        // Not written to a file on purpose, the encrypted file is the backup.
+        let passphrase = get_password("Export passphrase", false)?;
+        let mnemonic = decrypt_with_passphrase(&passphrase, &std::fs::read_to_string(path)?)?;
+
+        String::from_utf8(mnemonic).map_err(|_| Error::Miscellaneous(format!("\"{path}\" holds no mnemonic")))?
+    } else if parameters.combine_shares.is_empty() {
+        let mnemonic = match parameters.mnemonic {
+            Some(mnemonic) => mnemonic,
+            None => generate_mnemonic()?,
+        };
+
+        let mut file = File::options().create(true).append(true).open("mnemonic.txt")?;
+        // Write mnemonic with new line
+        file.write_all(format!("init_command: {mnemonic}\n").as_bytes())?;
+
+        log::info!("IMPORTANT: mnemonic has been written to \"mnemonic.txt\", handle it safely.");
+        log::info!(
+            "It is the only way to recover your account if you ever forget your password and/or lose the stronghold \
+             file."
+        );
+
+        mnemonic
+    } else {
+        // Not written to a file on purpose, the shares are the backup.
+        combine_mnemonic_shares(&parameters.combine_shares)?
+    };
 
     if let SecretManager::Stronghold(secret_manager) = &mut *account_manager.get_secret_manager().write().await {
         secret_manager.store_mnemonic(mnemonic).await?;
     } else {
-        panic!("cli-wallet only supports Stronghold-backed secret managers at the moment.");
+        return Err(Error::Miscellaneous(
+            "cli-wallet only supports Stronghold-backed secret managers at the moment".to_string(),
+        ));
     }
     log::info!("Mnemonic stored successfully");
 
     Ok(account_manager)
 }
 
+pub async fn migrate_chrysalis_command(
+    secret_manager: SecretManager,
+    storage_path: String,
+    parameters: MigrateChrysalisParameters,
+    password: String,
+) -> Result<AccountManager, Error> {
+    let account_manager = AccountManager::builder()
+        .with_secret_manager(secret_manager)
+        .with_client_options(
+            ClientOptions::new().with_node(
+                parameters
+                    .node
+                    .as_deref()
+                    .or_else(|| config().nodes.first().map(String::as_str))
+                    .unwrap_or("http://localhost:14265"),
+            )?,
+        )
+        .with_storage_path(&storage_path)
+        // Chrysalis wallets used the same derivation path, so the Stardust addresses are the Chrysalis ones.
+        .with_coin_type(IOTA_COIN_TYPE)
+        .finish()
+        .await?;
+
+    if let Some(backup) = parameters.backup {
+        account_manager
+            .restore_backup(backup.into(), password)
+            .await
+            .map_err(|e| {
+                Error::Miscellaneous(format!(
+                    "failed to import the backup, Stronghold snapshots created by Chrysalis wallets may not be \
+                     readable, try again without a backup to provide the mnemonic instead: {e}"
+                ))
+            })?;
+    } else {
+        let mnemonic = Password::new().with_prompt("Chrysalis mnemonic").interact()?;
+
+        if let SecretManager::Stronghold(secret_manager) = &mut *account_manager.get_secret_manager().write().await {
+            secret_manager.store_mnemonic(mnemonic).await?;
+        } else {
+            return Err(Error::Miscellaneous(
+                "cli-wallet only supports Stronghold-backed secret managers at the moment".to_string(),
+            ));
+        }
+    }
+
+    log::info!("Searching for accounts and addresses with funds, this may take a while.");
+
+    let account_handles = account_manager
+        .recover_accounts(0, parameters.account_gap, parameters.address_gap, None)
+        .await?;
+
+    if account_handles.is_empty() {
+        log::info!("No funds found.");
+    }
+
+    report_recovered_accounts(&account_handles).await?;
+
+    Ok(account_manager)
+}
+
+// Reports the addresses and balance of accounts found by a search for funds.
+async fn report_recovered_accounts(account_handles: &[AccountHandle]) -> Result<(), Error> {
+    for account_handle in account_handles {
+        let balance = account_handle.balance().await?;
+        let account = account_handle.read().await;
+
+        log::info!(
+            "Account \"{}\": {} addresses, {} base coin, {} native tokens, {} NFTs",
+            account.alias(),
+            account.public_addresses().len() + account.internal_addresses().len(),
+            balance.base_coin.total,
+            balance.native_tokens.len(),
+            balance.nfts.len()
+        );
+    }
+
+    Ok(())
+}
+
 pub async fn mnemonic_command() -> Result<(), Error> {
     let mnemonic = generate_mnemonic()?;
 
@@ -124,12 +1146,21 @@ pub async fn mnemonic_command() -> Result<(), Error> {
     Ok(())
 }
 
-pub async fn new_command(manager: &AccountManager, alias: Option<String>) -> Result<String, Error> {
+pub async fn new_command(
+    manager: &AccountManager,
+    alias: Option<String>,
+    signer: Option<&ExternalSigner>,
+) -> Result<String, Error> {
     let mut builder = manager.create_account();
 
     if let Some(alias) = alias {
         builder = builder.with_alias(alias);
     }
+    if let Some(signer) = signer {
+        let account_index = manager.get_accounts().await?.len() as u32;
+
+        builder = builder.with_addresses(vec![signer.first_address(stored_coin_type()?, account_index)?]);
+    }
 
     let account_handle = builder.finish().await?;
     let alias = account_handle.read().await.alias().to_string();
@@ -139,43 +1170,1651 @@ pub async fn new_command(manager: &AccountManager, alias: Option<String>) -> Res
     Ok(alias)
 }
 
-pub async fn restore_command(
-    secret_manager: SecretManager,
-    storage_path: String,
-    backup_path: String,
-    password: String,
-) -> Result<AccountManager, Error> {
-    let account_manager = AccountManager::builder()
-        .with_secret_manager(secret_manager)
-        // Will be overwritten by the backup's value.
-        .with_client_options(
-            ClientOptions::new()
-                .with_node("http://localhost:14265")?
-                .with_node_sync_disabled(),
-        )
-        .with_storage_path(&storage_path)
-        // Will be overwritten by the backup's value.
-        .with_coin_type(SHIMMER_COIN_TYPE)
-        .finish()
-        .await?;
+/// Returns the context given to a plugin run from the command line, read from the database since the wallet isn't open.
+pub fn plugin_context(storage_path: &str) -> serde_json::Value {
+    let mut nodes = Vec::new();
+    let mut accounts = Vec::new();
 
-    account_manager.restore_backup(backup_path.into(), password).await?;
+    if let Ok(db) = open_storage(storage_path) {
+        if let Some(client_options) = stored_client_options(&db) {
+            nodes = node_urls(&client_options);
+        }
 
-    Ok(account_manager)
+        for (key, value) in db.prefix_iterator(STORAGE_ACCOUNT_KEY).flatten() {
+            // See `prune_command`.
+            if !key.starts_with(STORAGE_ACCOUNT_KEY)
+                || !std::str::from_utf8(&key[STORAGE_ACCOUNT_KEY.len()..])
+                    .is_ok_and(|index| index.parse::<u32>().is_ok())
+            {
+                continue;
+            }
+
+            let account = serde_json::from_slice::<serde_json::Value>(&value).unwrap_or_default();
+            let bech32_hrp = account["publicAddresses"][0]["address"]
+                .as_str()
+                .and_then(|address| Address::try_from_bech32(address).ok())
+                .map(|(bech32_hrp, _)| bech32_hrp);
+
+            accounts.push(serde_json::json!({
+                "index": account["index"],
+                "alias": account["alias"],
+                "coinType": account["coinType"],
+                "bech32Hrp": bech32_hrp,
+            }));
+        }
+    }
+
+    serde_json::json!({
+        "databasePath": storage_path,
+        "nodes": nodes,
+        "accounts": accounts,
+    })
 }
 
-pub async fn set_node_command(manager: &AccountManager, url: String) -> Result<(), Error> {
-    manager
-        .set_client_options(ClientOptions::new().with_node(&url)?)
-        .await?;
+pub async fn prune_command(storage_path: &str, before: String) -> Result<(), Error> {
+    let before = u128::from(parse_date(&before)?) * 1000;
+    let size_before = storage_size(storage_path)?;
+    let db = open_storage(storage_path)?;
+    let mut pruned = 0;
+
+    for entry in db.prefix_iterator(STORAGE_ACCOUNT_KEY) {
+        let (key, value) = entry.map_err(|e| Error::Miscellaneous(e.to_string()))?;
+
+        // Without a prefix extractor, the iterator doesn't stop at the end of the prefix, which is also shared with the
+        // account manager key.
+        if !key.starts_with(STORAGE_ACCOUNT_KEY)
+            || !std::str::from_utf8(&key[STORAGE_ACCOUNT_KEY.len()..]).is_ok_and(|index| index.parse::<u32>().is_ok())
+        {
+            continue;
+        }
+        if !value.starts_with(b"{") {
+            return Err(Error::Miscellaneous(
+                "the database is encrypted and can't be pruned".to_string(),
+            ));
+        }
+
+        let mut account = serde_json::from_slice::<serde_json::Value>(&value)?;
+        let pending = account["pendingTransactions"].clone();
+
+        if let Some(transactions) = account["transactions"].as_object_mut() {
+            let count = transactions.len();
+
+            // Pending transactions are kept whatever their age, they are still needed to follow them up.
+            transactions.retain(|transaction_id, transaction| {
+                transaction["inclusionState"] == "Pending"
+                    || pending
+                        .as_array()
+                        .is_some_and(|pending| pending.iter().any(|id| id == transaction_id.as_str()))
+                    || transaction["timestamp"]
+                        .as_u64()
+                        .is_none_or(|timestamp| u128::from(timestamp) >= before)
+            });
+            pruned += count - transactions.len();
+        }
+
+        db.put(&key, serde_json::to_vec(&account)?)
+            .map_err(|e| Error::Miscellaneous(e.to_string()))?;
+    }
+
+    db.compact_range(None::<&[u8]>, None::<&[u8]>);
+    drop(db);
+
+    log::info!(
+        "{pruned} transaction record(s) pruned: {size_before} bytes before, {} bytes after.",
+        storage_size(storage_path)?
+    );
 
     Ok(())
 }
 
-pub async fn sync_command(manager: &AccountManager) -> Result<(), Error> {
-    let total_balance = manager.sync(None).await?;
+pub async fn recover_command(manager: &AccountManager, account_gap: u32, address_gap: u32) -> Result<(), Error> {
+    let existing_accounts = manager.get_accounts().await?.len();
 
-    log::info!("Synchronized all accounts: {:?}", total_balance);
+    log::info!("Searching for accounts and addresses with funds, this may take a while.");
 
-    Ok(())
+    // The existing accounts are kept, only the empty ones found after them are dropped.
+    let account_handles = timed(Phase::Node, manager.recover_accounts(0, account_gap, address_gap, None)).await?;
+    let mut accounts_with_funds = 0;
+    let mut addresses_with_funds = 0;
+
+    for account_handle in &account_handles {
+        let addresses = account_handle.addresses_with_unspent_outputs().await?.len();
+
+        if addresses > 0 {
+            accounts_with_funds += 1;
+            addresses_with_funds += addresses;
+        }
+    }
+
+    report_recovered_accounts(&account_handles).await?;
+
+    log::info!(
+        "Found {accounts_with_funds} account(s) and {addresses_with_funds} address(es) with funds, {} new account(s) \
+         created.",
+        account_handles.len().saturating_sub(existing_accounts)
+    );
+
+    Ok(())
+}
+
+pub async fn repair_storage_command(
+    secret_manager: SecretManager,
+    storage_path: String,
+    snapshot_path: &Path,
+    password: &str,
+) -> Result<AccountManager, Error> {
+    let account_manager = AccountManager::builder()
+        .with_secret_manager(secret_manager)
+        .with_storage_path(&storage_path)
+        .finish()
+        .await?;
+    let client_options = account_manager.get_client_options().await;
+    let mut accounts = Vec::new();
+
+    for account_handle in account_manager.get_accounts().await? {
+        let account = account_handle.read().await;
+
+        accounts.push((
+            account.alias().clone(),
+            *account.coin_type(),
+            account.public_addresses().len() as u32,
+            account.internal_addresses().len() as u32,
+        ));
+    }
+
+    // The database is locked as long as the account manager is alive.
+    drop(account_manager);
+
+    let previous = format!("{storage_path}.previous");
+
+    if Path::new(&previous).exists() {
+        return Err(Error::Miscellaneous(format!(
+            "\"{previous}\" already exists, remove it before repairing the storage again"
+        )));
+    }
+
+    std::fs::rename(&storage_path, &previous)?;
+
+    let account_manager = AccountManager::builder()
+        .with_secret_manager(SecretManager::Stronghold(
+            StrongholdSecretManager::builder()
+                .password(password)
+                .build(snapshot_path)?,
+        ))
+        .with_client_options(client_options)
+        .with_storage_path(&storage_path)
+        .with_coin_type(
+            accounts
+                .first()
+                .map_or(SHIMMER_COIN_TYPE, |(_, coin_type, _, _)| *coin_type),
+        )
+        .finish()
+        .await?;
+
+    for (alias, _, public_addresses, internal_addresses) in accounts {
+        let account_handle = account_manager
+            .create_account()
+            .with_alias(alias.clone())
+            .finish()
+            .await?;
+
+        // The first public address is generated with the account.
+        if public_addresses > 1 {
+            account_handle.generate_addresses(public_addresses - 1, None).await?;
+        }
+        if internal_addresses > 0 {
+            account_handle
+                .generate_addresses(
+                    internal_addresses,
+                    Some(AddressGenerationOptions {
+                        internal: true,
+                        ..Default::default()
+                    }),
+                )
+                .await?;
+        }
+
+        let balance = account_handle
+            .sync(Some(SyncOptions {
+                force_syncing: true,
+                ..Default::default()
+            }))
+            .await?;
+
+        log::info!("Account \"{alias}\" has been rebuilt: {:?}", balance.base_coin);
+    }
+
+    log::info!("Storage has been rebuilt, the previous one has been kept at \"{previous}\".");
+
+    Ok(account_manager)
+}
+
+pub async fn restore_command(
+    secret_manager: SecretManager,
+    storage_path: String,
+    backup_path: String,
+    password: String,
+) -> Result<AccountManager, Error> {
+    let account_manager = AccountManager::builder()
+        .with_secret_manager(secret_manager)
+        // Will be overwritten by the backup's value.
+        .with_client_options(
+            ClientOptions::new()
+                .with_node("http://localhost:14265")?
+                .with_node_sync_disabled(),
+        )
+        .with_storage_path(&storage_path)
+        // Will be overwritten by the backup's value.
+        .with_coin_type(SHIMMER_COIN_TYPE)
+        .finish()
+        .await?;
+
+    account_manager.restore_backup(backup_path.into(), password).await?;
+
+    let account_handles = account_manager.get_accounts().await?;
+
+    if account_handles.is_empty() {
+        log::warn!("The backup holds no account, create one with the `new` command.");
+    } else {
+        let mut aliases = Vec::new();
+
+        for account_handle in &account_handles {
+            let account = account_handle.read().await;
+            aliases.push(format!("{} ({})", account.alias(), account.index()));
+        }
+
+        log::info!(
+            "Restored {} account(s): {}. Their balances are the ones of the backup, run `sync` in each of them.",
+            aliases.len(),
+            aliases.join(", ")
+        );
+    }
+
+    Ok(account_manager)
+}
+
+pub async fn rotate_stronghold_command(
+    manager: &AccountManager,
+    snapshot_path: &Path,
+    current: &str,
+    keep_previous: bool,
+) -> Result<(), Error> {
+    let new = get_password("Stronghold new password", true)?;
+
+    // The salt and iterations of the key derivation are fixed, only a new password gives a new key.
+    if new == current {
+        return Err(Error::Miscellaneous(
+            "the new password must differ from the current one, the same password gives the same key".to_string(),
+        ));
+    }
+    let mut previous = snapshot_path.as_os_str().to_owned();
+    previous.push(".previous");
+
+    // Kept until the rotated snapshot has been checked, and afterwards only if asked to.
+    std::fs::copy(snapshot_path, &previous)?;
+
+    // Changing the password re-encrypts the store and rewrites the whole snapshot.
+    manager.change_stronghold_password(current, &new).await?;
+
+    if !snapshot_holds_secret(snapshot_path, &new)? {
+        return Err(Error::Miscellaneous(format!(
+            "rotated snapshot holds no secret, the previous one has been kept at \"{}\"",
+            Path::new(&previous).display()
+        )));
+    }
+
+    if keep_previous {
+        log::info!(
+            "Stronghold snapshot has been re-encrypted, the previous one has been kept at \"{}\", delete it safely \
+             once no longer needed.",
+            Path::new(&previous).display()
+        );
+    } else {
+        wipe_file(Path::new(&previous))?;
+        log::info!("Stronghold snapshot has been re-encrypted, the previous one has been deleted.");
+    }
+
+    Ok(())
+}
+
+// Overwrites a file with zeros before deleting it, so that its content doesn't stay on the disk. Copy-on-write file
+// systems and SSDs may still keep the old blocks.
+fn wipe_file(path: &Path) -> Result<(), Error> {
+    let length = std::fs::metadata(path)?.len();
+    let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+
+    std::io::Write::write_all(&mut file, &vec![0; length as usize])?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::remove_file(path)?;
+
+    Ok(())
+}
+
+pub async fn self_update_command(check_only: bool) -> Result<(), Error> {
+    let current = env!("CARGO_PKG_VERSION");
+    let client = reqwest::Client::builder()
+        .user_agent(format!("cli-wallet/{current}"))
+        .build()
+        .map_err(|e| Error::Miscellaneous(e.to_string()))?;
+    let release = client
+        .get(LATEST_RELEASE_URL)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| Error::Miscellaneous(format!("can't get the latest release: {e}")))?
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| Error::Miscellaneous(format!("can't get the latest release: {e}")))?;
+    let tag = release["tag_name"]
+        .as_str()
+        .ok_or_else(|| Error::Miscellaneous("the latest release has no tag".to_string()))?;
+    let latest = tag.trim_start_matches('v');
+
+    if version_key(latest) <= version_key(current) {
+        log::info!("The wallet is up to date, version {current}.");
+        return Ok(());
+    }
+
+    log::info!(
+        "Version {latest} is available, this is version {current}: {}",
+        release["html_url"].as_str().unwrap_or(LATEST_RELEASE_URL)
+    );
+
+    if check_only {
+        return Ok(());
+    }
+
+    let asset_name = if cfg!(target_os = "linux") {
+        "wallet-linux"
+    } else if cfg!(target_os = "macos") {
+        "wallet-macos"
+    } else if cfg!(target_os = "windows") {
+        "wallet-windows.exe"
+    } else {
+        return Err(Error::Miscellaneous(
+            "no release binary for this platform, build the wallet from source".to_string(),
+        ));
+    };
+    let asset_url = release["assets"]
+        .as_array()
+        .and_then(|assets| assets.iter().find(|asset| asset["name"] == asset_name))
+        .and_then(|asset| asset["browser_download_url"].as_str())
+        .ok_or_else(|| Error::Miscellaneous(format!("release {tag} has no {asset_name} binary")))?;
+    // The checksums are published in a `|asset|SHA-256 checksum|` table of the release notes.
+    let checksum = release["body"]
+        .as_str()
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut cells = line.split('|').map(str::trim).skip(1);
+            (cells.next() == Some(asset_name)).then(|| cells.next()).flatten()
+        })
+        .find(|checksum| checksum.len() == 64)
+        .ok_or_else(|| {
+            Error::Miscellaneous(format!(
+                "release {tag} publishes no checksum for {asset_name}, refusing to install it"
+            ))
+        })?
+        .to_lowercase();
+
+    log::info!("Downloading {asset_url}");
+
+    let binary = client
+        .get(asset_url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| Error::Miscellaneous(format!("can't download {asset_name}: {e}")))?
+        .bytes()
+        .await
+        .map_err(|e| Error::Miscellaneous(format!("can't download {asset_name}: {e}")))?;
+    let digest = prefix_hex::encode(Sha256::digest(&binary).as_slice());
+
+    if digest.trim_start_matches("0x") != checksum {
+        return Err(Error::Miscellaneous(format!(
+            "checksum mismatch for {asset_name}: expected {checksum}, got {}, refusing to install it",
+            digest.trim_start_matches("0x")
+        )));
+    }
+
+    let executable = std::env::current_exe()?;
+    let new = executable.with_extension("new");
+    let old = executable.with_extension("old");
+
+    std::fs::write(&new, &binary)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::set_permissions(&new, std::fs::Permissions::from_mode(0o755))?;
+    }
+    // A running executable can't be overwritten on Windows but can be renamed.
+    std::fs::rename(&executable, &old)?;
+    if let Err(e) = std::fs::rename(&new, &executable) {
+        std::fs::rename(&old, &executable)?;
+        return Err(e.into());
+    }
+    // Still in use on Windows, left for the next update to overwrite.
+    let _ = std::fs::remove_file(&old);
+
+    log::info!("The wallet has been updated to version {latest}.");
+
+    Ok(())
+}
+
+pub async fn set_node_command(manager: &AccountManager, url: String) -> Result<(), Error> {
+    // The other client options, e.g. the quorum, are kept.
+    let mut options = manager.get_client_options().await;
+
+    options.node_manager_builder.primary_node = None;
+    options.node_manager_builder.nodes.clear();
+    manager.set_client_options(options.with_node(&url)?).await?;
+
+    Ok(())
+}
+
+/// `add-node` command
+pub async fn add_node_command(manager: &AccountManager, url: String, primary: bool) -> Result<(), Error> {
+    let bare = parse_node_url(&url)?;
+    let mut options = manager.get_client_options().await;
+    let builder = &mut options.node_manager_builder;
+
+    if builder
+        .primary_node
+        .iter()
+        .chain(&builder.nodes)
+        .any(|node| bare_url(node) == bare)
+    {
+        return Err(Error::Miscellaneous(format!("{url} is already a node of the wallet")));
+    }
+
+    let options = if primary {
+        // The previous primary node becomes a fallback one.
+        if let Some(previous) = builder.primary_node.take() {
+            builder.nodes.insert(previous);
+        }
+        options.with_primary_node(&url, None)?
+    } else {
+        options.with_node(&url)?
+    };
+
+    manager.set_client_options(options).await?;
+
+    log::info!(
+        "Node {url} has been added{}.",
+        if primary { " as primary node" } else { "" }
+    );
+
+    Ok(())
+}
+
+/// `list-nodes` command
+pub async fn list_nodes_command(manager: &AccountManager) -> Result<(), Error> {
+    let options = manager.get_client_options().await;
+    let builder = &options.node_manager_builder;
+    let mut nodes = builder.nodes.iter().map(Node::from).collect::<Vec<_>>();
+
+    nodes.sort_by(|a, b| a.url.cmp(&b.url));
+
+    let mut list = "Nodes:".to_string();
+
+    for (node, primary) in builder
+        .primary_node
+        .iter()
+        .map(|node| (Node::from(node), true))
+        .chain(nodes.into_iter().map(|node| (node, false)))
+    {
+        let health = match Client::get_node_info(node.url.as_str(), node.auth.clone()).await {
+            Ok(info) if info.status.is_healthy => {
+                format!("healthy, confirmed milestone {}", info.status.confirmed_milestone.index)
+            }
+            Ok(_) => "not healthy".to_string(),
+            Err(_) => "unreachable".to_string(),
+        };
+
+        list = format!(
+            "{list}\n  {}{}: {health}",
+            bare_url(&NodeDto::Node(node)),
+            if primary { " (primary)" } else { "" }
+        );
+    }
+
+    let quorum = if builder.quorum {
+        format!(
+            "at least {} nodes, {}% of them agreeing",
+            builder.min_quorum_size, builder.quorum_threshold
+        )
+    } else {
+        "disabled".to_string()
+    };
+
+    log::info!("{list}\nQuorum: {quorum}");
+
+    Ok(())
+}
+
+/// `node-info` command
+pub async fn node_info_command(manager: &AccountManager) -> Result<(), Error> {
+    let node_info = timed(Phase::Node, manager.get_node_info()).await?;
+    let info = &node_info.node_info;
+    let milestone = |index: u32, timestamp: Option<u32>| match timestamp {
+        Some(timestamp) => format!("{index}, on {}", format_timestamp(u64::from(timestamp))),
+        None => index.to_string(),
+    };
+
+    log::info!(
+        "Node: {} ({} {}), {}\nLatest milestone: {}\nConfirmed milestone: {}\nPruning index: {}\nNetwork: {}, \
+         protocol version {}\nBech32 HRP: {}\nMinimum PoW score: {}\nBelow max depth: {}\nRent structure: {} per \
+         virtual byte, key factor {}, data factor {}\nBase token: {} ({}), {} decimals, supply of {}",
+        node_info.url,
+        info.name,
+        info.version,
+        if info.status.is_healthy {
+            "healthy"
+        } else {
+            "not healthy"
+        },
+        milestone(
+            info.status.latest_milestone.index,
+            info.status.latest_milestone.timestamp
+        ),
+        milestone(
+            info.status.confirmed_milestone.index,
+            info.status.confirmed_milestone.timestamp
+        ),
+        info.status.pruning_index,
+        info.protocol.network_name,
+        info.protocol.version,
+        info.protocol.bech32_hrp,
+        info.protocol.min_pow_score,
+        info.protocol.below_max_depth,
+        info.protocol.rent_structure.v_byte_cost,
+        info.protocol.rent_structure.v_byte_factor_key,
+        info.protocol.rent_structure.v_byte_factor_data,
+        info.base_token.name,
+        info.base_token.ticker_symbol,
+        info.base_token.decimals,
+        info.protocol.token_supply
+    );
+
+    Ok(())
+}
+
+/// `remove-node` command
+pub async fn remove_node_command(manager: &AccountManager, url: String) -> Result<(), Error> {
+    let bare = parse_node_url(&url)?;
+    let mut options = manager.get_client_options().await;
+    let builder = &mut options.node_manager_builder;
+    let count = builder.primary_node.iter().count() + builder.nodes.len();
+
+    if !builder
+        .primary_node
+        .iter()
+        .chain(&builder.nodes)
+        .any(|node| bare_url(node) == bare)
+    {
+        return Err(Error::Miscellaneous(format!(
+            "{url} isn't a node of the wallet, run `list-nodes` to see them"
+        )));
+    }
+    if count == 1 {
+        return Err(Error::Miscellaneous(
+            "the last node can't be removed, run `set-node` to replace it".to_string(),
+        ));
+    }
+    if builder.quorum && builder.min_quorum_size >= count {
+        return Err(Error::Miscellaneous(format!(
+            "the quorum needs {} nodes, lower it or disable it with `set-quorum` first",
+            builder.min_quorum_size
+        )));
+    }
+
+    if builder.primary_node.as_ref().is_some_and(|node| bare_url(node) == bare) {
+        builder.primary_node = None;
+    }
+    builder.nodes.retain(|node| bare_url(node) != bare);
+    manager.set_client_options(options).await?;
+
+    log::info!("Node {url} has been removed.");
+
+    Ok(())
+}
+
+/// `set-quorum` command
+pub async fn set_quorum_command(
+    manager: &AccountManager,
+    disable: bool,
+    min_size: Option<usize>,
+    threshold: Option<usize>,
+) -> Result<(), Error> {
+    let mut options = manager.get_client_options().await;
+    let builder = &options.node_manager_builder;
+    let count = builder.primary_node.iter().count() + builder.nodes.len();
+    let min_size = min_size.unwrap_or(builder.min_quorum_size);
+    let threshold = threshold.unwrap_or(builder.quorum_threshold);
+
+    if !disable {
+        if min_size == 0 || min_size > count {
+            return Err(Error::Miscellaneous(format!(
+                "the quorum size must be between 1 and the {count} node(s) of the wallet, add nodes with `add-node`"
+            )));
+        }
+        if threshold == 0 || threshold > 100 {
+            return Err(Error::Miscellaneous(
+                "the quorum threshold is a percentage, between 1 and 100".to_string(),
+            ));
+        }
+
+        options = options.with_min_quorum_size(min_size).with_quorum_threshold(threshold);
+    }
+
+    manager.set_client_options(options.with_quorum(!disable)).await?;
+
+    if disable {
+        log::info!("Quorum disabled.");
+    } else {
+        log::info!("Quorum enabled: at least {min_size} nodes, {threshold}% of them agreeing.");
+    }
+
+    Ok(())
+}
+
+// Parses a node URL the way the client options store it, to compare it with theirs.
+fn parse_node_url(url: &str) -> Result<Url, Error> {
+    let parsed = Url::parse(url).map_err(|e| Error::Miscellaneous(format!("invalid node URL \"{url}\": {e}")))?;
+
+    Ok(validate_url(parsed)?)
+}
+
+// URL of a node of the client options, without its credentials.
+fn bare_url(node: &NodeDto) -> Url {
+    let mut url = Node::from(node).url;
+
+    // Only fails for URLs that can't have credentials.
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url
+}
+
+pub fn config_command(command: ConfigCommand, profile: Option<&str>) -> Result<(), Error> {
+    match command {
+        ConfigCommand::Get { key } => config_get(profile, key.as_deref()),
+        ConfigCommand::Set { key, value } => config_set(profile, &key, Some(&value)),
+        ConfigCommand::Unset { key } => config_set(profile, &key, None),
+    }
+}
+
+pub fn set_price_source_command(source: Option<String>, currency: String, offline: bool) -> Result<(), Error> {
+    let config = match source {
+        Some(source) => {
+            let config = PriceConfig {
+                source: source.parse()?,
+                currency: currency.to_lowercase(),
+                offline,
+            };
+
+            set_price_config(&config)?;
+            config
+        }
+        None => price_config()?,
+    };
+
+    log::info!(
+        "Price source: {}, currency: {}{}",
+        config.source,
+        config.currency.to_uppercase(),
+        if config.offline { ", offline" } else { "" }
+    );
+
+    Ok(())
+}
+
+pub async fn sign_qr_command(manager: &AccountManager) -> Result<(), Error> {
+    let request = serde_json::from_str::<serde_json::Value>(&read_qr_parts("Pictures of the request QR codes")?)?;
+    // Generated addresses are encoded with the HRP of the existing accounts, which also tells testnets apart.
+    let accounts_bech32_hrp = match manager.get_accounts().await?.first() {
+        Some(account_handle) => account_handle
+            .read()
+            .await
+            .public_addresses()
+            .first()
+            .map(|address| address.address().bech32_hrp().to_string()),
+        None => None,
+    };
+    let bech32_hrp = |coin_type: u32| {
+        accounts_bech32_hrp
+            .clone()
+            .or_else(|| config().bech32_hrp.clone())
+            .unwrap_or_else(|| {
+                if coin_type == IOTA_COIN_TYPE {
+                    IOTA_BECH32_HRP.to_string()
+                } else {
+                    SHIMMER_BECH32_HRP.to_string()
+                }
+            })
+    };
+    let secret_manager = manager.get_secret_manager();
+    let secret_manager = secret_manager.read().await;
+
+    let response = match request["type"].as_str() {
+        Some("generateAddress") => {
+            let field = |name: &str| {
+                request[name]
+                    .as_u64()
+                    .map(|value| value as u32)
+                    .ok_or_else(|| Error::Miscellaneous(format!("missing \"{name}\" in the request")))
+            };
+            let (coin_type, account_index, address_index) =
+                (field("coinType")?, field("accountIndex")?, field("addressIndex")?);
+            let address = secret_manager
+                .generate_addresses(
+                    coin_type,
+                    account_index,
+                    address_index..address_index + 1,
+                    request["internal"].as_bool().unwrap_or_default(),
+                    None,
+                )
+                .await?
+                .first()
+                .map(|address| address.to_bech32(bech32_hrp(coin_type)))
+                .ok_or_else(|| Error::Miscellaneous("no address generated".to_string()))?;
+
+            log::info!("Address {address_index} of account {account_index}: {address}");
+
+            serde_json::json!({ "address": address })
+        }
+        Some("signTransaction") => {
+            let prepared_transaction_data = PreparedTransactionData::try_from_dto_unverified(
+                &serde_json::from_value::<PreparedTransactionDataDto>(request["preparedTransactionData"].clone())?,
+            )
+            .map_err(|e| Error::Miscellaneous(format!("invalid transaction: {e}")))?;
+
+            log_transaction_outputs(&prepared_transaction_data)?;
+
+            if !Confirm::new().with_prompt("Sign this transaction?").interact()? {
+                return Err(Error::Miscellaneous("transaction not signed".to_string()));
+            }
+
+            let unlocks = secret_manager
+                .sign_transaction_essence(&prepared_transaction_data)
+                .await?;
+
+            serde_json::json!({ "unlocks": unlocks.iter().map(UnlockDto::from).collect::<Vec<_>>() })
+        }
+        _ => return Err(Error::Miscellaneous("unknown request".to_string())),
+    };
+
+    show_qr_parts(&response.to_string(), "Scan with the online wallet")?;
+
+    Ok(())
+}
+
+/// Logs the outputs of a prepared transaction, to be checked against the intended transaction before signing it.
+pub fn log_transaction_outputs(prepared_transaction_data: &PreparedTransactionData) -> Result<(), Error> {
+    // The inputs are owned by the wallet, their addresses give the network.
+    let bech32_hrp = prepared_transaction_data
+        .inputs_data
+        .first()
+        .map(|input| Address::try_from_bech32(&input.bech32_address))
+        .transpose()?
+        .map_or_else(|| SHIMMER_BECH32_HRP.to_string(), |(bech32_hrp, _)| bech32_hrp);
+    let coin_type = match bech32_hrp.as_str() {
+        IOTA_BECH32_HRP | IOTA_TESTNET_BECH32_HRP => IOTA_COIN_TYPE,
+        _ => SHIMMER_COIN_TYPE,
+    };
+    let TransactionEssence::Regular(essence) = &prepared_transaction_data.essence;
+    let remainder = prepared_transaction_data
+        .remainder
+        .as_ref()
+        .map(|remainder| remainder.address);
+
+    for output in essence.outputs().iter() {
+        let owner = output_owner(output);
+        log::info!(
+            "Output of {} to {}{}",
+            format_amount(output.amount(), coin_type),
+            owner.map_or_else(
+                || "an unknown address".to_string(),
+                |owner| owner.to_bech32(&bech32_hrp)
+            ),
+            if owner.is_some() && owner == remainder.as_ref() {
+                " (remainder)"
+            } else {
+                ""
+            }
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn split_mnemonic_command(manager: &AccountManager, shares: u8, threshold: u8) -> Result<(), Error> {
+    if threshold < 2 || threshold > shares {
+        return Err(Error::Miscellaneous(format!(
+            "threshold must be between 2 and the number of shares ({shares})"
+        )));
+    }
+
+    let paths = (1..=shares)
+        .map(|index| format!("mnemonic-share-{index}.txt"))
+        .collect::<Vec<_>>();
+
+    if let Some(path) = paths.iter().find(|path| Path::new(path).exists()) {
+        return Err(Error::Miscellaneous(format!(
+            "\"{path}\" already exists, refusing to overwrite it"
+        )));
+    }
+
+    let mnemonic = wallet_mnemonic_prompt(manager).await?;
+
+    for (path, share) in paths.iter().zip(split_mnemonic(&mnemonic, threshold)?) {
+        write_private_file(path, share)?;
+    }
+
+    log::info!("IMPORTANT: mnemonic has been split into {shares} shares, written to \"mnemonic-share-*.txt\".");
+    log::info!(
+        "Any {threshold} of them are enough to recover it with `init --combine-shares`, distribute them safely."
+    );
+
+    Ok(())
+}
+
+/// Writes a raw hex encoded seed to the Stronghold snapshot, where a mnemonic would have been stored by `init`.
+///
+/// This has to happen before the Stronghold secret manager is built, as it only loads the snapshot once.
+pub fn store_seed(snapshot_path: &Path, password: &str, seed: &str) -> Result<(), Error> {
+    let seed = prefix_hex::decode::<Vec<u8>>(seed).map_err(|e| Error::Miscellaneous(format!("invalid seed: {e}")))?;
+
+    if seed.len() != 64 {
+        return Err(Error::Miscellaneous(format!(
+            "invalid seed: expected 64 bytes, got {}",
+            seed.len()
+        )));
+    }
+
+    let key_provider = stronghold_key_provider(password)?;
+    let stronghold = Stronghold::default();
+    let snapshot = SnapshotPath::from_path(snapshot_path);
+    let client = if snapshot_path.exists() {
+        stronghold.load_client_from_snapshot(STRONGHOLD_CLIENT_PATH, &key_provider, &snapshot)
+    } else {
+        stronghold.create_client(STRONGHOLD_CLIENT_PATH)
+    }
+    .map_err(|e| Error::Miscellaneous(e.to_string()))?;
+    let location = Location::generic(STRONGHOLD_SECRET_VAULT_PATH, STRONGHOLD_SEED_RECORD_PATH);
+
+    if client
+        .record_exists(&location)
+        .map_err(|e| Error::Miscellaneous(e.to_string()))?
+    {
+        return Err(Error::Miscellaneous(
+            "a secret is already stored in the Stronghold snapshot".to_string(),
+        ));
+    }
+
+    client
+        .vault(STRONGHOLD_SECRET_VAULT_PATH)
+        .write_secret(location, seed)
+        .map_err(|e| Error::Miscellaneous(e.to_string()))?;
+    stronghold
+        .write_client(STRONGHOLD_CLIENT_PATH)
+        .map_err(|e| Error::Miscellaneous(e.to_string()))?;
+    stronghold
+        .commit_with_keyprovider(&snapshot, &key_provider)
+        .map_err(|e| Error::Miscellaneous(e.to_string()))?;
+
+    Ok(())
+}
+
+pub async fn stronghold_command(command: StrongholdCommand, snapshot_path: &Path) -> Result<(), Error> {
+    match command {
+        StrongholdCommand::List => {
+            let directory = match snapshot_path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            let mut snapshots = std::fs::read_dir(directory)?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|extension| extension == "stronghold"))
+                .collect::<Vec<_>>();
+            snapshots.sort();
+
+            if snapshots.is_empty() {
+                log::info!("No Stronghold snapshot in \"{}\"", directory.display());
+            }
+
+            for snapshot in snapshots {
+                let marker = if same_file(&snapshot, snapshot_path) { "*" } else { " " };
+                log::info!("{marker} {}", snapshot.display());
+            }
+        }
+        StrongholdCommand::SetActive { path } => {
+            if !Path::new(&path).is_file() {
+                return Err(Error::Miscellaneous(format!("\"{path}\" is not a file")));
+            }
+
+            std::fs::write(ACTIVE_STRONGHOLD_FILE, &path)?;
+
+            log::info!("\"{path}\" is now the active Stronghold snapshot.");
+        }
+        StrongholdCommand::Verify { path } => {
+            if !Path::new(&path).is_file() {
+                return Err(Error::Miscellaneous(format!("\"{path}\" is not a file")));
+            }
+
+            let password = get_password("Stronghold password", false)?;
+
+            if snapshot_holds_secret(Path::new(&path), &password)? {
+                log::info!("\"{path}\" is a valid Stronghold snapshot holding a secret.");
+            } else {
+                log::info!("\"{path}\" is a valid Stronghold snapshot but holds no secret.");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// `listen` command, for all the accounts or only one of them.
+pub async fn listen_command(
+    manager: &AccountManager,
+    account_index: Option<u32>,
+    interval: Option<String>,
+) -> Result<(), Error> {
+    let interval = interval.map(|interval| parse_duration(&interval)).transpose()?;
+    // Alias, coin type and base coin balance of the accounts listened to, the balance following their events.
+    let mut accounts = HashMap::new();
+
+    for account_handle in manager.get_accounts().await? {
+        let (index, alias, coin_type) = {
+            let account = account_handle.read().await;
+            (*account.index(), account.alias().clone(), *account.coin_type())
+        };
+
+        if account_index.is_none_or(|account_index| account_index == index) {
+            accounts.insert(
+                index,
+                (alias, coin_type, account_handle.balance().await?.base_coin.total),
+            );
+        }
+    }
+
+    let accounts = Arc::new(Mutex::new(accounts));
+    let events = vec![
+        WalletEventType::NewOutput,
+        WalletEventType::SpentOutput,
+        WalletEventType::TransactionInclusion,
+    ];
+    let _running = Running::start();
+
+    manager
+        .listen(events.clone(), move |event| {
+            if let Some(account) = accounts
+                .lock()
+                .ok()
+                .as_mut()
+                .and_then(|accounts| accounts.get_mut(&event.account_index))
+            {
+                log_event(account, &event.event);
+            }
+        })
+        .await;
+    // The events are emitted while syncing, all the accounts being synced.
+    manager
+        .start_background_syncing(None, interval.map(Duration::from_secs))
+        .await?;
+
+    log::info!("Listening, interrupt to stop.");
+
+    let interrupted = interruption().await;
+
+    manager.stop_background_syncing()?;
+    manager.clear_listeners(events).await;
+    interrupted?;
+
+    log::info!("Stopped listening.");
+
+    Ok(())
+}
+
+// Logs a wallet event of an account, updating its balance.
+fn log_event((alias, coin_type, balance): &mut (String, u32, u64), event: &WalletEvent) {
+    let output_change = |output_id, output: Output, sign, balance: u64| {
+        let mut change = format!("{sign}{}", format_amount(output.amount(), *coin_type));
+
+        if let Output::Nft(nft_output) = &output {
+            change.push_str(&format!(
+                " and NFT {}",
+                nft_output.nft_id().or_from_output_id(output_id)
+            ));
+        }
+        if let Some(native_tokens) = output.native_tokens().filter(|native_tokens| !native_tokens.is_empty()) {
+            change.push_str(&format!(" and {} native token(s)", native_tokens.len()));
+        }
+
+        format!("{change}, balance {}", format_amount(balance, *coin_type))
+    };
+
+    match event {
+        WalletEvent::NewOutput(event) => {
+            if let Ok(output) = Output::try_from_dto_unverified(&event.output.output) {
+                *balance += output.amount();
+                log::info!(
+                    "Account \"{alias}\": new output {}: {}",
+                    event.output.output_id,
+                    output_change(event.output.output_id, output, "+", *balance)
+                );
+            }
+        }
+        WalletEvent::SpentOutput(event) => {
+            if let Ok(output) = Output::try_from_dto_unverified(&event.output.output) {
+                *balance = balance.saturating_sub(output.amount());
+                log::info!(
+                    "Account \"{alias}\": spent output {}: {}",
+                    event.output.output_id,
+                    output_change(event.output.output_id, output, "-", *balance)
+                );
+            }
+        }
+        WalletEvent::TransactionInclusion(event) => log::info!(
+            "Account \"{alias}\": transaction {} {:?}",
+            event.transaction_id,
+            event.inclusion_state
+        ),
+        _ => {}
+    }
+}
+
+pub async fn sync_command(manager: &AccountManager) -> Result<(), Error> {
+    let total_balance = timed(Phase::Node, manager.sync(None)).await?;
+
+    for account_handle in manager.get_accounts().await? {
+        record_balance(&account_handle).await?;
+        report_deposits(&account_handle).await?;
+        report_invoices(&account_handle).await?;
+
+        if let Some(transaction) = maintain_participation(&account_handle).await? {
+            log::info!(
+                "Participation transaction sent for account \"{}\":\ntransaction id: {}\n{:?}",
+                account_handle.read().await.alias(),
+                transaction.transaction_id,
+                transaction.block_id
+            );
+        }
+    }
+
+    log::info!("Synchronized all accounts: {:?}", total_balance);
+
+    Ok(())
+}
+
+// Unspent outputs of an address on the node, found like the library syncs them: the basic and NFT outputs it owns, the
+// alias outputs it governs or controls and the foundries of these aliases.
+async fn node_output_ids(client: &Client, bech32: &str) -> Result<HashSet<OutputId>, Error> {
+    let mut output_ids = client
+        .basic_output_ids(vec![QueryParameter::Address(bech32.to_string())])
+        .await?
+        .into_iter()
+        .collect::<HashSet<_>>();
+    output_ids.extend(
+        client
+            .nft_output_ids(vec![QueryParameter::Address(bech32.to_string())])
+            .await?,
+    );
+
+    let mut alias_output_ids = client
+        .alias_output_ids(vec![QueryParameter::Governor(bech32.to_string())])
+        .await?
+        .into_iter()
+        .collect::<HashSet<_>>();
+    alias_output_ids.extend(
+        client
+            .alias_output_ids(vec![QueryParameter::StateController(bech32.to_string())])
+            .await?,
+    );
+
+    let bech32_hrp = client.get_bech32_hrp()?;
+
+    for response in client.get_outputs(alias_output_ids.iter().copied().collect()).await? {
+        if let Output::Alias(alias_output) =
+            Output::try_from_dto_unverified(&response.output).map_err(|e| Error::Miscellaneous(format!("{e:?}")))?
+        {
+            let alias_id = alias_output
+                .alias_id()
+                .or_from_output_id(response.metadata.output_id()?);
+            let alias_address = Address::Alias(AliasAddress::from(alias_id)).to_bech32(&bech32_hrp);
+
+            output_ids.extend(
+                client
+                    .foundry_output_ids(vec![QueryParameter::AliasAddress(alias_address)])
+                    .await?,
+            );
+        }
+    }
+    output_ids.extend(alias_output_ids);
+
+    Ok(output_ids)
+}
+
+pub async fn verify_command(manager: &AccountManager, repair: bool) -> Result<(), Error> {
+    let mut inconsistent_accounts = Vec::new();
+
+    for account_handle in manager.get_accounts().await? {
+        let alias = account_handle.read().await.alias().clone();
+        let account_outputs = account_handle.unspent_outputs(None).await?;
+        let account_output_ids = account_outputs
+            .iter()
+            .map(|output_data| output_data.output_id)
+            .collect::<HashSet<_>>();
+        let client = account_handle.client();
+        let mut consistent = true;
+
+        for address in account_handle.addresses().await? {
+            let bech32 = address.address().to_bech32();
+            let chain_output_ids = node_output_ids(client, &bech32).await?;
+            let local_outputs = account_outputs
+                .iter()
+                .filter(|output_data| &output_data.address == address.address().as_ref())
+                .map(|output_data| (output_data.output_id, output_data.output.amount()))
+                .collect::<HashMap<_, _>>();
+
+            // An alias is found through both its governor and its state controller, but kept under one of them.
+            let missing = chain_output_ids
+                .iter()
+                .filter(|output_id| !account_output_ids.contains(output_id))
+                .copied()
+                .collect::<Vec<_>>();
+            let unknown = local_outputs
+                .keys()
+                .filter(|output_id| !chain_output_ids.contains(output_id))
+                .collect::<Vec<_>>();
+
+            if missing.is_empty() && unknown.is_empty() {
+                continue;
+            }
+
+            consistent = false;
+
+            let local_amount = local_outputs.values().sum::<u64>();
+            let mut chain_amount = local_outputs
+                .iter()
+                .filter(|(output_id, _)| chain_output_ids.contains(output_id))
+                .map(|(_, amount)| amount)
+                .sum::<u64>();
+
+            for response in client.get_outputs(missing.clone()).await? {
+                chain_amount += Output::try_from_dto_unverified(&response.output)
+                    .map_err(|e| Error::Miscellaneous(format!("{e:?}")))?
+                    .amount();
+            }
+
+            log::warn!(
+                "Account \"{alias}\", address {bech32}: local amount {local_amount}, node amount {chain_amount}"
+            );
+
+            for output_id in missing {
+                log::warn!("  {output_id} is unspent on the node but missing locally");
+            }
+
+            for output_id in unknown {
+                log::warn!("  {output_id} is unspent locally but unknown to the node");
+            }
+        }
+
+        if consistent {
+            log::info!("Account \"{alias}\" is consistent with the node.");
+        } else {
+            inconsistent_accounts.push(account_handle);
+        }
+    }
+
+    if inconsistent_accounts.is_empty() {
+        return Ok(());
+    }
+
+    if !repair {
+        log::info!("Run `verify --repair` to resync the inconsistent accounts.");
+        return Ok(());
+    }
+
+    for account_handle in inconsistent_accounts {
+        let balance = account_handle
+            .sync(Some(SyncOptions {
+                force_syncing: true,
+                ..Default::default()
+            }))
+            .await?;
+
+        log::info!(
+            "Account \"{}\" has been resynced: {:?}",
+            account_handle.read().await.alias(),
+            balance.base_coin
+        );
+    }
+
+    Ok(())
+}
+
+// Derives the snapshot key from the password the same way the Stronghold secret manager of `iota-client` does.
+fn stronghold_key_provider(password: &str) -> Result<KeyProvider, Error> {
+    let mut buffer = [0u8; 64];
+    PBKDF2_HMAC_SHA512(
+        password.as_bytes(),
+        STRONGHOLD_PBKDF_SALT,
+        STRONGHOLD_PBKDF_ITER,
+        &mut buffer,
+    )
+    .map_err(|e| Error::Miscellaneous(e.to_string()))?;
+    let key_provider =
+        KeyProvider::with_passphrase_truncated(buffer[..32].to_vec()).map_err(|e| Error::Miscellaneous(e.to_string()));
+    buffer.iter_mut().for_each(|byte| *byte = 0);
+
+    key_provider
+}
+
+// Opens a snapshot on its own, failing if it is corrupted or the password is wrong, and tells whether it holds a
+// secret.
+fn snapshot_holds_secret(path: &Path, password: &str) -> Result<bool, Error> {
+    let key_provider = stronghold_key_provider(password)?;
+    let client = Stronghold::default()
+        .load_client_from_snapshot(STRONGHOLD_CLIENT_PATH, &key_provider, &SnapshotPath::from_path(path))
+        .map_err(|e| Error::Miscellaneous(format!("snapshot is corrupted or the password is wrong: {e}")))?;
+
+    client
+        .record_exists(&Location::generic(
+            STRONGHOLD_SECRET_VAULT_PATH,
+            STRONGHOLD_SEED_RECORD_PATH,
+        ))
+        .map_err(|e| Error::Miscellaneous(e.to_string()))
+}
+
+// Opens the database the same way the wallet does, it must not be in use.
+fn open_storage(storage_path: &str) -> Result<DB, Error> {
+    let mut options = Options::default();
+    options.set_compression_type(DBCompressionType::Lz4);
+
+    DB::open(&options, storage_path).map_err(|e| Error::Miscellaneous(format!("can't open the database: {e}")))
+}
+
+fn stored_client_options(db: &DB) -> Option<ClientOptions> {
+    let manager = serde_json::from_slice::<serde_json::Value>(&db.get(STORAGE_ACCOUNT_MANAGER_KEY).ok()??).ok()?;
+
+    serde_json::from_value(manager["client_options"].clone()).ok()
+}
+
+// The coin type of the account manager isn't exposed, it's read from the database, which is open and locked by the
+// account manager at this point.
+fn stored_coin_type() -> Result<u32, Error> {
+    let db = DB::open_for_read_only(&Options::default(), storage_path(), false)
+        .map_err(|e| Error::Miscellaneous(format!("can't open the database: {e}")))?;
+    let manager = db
+        .get(STORAGE_ACCOUNT_MANAGER_KEY)
+        .ok()
+        .flatten()
+        .and_then(|manager| serde_json::from_slice::<serde_json::Value>(&manager).ok())
+        .unwrap_or_default();
+
+    manager["coin_type"]
+        .as_u64()
+        .map(|coin_type| coin_type as u32)
+        .ok_or_else(|| Error::Miscellaneous("no coin type in the database".to_string()))
+}
+
+fn storage_size(storage_path: &str) -> Result<u64, Error> {
+    if !Path::new(storage_path).is_dir() {
+        return Err(Error::Miscellaneous(format!("no database at \"{storage_path}\"")));
+    }
+
+    let mut size = 0;
+
+    for entry in std::fs::read_dir(storage_path)? {
+        let entry = entry?;
+
+        // Informational logs of RocksDB, a new one being created every time the database is opened.
+        if !entry.file_name().to_string_lossy().starts_with("LOG") {
+            size += entry.metadata()?.len();
+        }
+    }
+
+    Ok(size)
+}
+
+// Orders `major.minor.patch[-pre.release]` versions, a pre-release coming before its release and numeric identifiers
+// before alphanumeric ones.
+fn version_key(version: &str) -> (Vec<u64>, bool, Vec<(bool, u64, String)>) {
+    let (core, pre_release) = version
+        .split_once('-')
+        .map_or((version, None), |(core, pre)| (core, Some(pre)));
+    let core = core.split('.').map(|part| part.parse().unwrap_or_default()).collect();
+    let identifiers = pre_release
+        .unwrap_or_default()
+        .split('.')
+        .filter(|identifier| !identifier.is_empty())
+        .map(|identifier| match identifier.parse() {
+            Ok(number) => (false, number, String::new()),
+            Err(_) => (true, 0, identifier.to_string()),
+        })
+        .collect();
+
+    (core, pre_release.is_none(), identifiers)
+}
+
+// Keeps the most recent log entries, without the ones of the mnemonic targets and with the passwords and addresses
+// redacted.
+fn redact_logs(logs: &str) -> String {
+    let mut entries: Vec<Vec<&str>> = Vec::new();
+
+    for line in logs.lines() {
+        // Entries start with `YYYY-MM-DD HH:MM:SS (UTC) target`, other lines continue the previous entry.
+        if line.get(19..26) == Some(" (UTC) ") || entries.is_empty() {
+            entries.push(vec![line]);
+        } else if let Some(entry) = entries.last_mut() {
+            entry.push(line);
+        }
+    }
+
+    let entries = entries
+        .into_iter()
+        .filter(|entry| {
+            !entry[0]
+                .get(26..)
+                .and_then(|rest| rest.split_whitespace().next())
+                .is_some_and(|target| target.contains("mnemonic"))
+        })
+        .collect::<Vec<_>>();
+    let mut redacted = String::new();
+
+    for line in entries[entries.len().saturating_sub(BUG_REPORT_LOG_ENTRIES)..]
+        .iter()
+        .flatten()
+    {
+        let line = redact_passwords(line);
+
+        for word in line.split_inclusive(|c: char| !c.is_ascii_alphanumeric()) {
+            let (word, separator) = word.split_at(word.trim_end_matches(|c: char| !c.is_ascii_alphanumeric()).len());
+            let is_address = ["smr1", "rms1", "iota1", "atoi1"]
+                .iter()
+                .any(|hrp| word.starts_with(hrp) && word.len() >= 60);
+
+            redacted.push_str(if is_address { "<address>" } else { word });
+            redacted.push_str(separator);
+        }
+        redacted.push('\n');
+    }
+
+    redacted
+}
+
+// Redacts the quoted values of `password` fields, as logged in the JSON or debug output of secret manager options.
+fn redact_passwords(line: &str) -> String {
+    let mut redacted = String::new();
+    let mut rest = line;
+
+    while let Some(index) = rest.find("password") {
+        let (before, after) = rest.split_at(index + "password".len());
+        let value = after.strip_prefix('"').unwrap_or(after);
+        let value = value.trim_start_matches([':', '=', ' ']);
+        let value = value.strip_prefix("Some(").unwrap_or(value);
+
+        redacted.push_str(before);
+        rest = after;
+
+        if let Some(quoted) = value.strip_prefix('"') {
+            let mut escaped = false;
+            // The closing quote, skipping escaped ones.
+            let end = quoted.find(|c| {
+                let end = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                end
+            });
+
+            if let Some(end) = end {
+                redacted.push_str(&after[..after.len() - quoted.len()]);
+                redacted.push_str("<redacted>");
+                rest = &quoted[end..];
+            }
+        }
+    }
+    redacted.push_str(rest);
+
+    redacted
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+// Asks for the mnemonic and makes sure it is the one backing the wallet. The Stronghold only keeps the derived seed, so
+// this is the only way to get it back.
+async fn wallet_mnemonic_prompt(manager: &AccountManager) -> Result<String, Error> {
+    let mnemonic = Password::new().with_prompt("Mnemonic").interact()?;
+    let mnemonic = mnemonic.trim().to_string();
+
+    manager.verify_mnemonic(&mnemonic)?;
+
+    if let Some(account_handle) = manager.get_accounts().await?.first() {
+        let account = account_handle.read().await;
+
+        if let Some(address) = account.public_addresses().first() {
+            let derived_addresses = MnemonicSecretManager::try_from_mnemonic(&mnemonic)?
+                .generate_addresses(
+                    *account.coin_type(),
+                    *account.index(),
+                    *address.key_index()..*address.key_index() + 1,
+                    false,
+                    None,
+                )
+                .await?;
+
+            if derived_addresses.first() != Some(address.address().as_ref()) {
+                return Err(Error::Miscellaneous(
+                    "the provided mnemonic doesn't match the wallet".to_string(),
+                ));
+            }
+        }
+    }
+
+    Ok(mnemonic)
+}
+
+/// Splits a mnemonic into an endless stream of shares in the cli-wallet specific `<threshold>:<digest>:<share>` format.
+fn split_mnemonic(mnemonic: &str, threshold: u8) -> Result<impl Iterator<Item = String>, Error> {
+    let entropy = wordlist::decode(mnemonic, &wordlist::ENGLISH).map_err(|e| Error::Miscellaneous(format!("{e:?}")))?;
+    // Shares are prefixed with the threshold and a digest of the secret so that mixing up shares of different sets, or
+    // providing too few of them, can be detected when combining them.
+    let digest = prefix_hex::encode(&Blake2b256::digest(&entropy)[..4]);
+
+    Ok(Sharks(threshold)
+        .dealer(&entropy)
+        .map(move |share| format!("{threshold}:{digest}:{}\n", prefix_hex::encode(Vec::from(&share)))))
+}
+
+// Recovers a mnemonic from share files created by `split-mnemonic`.
+fn combine_mnemonic_shares(paths: &[String]) -> Result<String, Error> {
+    let mut set: Option<(u8, String)> = None;
+    let mut shares = Vec::new();
+
+    for path in paths {
+        let content = std::fs::read_to_string(path)?;
+        let invalid_share = || Error::Miscellaneous(format!("\"{path}\" is not a valid mnemonic share"));
+        let mut parts = content.trim().split(':');
+        let (threshold, digest, share) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(threshold), Some(digest), Some(share), None) => (threshold, digest, share),
+            _ => return Err(invalid_share()),
+        };
+        let threshold = threshold.parse::<u8>().map_err(|_| invalid_share())?;
+        let share = prefix_hex::decode::<Vec<u8>>(share).map_err(|_| invalid_share())?;
+
+        match &set {
+            Some((set_threshold, set_digest)) if (*set_threshold, set_digest.as_str()) != (threshold, digest) => {
+                return Err(Error::Miscellaneous(format!(
+                    "\"{path}\" doesn't belong to the same set of shares"
+                )));
+            }
+            Some(_) => {}
+            None => set = Some((threshold, digest.to_string())),
+        }
+
+        shares.push(Share::try_from(share.as_slice()).map_err(|_| invalid_share())?);
+    }
+
+    let (threshold, digest) = set.ok_or_else(|| Error::Miscellaneous("no mnemonic share provided".to_string()))?;
+
+    if shares.len() < threshold as usize {
+        return Err(Error::Miscellaneous(format!(
+            "{threshold} shares are needed to recover the mnemonic, only {} provided",
+            shares.len()
+        )));
+    }
+
+    let entropy = Sharks(threshold)
+        .recover(&shares)
+        .map_err(|e| Error::Miscellaneous(e.to_string()))?;
+
+    if prefix_hex::encode(&Blake2b256::digest(&entropy)[..4]) != digest {
+        return Err(Error::Miscellaneous(
+            "the recovered mnemonic doesn't match the shares digest, some shares may be corrupted".to_string(),
+        ));
+    }
+
+    wordlist::encode(&entropy, &wordlist::ENGLISH).map_err(|e| Error::Miscellaneous(format!("{e:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str = "aunt middle impose faith ramp kid olive good practice motor grab ready group episode oven \
+                            matrix silver rhythm avocado assume humble tiger shiver hurt";
+
+    // Removes the directory of the share files once dropped, even when a test fails.
+    struct SharesDir(std::path::PathBuf);
+
+    impl Drop for SharesDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn write_shares(name: &str, shares: &[String]) -> (SharesDir, Vec<String>) {
+        let dir = std::env::temp_dir().join(format!("cli-wallet-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let paths = shares
+            .iter()
+            .enumerate()
+            .map(|(index, share)| {
+                let path = dir.join(format!("mnemonic-share-{index}.txt"));
+                std::fs::write(&path, share).unwrap();
+                path.to_string_lossy().into_owned()
+            })
+            .collect();
+
+        (SharesDir(dir), paths)
+    }
+
+    #[test]
+    fn version_key_orders_versions() {
+        assert!(version_key("1.0.1") > version_key("1.0.0"));
+        assert!(version_key("1.10.0") > version_key("1.9.0"));
+        assert!(version_key("2.0.0") > version_key("1.99.99"));
+        assert!(version_key("1.0.0") > version_key("1.0.0-rc.2"));
+        assert!(version_key("1.0.0-rc.2") > version_key("1.0.0-rc.1"));
+        assert!(version_key("1.0.0-rc.10") > version_key("1.0.0-rc.9"));
+        assert!(version_key("1.0.0-rc.1") > version_key("1.0.0-beta.11"));
+        assert!(version_key("1.0.0-beta.2") > version_key("1.0.0-alpha"));
+        assert!(version_key("1.0.0-alpha.1") > version_key("1.0.0-alpha"));
+        assert!(version_key("1.0.0-alpha.beta") > version_key("1.0.0-alpha.1"));
+        assert!(version_key("1.0.0-rc.1") > version_key("0.9.9"));
+    }
+
+    #[test]
+    fn version_key_equal_versions() {
+        assert_eq!(version_key("1.0.0"), version_key("1.0.0"));
+        assert_eq!(version_key("1.0.0-rc.2"), version_key("1.0.0-rc.2"));
+        assert!(version_key("1.0.0-rc.2") <= version_key("1.0.0-rc.2"));
+        assert_ne!(version_key("1.0.0-rc.2"), version_key("1.0.0"));
+    }
+
+    #[test]
+    fn redact_logs_strips_secrets() {
+        let address = "smr1qqy7d9m4lf9kl3sk6e3fprjmhc9wkg72ll5h4yh9t0t5ttw3l6zqzwqtnf3";
+        let logs = format!(
+            "2022-12-01 10:00:00 (UTC) wallet::command::account_manager           INFO  Synced\n\
+             2022-12-01 10:00:01 (UTC) iota_client::secret::mnemonic              DEBUG mnemonic: aunt middle impose\n\
+             faith ramp kid olive good practice\n\
+             2022-12-01 10:00:02 (UTC) iota_wallet::storage::manager              DEBUG get_secret_manager \
+             {{\"Stronghold\":{{\"password\":\"hunter2 \\\" quoted\",\"timeout\":null}}}}\n\
+             2022-12-01 10:00:03 (UTC) wallet::command::account                   DEBUG StrongholdDto {{ password: \
+             Some(\"hunter2\"), timeout: None }}\n\
+             2022-12-01 10:00:04 (UTC) wallet::command::account                   INFO  Sent to {address}, the password \
+             was fine"
+        );
+
+        let redacted = redact_logs(&logs);
+
+        for secret in ["aunt", "faith", "hunter2", "quoted", address] {
+            assert!(!redacted.contains(secret), "{secret} leaked in:\n{redacted}");
+        }
+        assert!(redacted.contains("INFO  Synced"), "{redacted}");
+        assert!(redacted.contains("{\"Stronghold\":{\"password\":\"<redacted>\",\"timeout\":null}}"));
+        assert!(redacted.contains("password: Some(\"<redacted>\"), timeout: None"));
+        assert!(redacted.contains("Sent to <address>, the password was fine"));
+        assert_eq!(redacted.lines().count(), 4);
+    }
+
+    #[test]
+    fn combine_shares_round_trip() {
+        let shares = split_mnemonic(MNEMONIC, 3).unwrap().take(5).collect::<Vec<_>>();
+        let (_dir, paths) = write_shares("round-trip", &shares);
+
+        for subset in [[0, 1, 2], [0, 2, 4], [4, 3, 1]] {
+            let subset = subset.iter().map(|&index| paths[index].clone()).collect::<Vec<_>>();
+            assert_eq!(combine_mnemonic_shares(&subset).unwrap(), MNEMONIC);
+        }
+        assert_eq!(combine_mnemonic_shares(&paths).unwrap(), MNEMONIC);
+    }
+
+    #[test]
+    fn combine_shares_rejects_insufficient_shares() {
+        let shares = split_mnemonic(MNEMONIC, 3).unwrap().take(2).collect::<Vec<_>>();
+        let (_dir, paths) = write_shares("insufficient", &shares);
+
+        let error = combine_mnemonic_shares(&paths).unwrap_err().to_string();
+        assert!(error.contains("3 shares are needed"), "{error}");
+        assert!(combine_mnemonic_shares(&[]).is_err());
+    }
+
+    #[test]
+    fn combine_shares_rejects_corrupted_shares() {
+        let mut shares = split_mnemonic(MNEMONIC, 2).unwrap().take(2).collect::<Vec<_>>();
+        // Flips the last hex digit of the second share.
+        let last = shares[1].trim_end().len() - 1;
+        let flipped = if &shares[1][last..=last] == "0" { "1" } else { "0" };
+        shares[1].replace_range(last..=last, flipped);
+        let (_dir, paths) = write_shares("corrupted", &shares);
+
+        let error = combine_mnemonic_shares(&paths).unwrap_err().to_string();
+        assert!(error.contains("some shares may be corrupted"), "{error}");
+    }
+
+    #[test]
+    fn combine_shares_rejects_mixed_sets_and_malformed_shares() {
+        let other = generate_mnemonic().unwrap();
+        let shares = [
+            split_mnemonic(MNEMONIC, 2).unwrap().next().unwrap(),
+            split_mnemonic(&other, 2).unwrap().nth(1).unwrap(),
+            "2:0x00000000".to_string(),
+        ];
+        let (_dir, paths) = write_shares("mixed", &shares);
+
+        let error = combine_mnemonic_shares(&paths[..2]).unwrap_err().to_string();
+        assert!(error.contains("doesn't belong to the same set"), "{error}");
+        let error = combine_mnemonic_shares(&paths[2..]).unwrap_err().to_string();
+        assert!(error.contains("is not a valid mnemonic share"), "{error}");
+    }
 }
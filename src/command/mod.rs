@@ -3,3 +3,4 @@
 
 pub mod account;
 pub mod account_manager;
+pub mod participation;
@@ -0,0 +1,190 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use clap::Subcommand;
+use iota_wallet::account::AccountHandle;
+
+use crate::{
+    command::account::{voting_power_command, VotingPowerCommand},
+    error::Error,
+    helper::{format_amount, print_paged},
+    participation::{
+        counted_participations, current_voting_power, event, events, participations, stop_participating, vote, Event,
+    },
+};
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ParticipationCommand {
+    /// Decrease the voting power, dissolving the voting output when it drops to zero: `participation
+    /// decrease-voting-power 1000000`
+    DecreaseVotingPower { amount: u64 },
+    /// List the events of the node, ballots with their questions and answers and staking events, with their status.
+    Events,
+    /// Increase the voting power, creating the voting output if needed: `participation increase-voting-power 1000000`
+    IncreaseVotingPower { amount: u64 },
+    /// Show the voting power and the events the account participates in, with the answers given and whether the node
+    /// counts them.
+    Status,
+    /// Stop participating in an event, keeping the voting power: `participation stop-vote 0x...`
+    StopVote { event_id: String },
+    /// Vote in a ballot with the value of an answer to each of its questions, in order: `participation vote 0x... 1 2`
+    Vote {
+        event_id: String,
+        #[clap(required = true)]
+        answers: Vec<u8>,
+    },
+}
+
+/// `participation` command
+pub async fn participation_command(account_handle: &AccountHandle, command: ParticipationCommand) -> Result<(), Error> {
+    match command {
+        ParticipationCommand::DecreaseVotingPower { amount } => {
+            voting_power_command(account_handle, VotingPowerCommand::Decrease { amount }).await
+        }
+        ParticipationCommand::Events => events_command(account_handle).await,
+        ParticipationCommand::IncreaseVotingPower { amount } => {
+            voting_power_command(account_handle, VotingPowerCommand::Increase { amount }).await
+        }
+        ParticipationCommand::Status => status_command(account_handle).await,
+        ParticipationCommand::StopVote { event_id } => stop_vote_command(account_handle, event_id).await,
+        ParticipationCommand::Vote { event_id, answers } => vote_command(account_handle, event_id, answers).await,
+    }
+}
+
+// `participation events` command
+async fn events_command(account_handle: &AccountHandle) -> Result<(), Error> {
+    let events = events(account_handle).await?;
+
+    if events.is_empty() {
+        log::info!("The node has no participation events.");
+        return Ok(());
+    }
+
+    let list = events.iter().map(format_event).collect::<Vec<_>>().join("\n\n");
+
+    print_paged(&list);
+
+    Ok(())
+}
+
+// `participation status` command
+async fn status_command(account_handle: &AccountHandle) -> Result<(), Error> {
+    let coin_type = *account_handle.read().await.coin_type();
+    let voting_power = current_voting_power(account_handle).await?;
+    let participations = participations(account_handle).await?;
+    let counted = counted_participations(account_handle).await?;
+    let mut status = format!("Voting power: {}", format_amount(voting_power, coin_type));
+
+    if participations.is_empty() {
+        status.push_str("\nNot participating in any event.");
+    }
+
+    for (event_id, answers) in participations {
+        let event = event(account_handle, &event_id).await?;
+        let answers = if answers.is_empty() {
+            "staking".to_string()
+        } else {
+            format!(
+                "answers {}",
+                answers.iter().map(u8::to_string).collect::<Vec<_>>().join(", ")
+            )
+        };
+        // The node only counts the voting output once it has seen it while the event was holding.
+        let counted = match counted.as_ref().map(|counted| &counted[&event_id]) {
+            Some(participation) if participation.is_object() => {
+                let amount = format_amount(participation["amount"].as_u64().unwrap_or_default(), coin_type);
+                let start = participation["startMilestoneIndex"].as_u64().unwrap_or_default();
+
+                match participation["endMilestoneIndex"].as_u64() {
+                    Some(end) if end > 0 => format!("counted with {amount} from milestone {start} to {end}"),
+                    _ => format!("counted with {amount} since milestone {start}"),
+                }
+            }
+            _ => "not counted by the node yet".to_string(),
+        };
+
+        status.push_str(&format!(
+            "\n{} \"{}\" ({}, {}): {answers}, {counted}",
+            event.id,
+            event.name(),
+            event.kind(),
+            event.status()
+        ));
+    }
+
+    log::info!("{status}");
+
+    Ok(())
+}
+
+// `participation stop-vote` command
+async fn stop_vote_command(account_handle: &AccountHandle, event_id: String) -> Result<(), Error> {
+    match stop_participating(account_handle, &event_id).await? {
+        Some(transaction) => log::info!(
+            "Participation transaction sent:\ntransaction id: {}\n{:?}",
+            transaction.transaction_id,
+            transaction.block_id
+        ),
+        None => log::info!("No longer participating in event {event_id}."),
+    }
+
+    Ok(())
+}
+
+// `participation vote` command
+async fn vote_command(account_handle: &AccountHandle, event_id: String, answers: Vec<u8>) -> Result<(), Error> {
+    let transaction = vote(account_handle, &event_id, answers).await?;
+
+    log::info!(
+        "Vote transaction sent:\ntransaction id: {}\n{:?}",
+        transaction.transaction_id,
+        transaction.block_id
+    );
+
+    Ok(())
+}
+
+// Formats an event with its milestones and, for a ballot, its questions with the values to vote for their answers and
+// the votes they got.
+fn format_event(event: &Event) -> String {
+    let mut text = format!(
+        "{}  {:<7}  {:<10}  {}\n    commencing at milestone {}, holding from {} to {}",
+        event.id,
+        event.kind(),
+        event.status(),
+        event.name(),
+        event.info["milestoneIndexCommence"],
+        event.info["milestoneIndexStart"],
+        event.info["milestoneIndexEnd"]
+    );
+
+    for (index, question) in event.questions().iter().enumerate() {
+        text.push_str(&format!(
+            "\n    {}. {}",
+            index + 1,
+            question["text"].as_str().unwrap_or_default()
+        ));
+
+        for answer in question["answers"].as_array().into_iter().flatten() {
+            // The votes are listed by question and answer value in the status.
+            let votes = event.status["questions"][index]["answers"]
+                .as_array()
+                .and_then(|votes| votes.iter().find(|votes| votes["value"] == answer["value"]));
+
+            text.push_str(&format!(
+                "\n        {}: {}",
+                answer["value"],
+                answer["text"].as_str().unwrap_or_default()
+            ));
+
+            if let Some(votes) = votes {
+                text.push_str(&format!(
+                    " ({} currently, {} accumulated)",
+                    votes["current"], votes["accumulated"]
+                ));
+            }
+        }
+    }
+
+    text
+}
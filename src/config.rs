@@ -0,0 +1,323 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{env::var_os, sync::OnceLock};
+
+use clap::{ArgMatches, ValueSource};
+
+use crate::{
+    command::{account::OutputFormat, account_manager::AccountManagerCli},
+    error::Error,
+};
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Kind of the value of a setting, checked when it's set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Bool,
+    Integer,
+    String,
+    Strings,
+}
+
+// The settings of the config file, by key.
+const SETTINGS: [(&str, Kind); 11] = [
+    ("account", Kind::String),
+    ("backup-max-age", Kind::Integer),
+    ("bech32-hrp", Kind::String),
+    ("faucet-url", Kind::String),
+    ("nodes", Kind::Strings),
+    ("output", Kind::String),
+    ("password-timeout", Kind::String),
+    ("price-source", Kind::String),
+    ("storage-path", Kind::String),
+    ("stronghold-path", Kind::String),
+    ("timings", Kind::Bool),
+];
+
+/// Settings of the config file for the selected profile, the built-in defaults applying to the missing ones.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub account: Option<String>,
+    pub backup_max_age: Option<u64>,
+    pub bech32_hrp: Option<String>,
+    pub faucet_url: Option<String>,
+    pub nodes: Vec<String>,
+    pub output: Option<OutputFormat>,
+    pub password_timeout: Option<String>,
+    pub price_source: Option<String>,
+    pub storage_path: Option<String>,
+    pub stronghold_path: Option<String>,
+    pub timings: Option<bool>,
+}
+
+impl Config {
+    // Reads the settings of a table of the config file, the top-level one or a profile.
+    fn from_table(table: &toml::value::Table) -> Result<Self, Error> {
+        let string = |key: &str| table.get(key).and_then(toml::Value::as_str).map(str::to_string);
+
+        Ok(Self {
+            account: string("account"),
+            backup_max_age: table
+                .get("backup-max-age")
+                .and_then(toml::Value::as_integer)
+                .map(|days| days.max(0) as u64),
+            bech32_hrp: string("bech32-hrp"),
+            faucet_url: string("faucet-url"),
+            nodes: table
+                .get("nodes")
+                .and_then(toml::Value::as_array)
+                .map(|nodes| {
+                    nodes
+                        .iter()
+                        .filter_map(|node| node.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            output: string("output").map(|output| parse_output(&output)).transpose()?,
+            password_timeout: string("password-timeout"),
+            price_source: string("price-source"),
+            storage_path: string("storage-path"),
+            stronghold_path: string("stronghold-path"),
+            timings: table.get("timings").and_then(toml::Value::as_bool),
+        })
+    }
+
+    // The settings of a profile override the top-level ones.
+    fn or(self, other: Self) -> Self {
+        Self {
+            account: self.account.or(other.account),
+            backup_max_age: self.backup_max_age.or(other.backup_max_age),
+            bech32_hrp: self.bech32_hrp.or(other.bech32_hrp),
+            faucet_url: self.faucet_url.or(other.faucet_url),
+            nodes: if self.nodes.is_empty() { other.nodes } else { self.nodes },
+            output: self.output.or(other.output),
+            password_timeout: self.password_timeout.or(other.password_timeout),
+            price_source: self.price_source.or(other.price_source),
+            storage_path: self.storage_path.or(other.storage_path),
+            stronghold_path: self.stronghold_path.or(other.stronghold_path),
+            timings: self.timings.or(other.timings),
+        }
+    }
+
+    /// Fills the options of the command line that were neither given nor set through their environment variable.
+    pub fn apply(&self, cli: &mut AccountManagerCli, matches: &ArgMatches) {
+        let defaulted = |id: &str| {
+            !matches!(
+                matches.value_source(id),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            )
+        };
+
+        if defaulted("account") {
+            cli.account = cli.account.take().or_else(|| self.account.clone());
+        }
+        if defaulted("backup-max-age") {
+            if let Some(backup_max_age) = self.backup_max_age {
+                cli.backup_max_age = backup_max_age;
+            }
+        }
+        if defaulted("output") {
+            if let Some(output) = self.output {
+                cli.output = output;
+            }
+        }
+        if defaulted("password-timeout") {
+            cli.password_timeout = cli.password_timeout.take().or_else(|| self.password_timeout.clone());
+        }
+        if defaulted("timings") {
+            cli.timings = cli.timings || self.timings.unwrap_or_default();
+        }
+        // `WALLET_STRONGHOLD_PATH` still overrides the config file.
+        if defaulted("stronghold-path") && var_os("WALLET_STRONGHOLD_PATH").is_none() {
+            cli.stronghold_path = cli.stronghold_path.take().or_else(|| self.stronghold_path.clone());
+        }
+    }
+}
+
+fn parse_output(output: &str) -> Result<OutputFormat, Error> {
+    match output {
+        "text" => Ok(OutputFormat::Text),
+        "json" => Ok(OutputFormat::Json),
+        _ => Err(Error::Miscellaneous(format!(
+            "invalid output \"{output}\" in the config file, expected text or json"
+        ))),
+    }
+}
+
+/// Path of the config file, `./cli-wallet.toml` unless set with the `WALLET_CONFIG_PATH` environment variable.
+pub fn config_path() -> String {
+    var_os("WALLET_CONFIG_PATH").map_or_else(
+        || "./cli-wallet.toml".to_string(),
+        |os_str| os_str.into_string().expect("invalid WALLET_CONFIG_PATH"),
+    )
+}
+
+// The whole config file, an empty one if it doesn't exist.
+fn read_config_file() -> Result<toml::value::Table, Error> {
+    match std::fs::read_to_string(config_path()) {
+        Ok(config) => config
+            .parse::<toml::Value>()
+            .map_err(|e| Error::Miscellaneous(format!("invalid config file \"{}\": {e}", config_path())))?
+            .as_table()
+            .cloned()
+            .ok_or_else(|| Error::Miscellaneous(format!("invalid config file \"{}\"", config_path()))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(toml::value::Table::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// The table holding the settings of a profile, or the top-level one.
+fn profile_table<'a>(file: &'a toml::value::Table, profile: Option<&str>) -> Option<&'a toml::value::Table> {
+    match profile {
+        Some(profile) => file
+            .get("profiles")
+            .and_then(toml::Value::as_table)
+            .and_then(|profiles| profiles.get(profile))
+            .and_then(toml::Value::as_table),
+        None => Some(file),
+    }
+}
+
+/// Loads the settings of the config file, of a profile on top of the top-level ones if one is given, once at startup.
+pub fn load_config(profile: Option<&str>) -> Result<&'static Config, Error> {
+    let file = read_config_file()?;
+    let mut config = Config::from_table(&file)?;
+
+    if let Some(profile) = profile {
+        let table = profile_table(&file, Some(profile)).ok_or_else(|| {
+            Error::Miscellaneous(format!(
+                "no profile \"{profile}\" in \"{}\", add a [profiles.{profile}] table or run `wallet --profile \
+                 {profile} config set <key> <value>`",
+                config_path()
+            ))
+        })?;
+
+        config = Config::from_table(table)?.or(config);
+    }
+
+    Ok(CONFIG.get_or_init(|| config))
+}
+
+/// The settings loaded at startup, or none if the config file wasn't loaded.
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+fn setting_kind(key: &str) -> Result<Kind, Error> {
+    SETTINGS
+        .iter()
+        .find(|(setting, _)| *setting == key)
+        .map(|(_, kind)| *kind)
+        .ok_or_else(|| {
+            Error::Miscellaneous(format!(
+                "unknown setting \"{key}\", expected one of {}",
+                SETTINGS.map(|(setting, _)| setting).join(", ")
+            ))
+        })
+}
+
+/// Prints a setting of a profile, or the top-level one, or all of them without a key.
+pub fn config_get(profile: Option<&str>, key: Option<&str>) -> Result<(), Error> {
+    let file = read_config_file()?;
+    let table = profile_table(&file, profile).cloned().unwrap_or_default();
+
+    match key {
+        Some(key) => {
+            setting_kind(key)?;
+
+            match table.get(key) {
+                Some(value) => log::info!("{key} = {value}"),
+                None => log::info!("{key} isn't set, the built-in default applies"),
+            }
+        }
+        None => {
+            let mut log = format!("Settings of \"{}\"", config_path());
+
+            if let Some(profile) = profile {
+                log = format!("{log}, profile \"{profile}\"");
+            }
+            for (setting, _) in SETTINGS {
+                if let Some(value) = table.get(setting) {
+                    log = format!("{log}\n  {setting} = {value}");
+                }
+            }
+            if profile.is_none() {
+                if let Some(profiles) = file.get("profiles").and_then(toml::Value::as_table) {
+                    log = format!(
+                        "{log}\nProfiles: {}",
+                        profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+                    );
+                }
+            }
+
+            log::info!("{log}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Sets a setting of a profile, or the top-level one, creating the config file and the profile if needed. Without a
+/// value, the setting is removed.
+pub fn config_set(profile: Option<&str>, key: &str, value: Option<&str>) -> Result<(), Error> {
+    let kind = setting_kind(key)?;
+    let value = value
+        .map(|value| match kind {
+            Kind::Bool => value
+                .parse::<bool>()
+                .map(toml::Value::Boolean)
+                .map_err(|_| Error::Miscellaneous(format!("{key} must be true or false"))),
+            Kind::Integer => value
+                .parse::<u32>()
+                .map(|value| toml::Value::Integer(value.into()))
+                .map_err(|_| Error::Miscellaneous(format!("{key} must be a positive number"))),
+            Kind::String if key == "output" => parse_output(value).map(|_| toml::Value::String(value.to_string())),
+            Kind::String => Ok(toml::Value::String(value.to_string())),
+            // Comma separated.
+            Kind::Strings => Ok(toml::Value::Array(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(|value| toml::Value::String(value.to_string()))
+                    .collect(),
+            )),
+        })
+        .transpose()?;
+    let mut file = read_config_file()?;
+    let table = match profile {
+        Some(profile) => file
+            .entry("profiles")
+            .or_insert_with(|| toml::Value::Table(toml::value::Table::new()))
+            .as_table_mut()
+            .and_then(|profiles| {
+                profiles
+                    .entry(profile)
+                    .or_insert_with(|| toml::Value::Table(toml::value::Table::new()))
+                    .as_table_mut()
+            })
+            .ok_or_else(|| Error::Miscellaneous(format!("invalid profiles in \"{}\"", config_path())))?,
+        None => &mut file,
+    };
+
+    match value {
+        Some(value) => {
+            log::info!("{key} = {value}");
+            table.insert(key.to_string(), value);
+        }
+        None => {
+            table.remove(key);
+            log::info!("{key} unset, the built-in default applies");
+        }
+    }
+
+    std::fs::write(
+        config_path(),
+        toml::to_string_pretty(&toml::Value::Table(file))
+            .map_err(|e| Error::Miscellaneous(format!("can't write the config file: {e}")))?,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,233 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use iota_wallet::{account::AccountHandle, iota_client::block::address::Address};
+
+use crate::{
+    error::Error,
+    helper::{format_amount, received_amount, storage_path},
+    timings::{timed, Phase},
+};
+
+// Addresses added to the pool at once when it runs dry, generated in a single Stronghold session.
+const POOL_BATCH: u32 = 20;
+
+/// An external order and the deposit address assigned to it.
+#[derive(Debug, Clone)]
+pub struct Order {
+    pub address: String,
+    pub key_index: u32,
+    pub assigned_at: u64,
+    /// Amount received as of the last sync.
+    pub received: u64,
+}
+
+// The unused addresses of an account and the orders they were assigned to.
+#[derive(Debug, Default)]
+struct Deposits {
+    pool: Vec<(u32, String)>,
+    orders: BTreeMap<String, Order>,
+}
+
+// The deposits are kept next to the database, by account index.
+fn deposits_path() -> String {
+    format!("{}-deposits.json", storage_path())
+}
+
+fn read_deposits() -> Result<BTreeMap<u32, Deposits>, Error> {
+    let deposits = match std::fs::read_to_string(deposits_path()) {
+        Ok(deposits) => serde_json::from_str::<BTreeMap<u32, serde_json::Value>>(&deposits)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(deposits
+        .into_iter()
+        .map(|(account_index, deposits)| {
+            let pool = deposits["pool"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|address| {
+                    Some((
+                        u32::try_from(address["keyIndex"].as_u64()?).ok()?,
+                        address["address"].as_str()?.to_string(),
+                    ))
+                })
+                .collect();
+            let orders = deposits["orders"]
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter_map(|(order_id, order)| {
+                    Some((
+                        order_id.clone(),
+                        Order {
+                            address: order["address"].as_str()?.to_string(),
+                            key_index: u32::try_from(order["keyIndex"].as_u64()?).ok()?,
+                            assigned_at: order["assignedAt"].as_u64().unwrap_or_default(),
+                            received: order["received"].as_u64().unwrap_or_default(),
+                        },
+                    ))
+                })
+                .collect();
+
+            (account_index, Deposits { pool, orders })
+        })
+        .collect())
+}
+
+fn write_deposits(deposits: &BTreeMap<u32, Deposits>) -> Result<(), Error> {
+    let deposits = deposits
+        .iter()
+        .map(|(account_index, deposits)| {
+            let pool = deposits
+                .pool
+                .iter()
+                .map(|(key_index, address)| serde_json::json!({ "keyIndex": key_index, "address": address }))
+                .collect::<Vec<_>>();
+            let orders = deposits
+                .orders
+                .iter()
+                .map(|(order_id, order)| {
+                    (
+                        order_id,
+                        serde_json::json!({
+                            "address": order.address,
+                            "keyIndex": order.key_index,
+                            "assignedAt": order.assigned_at,
+                            "received": order.received,
+                        }),
+                    )
+                })
+                .collect::<BTreeMap<_, _>>();
+
+            (account_index, serde_json::json!({ "pool": pool, "orders": orders }))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    std::fs::write(deposits_path(), serde_json::to_string_pretty(&deposits)?)?;
+
+    Ok(())
+}
+
+/// Assigns an unused address of the pool to an order, refilling the pool when it's empty. An order keeps the address it
+/// was first assigned, returned with `false`.
+pub async fn assign_address(account_handle: &AccountHandle, order_id: &str) -> Result<(Order, bool), Error> {
+    if order_id.is_empty() {
+        return Err(Error::Miscellaneous("the order id can't be empty".to_string()));
+    }
+
+    let account_index = *account_handle.read().await.index();
+    let mut all_deposits = read_deposits()?;
+    let deposits = all_deposits.entry(account_index).or_default();
+
+    if let Some(order) = deposits.orders.get(order_id) {
+        return Ok((order.clone(), false));
+    }
+
+    if deposits.pool.is_empty() {
+        let addresses = timed(
+            Phase::SecretManager,
+            account_handle.generate_addresses(POOL_BATCH, None),
+        )
+        .await?;
+
+        // Without the keys, with an external signer, no address is generated.
+        if addresses.is_empty() {
+            return Err(Error::ExternalSignerUnsupported);
+        }
+
+        deposits.pool = addresses
+            .iter()
+            .map(|address| (*address.key_index(), address.address().to_bech32()))
+            .collect();
+
+        log::info!("{} addresses added to the deposit address pool.", deposits.pool.len());
+    }
+
+    let (key_index, address) = deposits.pool.remove(0);
+    let order = Order {
+        address,
+        key_index,
+        assigned_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs()),
+        received: 0,
+    };
+
+    deposits.orders.insert(order_id.to_string(), order.clone());
+    write_deposits(&all_deposits)?;
+
+    Ok((order, true))
+}
+
+/// Returns the number of addresses left in the pool and the orders of the account, with the amounts they received
+/// according to the outputs of the account.
+pub async fn received_by_order(account_handle: &AccountHandle) -> Result<(usize, BTreeMap<String, Order>), Error> {
+    let account_index = *account_handle.read().await.index();
+    let deposits = read_deposits()?.remove(&account_index).unwrap_or_default();
+    let mut orders = deposits.orders;
+    let mut received = HashMap::<Address, u64>::new();
+
+    for output_data in account_handle.outputs(None).await? {
+        if output_data.remainder {
+            continue;
+        }
+
+        *received.entry(output_data.address).or_default() += received_amount(&output_data.output);
+    }
+
+    for order in orders.values_mut() {
+        order.received = Address::try_from_bech32(&order.address)
+            .ok()
+            .and_then(|(_, address)| received.get(&address).copied())
+            .unwrap_or_default();
+    }
+
+    Ok((deposits.pool.len(), orders))
+}
+
+/// Reports the orders that received funds since the last report.
+pub async fn report_deposits(account_handle: &AccountHandle) -> Result<(), Error> {
+    let (account_index, coin_type) = {
+        let account = account_handle.read().await;
+        (*account.index(), *account.coin_type())
+    };
+    let mut all_deposits = read_deposits()?;
+
+    let Some(deposits) = all_deposits.get_mut(&account_index) else {
+        return Ok(());
+    };
+
+    let (_, orders) = received_by_order(account_handle).await?;
+    let mut changed = false;
+
+    for (order_id, order) in orders {
+        if let Some(known) = deposits.orders.get_mut(&order_id) {
+            if order.received != known.received {
+                if order.received > known.received {
+                    log::info!(
+                        "Order {order_id}: {} received on {}, {} more since the last sync.",
+                        format_amount(order.received, coin_type),
+                        order.address,
+                        format_amount(order.received - known.received, coin_type)
+                    );
+                }
+                known.received = order.received;
+                changed = true;
+            }
+        }
+    }
+
+    if changed {
+        write_deposits(&all_deposits)?;
+    }
+
+    Ok(())
+}
@@ -1,6 +1,8 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use std::process::ExitStatus;
+
 use fern_logger::Error as LoggerError;
 use iota_wallet::{
     error::Error as WalletError,
@@ -13,7 +15,17 @@ pub enum Error {
     #[error("block error: {0}")]
     Block(#[from] BlockError),
     #[error("client error: {0}")]
-    Client(#[from] ClientError),
+    Client(Box<ClientError>),
+    #[error(
+        "this command needs the keys, which the external signer holds\nhint: only `send` and `new` go through the \
+         external signer, run the command without `--secret-manager` to use the Stronghold"
+    )]
+    ExternalSignerUnsupported,
+    #[error(
+        "insufficient funds: {0}\nhint: amounts include the storage deposits of the new outputs and of the remainder, \
+         run `sync` to refresh the balance and `balance` to see how much is locked"
+    )]
+    InsufficientFunds(String),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
     #[error("logger error: {0}")]
@@ -22,8 +34,59 @@ pub enum Error {
     Miscellaneous(String),
     #[error("generate at least one address before using the faucet")]
     NoAddressForFaucet,
+    #[error(
+        "node unreachable: {0}\nhint: check that the node is running, or run `wallet set-node <url>` or `wallet \
+         add-node <url>` to use another node"
+    )]
+    NodeUnreachable(String),
+    #[error("plugin \"{plugin}\" exited with {status}")]
+    PluginFailed { plugin: String, status: ExitStatus },
     #[error("serde_json error: {0}")]
     SerdeJson(#[from] SerdeJsonError),
     #[error("wallet error: {0}")]
-    Wallet(#[from] WalletError),
+    Wallet(Box<WalletError>),
+    #[error(
+        "address {address} is not a \"{expected}\" address of the network of this wallet\nhint: ask the receiver for \
+         an address of this network, or run `wallet set-node <url>` if the wallet uses the wrong network"
+    )]
+    WrongNetwork { address: String, expected: String },
+    #[error(
+        "invalid stronghold password\nhint: retry with the right password, run `wallet stronghold list` to check which \
+         snapshot is in use, or `wallet restore <backup>` if the password is lost but a backup is at hand"
+    )]
+    WrongStrongholdPassword,
+}
+
+// Common client and wallet failures are turned into errors carrying a hint rather than wrapped as-is.
+impl From<ClientError> for Error {
+    fn from(error: ClientError) -> Self {
+        match error {
+            ClientError::NotEnoughBalance { found, required } => {
+                Self::InsufficientFunds(format!("{found} available, {required} required"))
+            }
+            ClientError::NoInputs => Self::InsufficientFunds("no unspent outputs available".to_string()),
+            ClientError::NoBalanceForNativeTokenRemainder => Self::InsufficientFunds(
+                "not enough left for the storage deposit of the remaining native tokens".to_string(),
+            ),
+            ClientError::HealthyNodePoolEmpty | ClientError::QuorumPoolSizeError { .. } => {
+                Self::NodeUnreachable(error.to_string())
+            }
+            ClientError::PlaceholderSecretManager => Self::ExternalSignerUnsupported,
+            ClientError::StrongholdInvalidPassword => Self::WrongStrongholdPassword,
+            error => Self::Client(Box::new(error)),
+        }
+    }
+}
+
+impl From<WalletError> for Error {
+    fn from(error: WalletError) -> Self {
+        match error {
+            WalletError::ClientError(error) => (*error).into(),
+            WalletError::IotaClientError(error) => error.into(),
+            WalletError::InsufficientFunds(available, required) => {
+                Self::InsufficientFunds(format!("{available} available, {required} required"))
+            }
+            error => Self::Wallet(Box::new(error)),
+        }
+    }
 }
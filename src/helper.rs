@@ -1,10 +1,98 @@
 // Copyright 2020-2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use dialoguer::{console::Term, theme::ColorfulTheme, Password, Select};
-use iota_wallet::account_manager::AccountManager;
+use std::{
+    env::var_os,
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    process::{Command, Stdio},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use crate::error::Error;
+use arboard::Clipboard;
+use argon2::{Config, Variant};
+use dialoguer::{
+    console::{Key, Term},
+    theme::ColorfulTheme,
+    FuzzySelect, Password,
+};
+use iota_wallet::{
+    account::AccountHandle,
+    account_manager::AccountManager,
+    iota_client::{
+        block::{
+            address::Address,
+            output::{feature::MetadataFeature, Output},
+        },
+        constants::{IOTA_COIN_TYPE, SHIMMER_COIN_TYPE},
+        crypto::{ciphers::chacha, utils::rand},
+        secret::SecretManager,
+    },
+    U256,
+};
+
+use crate::{config::config, error::Error, qr::decode_qr_image, timings::untimed};
+
+pub fn storage_path() -> String {
+    var_os("WALLET_DATABASE_PATH").map_or_else(
+        || {
+            config()
+                .storage_path
+                .clone()
+                .unwrap_or_else(|| "./stardust-cli-wallet-db".to_string())
+        },
+        |os_str| os_str.into_string().expect("invalid WALLET_DATABASE_PATH"),
+    )
+}
+
+// The balance history is kept next to the database, one JSON record per line.
+fn balance_history_path() -> String {
+    format!("{}-balance-history.jsonl", storage_path())
+}
+
+/// Appends the current balance of the account to its balance history.
+pub async fn record_balance(account_handle: &AccountHandle) -> Result<(), Error> {
+    let balance = account_handle.balance().await?;
+    let record = serde_json::json!({
+        "account": account_handle.read().await.index(),
+        "timestamp": SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs()),
+        "total": balance.base_coin.total,
+        "available": balance.base_coin.available,
+    });
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(balance_history_path())?;
+
+    writeln!(file, "{record}")?;
+
+    Ok(())
+}
+
+/// Returns the balance history of an account as `(timestamp, total, available)` records, oldest first.
+pub fn balance_history(account_index: u32) -> Result<Vec<(u64, u64, u64)>, Error> {
+    let file = match std::fs::File::open(balance_history_path()) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut history = Vec::new();
+
+    for line in BufReader::new(file).lines() {
+        let record = serde_json::from_str::<serde_json::Value>(&line?)?;
+
+        if record["account"].as_u64() == Some(account_index.into()) {
+            history.push((
+                record["timestamp"].as_u64().unwrap_or_default(),
+                record["total"].as_u64().unwrap_or_default(),
+                record["available"].as_u64().unwrap_or_default(),
+            ));
+        }
+    }
+
+    Ok(history)
+}
 
 pub fn get_password(prompt: &str, confirmation: bool) -> Result<String, Error> {
     let mut password = Password::new();
@@ -15,7 +103,34 @@ pub fn get_password(prompt: &str, confirmation: bool) -> Result<String, Error> {
         password.with_confirmation("Confirm password", "Password mismatch");
     }
 
-    Ok(password.interact()?)
+    Ok(untimed(|| password.interact())?)
+}
+
+/// Clears the Stronghold password from memory, for `lock`.
+pub async fn lock_stronghold(manager: &AccountManager) -> Result<(), Error> {
+    match &mut *manager.get_secret_manager().write().await {
+        SecretManager::Stronghold(stronghold) => {
+            stronghold.clear_key().await;
+            Ok(())
+        }
+        _ => Err(Error::Miscellaneous("only a Stronghold can be locked".to_string())),
+    }
+}
+
+/// Asks for the Stronghold password again if it has been cleared, by `lock` or after the `--password-timeout`, and
+/// restarts the timer clearing it otherwise, so that it only runs out after a period of inactivity.
+pub async fn unlock_stronghold(manager: &AccountManager) -> Result<(), Error> {
+    if let SecretManager::Stronghold(stronghold) = &mut *manager.get_secret_manager().write().await {
+        if stronghold.is_key_available().await {
+            stronghold.restart_key_clearing_task().await;
+        } else {
+            let password = get_password("Stronghold password", false)?;
+
+            stronghold.set_password(&password).await?;
+        }
+    }
+
+    Ok(())
 }
 
 pub async fn pick_account(manager: &AccountManager) -> Result<Option<u32>, Error> {
@@ -27,11 +142,19 @@ pub async fn pick_account(manager: &AccountManager) -> Result<Option<u32>, Error
         _ => {
             let mut items = Vec::new();
 
-            for account_handle in accounts {
-                items.push(account_handle.read().await.alias().clone());
+            for account_handle in &accounts {
+                let (alias, coin_type) = {
+                    let account = account_handle.read().await;
+                    (account.alias().clone(), *account.coin_type())
+                };
+                // From the last sync, the wallet isn't synced yet.
+                let balance = account_handle.balance().await?.base_coin.total;
+
+                items.push(format!("{alias} ({})", format_amount(balance, coin_type)));
             }
 
-            let index = Select::with_theme(&ColorfulTheme::default())
+            // Typing filters the accounts, fuzzily.
+            let index = FuzzySelect::with_theme(&ColorfulTheme::default())
                 .with_prompt("Select an account:")
                 .items(&items)
                 .default(0)
@@ -42,6 +165,77 @@ pub async fn pick_account(manager: &AccountManager) -> Result<Option<u32>, Error
     }
 }
 
+/// Logs `text`, or shows it through `$PAGER`, or the built-in pager if it isn't set, when it doesn't fit in the
+/// terminal.
+pub fn print_paged(text: &str) {
+    let term = Term::stdout();
+    let rows = match term.size_checked() {
+        Some((rows, _)) if term.is_term() && text.lines().count() >= rows as usize => rows as usize,
+        _ => {
+            log::info!("{text}");
+            return;
+        }
+    };
+
+    // Still kept in the archive log.
+    log::debug!("{text}");
+
+    match var_os("PAGER").and_then(|pager| pager.into_string().ok()) {
+        Some(pager) if !pager.trim().is_empty() => {
+            let mut args = pager.split_whitespace();
+            // PANIC: the pager isn't empty.
+            let program = args.next().unwrap();
+
+            match Command::new(program).args(args).stdin(Stdio::piped()).spawn() {
+                Ok(mut child) => {
+                    if let Some(mut stdin) = child.stdin.take() {
+                        // The pager may be quit before reading everything.
+                        let _ = writeln!(stdin, "{text}");
+                    }
+                    let _ = child.wait();
+                }
+                Err(e) => {
+                    log::warn!("Can't run pager \"{program}\": {e}");
+                    builtin_pager(&term, text, rows);
+                }
+            }
+        }
+        _ => builtin_pager(&term, text, rows),
+    }
+}
+
+// Prints a screen of `text` at a time, like `more`.
+fn builtin_pager(term: &Term, text: &str, rows: usize) {
+    let lines = text.lines().collect::<Vec<_>>();
+    // One row is kept for the prompt.
+    let page = rows.saturating_sub(1).max(1);
+    let (mut shown, mut step) = (0, page);
+
+    while shown < lines.len() {
+        let end = (shown + step).min(lines.len());
+
+        for line in &lines[shown..end] {
+            let _ = term.write_line(line);
+        }
+        shown = end;
+
+        if shown < lines.len() {
+            let _ = term.write_str(&format!(
+                "-- More ({shown}/{}) -- space: page, enter: line, q: quit",
+                lines.len()
+            ));
+            let key = term.read_key();
+            let _ = term.clear_line();
+
+            match key {
+                Ok(Key::Char(' ')) => step = page,
+                Ok(Key::Enter) => step = 1,
+                _ => break,
+            }
+        }
+    }
+}
+
 pub async fn bytes_from_hex_or_file(hex: Option<String>, file: Option<String>) -> Result<Option<Vec<u8>>, Error> {
     Ok(if let Some(hex) = hex {
         Some(prefix_hex::decode(&hex).map_err(|e| Error::Miscellaneous(e.to_string()))?)
@@ -51,3 +245,579 @@ pub async fn bytes_from_hex_or_file(hex: Option<String>, file: Option<String>) -
         None
     })
 }
+
+/// Builds IRC-30 foundry metadata, validated against the schema of the standard.
+pub fn irc30_metadata(
+    name: String,
+    symbol: String,
+    decimals: u32,
+    description: Option<String>,
+    logo_url: Option<String>,
+) -> Result<Vec<u8>, Error> {
+    if name.trim().is_empty() {
+        return Err(Error::Miscellaneous("the IRC-30 name can't be empty".to_string()));
+    }
+    if decimals > IRC30_MAX_DECIMALS {
+        return Err(Error::Miscellaneous(format!(
+            "the IRC-30 decimals can't exceed {IRC30_MAX_DECIMALS}"
+        )));
+    }
+    if symbol.is_empty() || symbol.chars().any(char::is_whitespace) {
+        return Err(Error::Miscellaneous(format!(
+            "the IRC-30 symbol must be non-empty and without whitespace: \"{symbol}\""
+        )));
+    }
+    if let Some(logo_url) = &logo_url {
+        let url = reqwest::Url::parse(logo_url)
+            .map_err(|e| Error::Miscellaneous(format!("invalid IRC-30 logo URL \"{logo_url}\": {e}")))?;
+
+        if !["http", "https"].contains(&url.scheme()) {
+            return Err(Error::Miscellaneous(format!(
+                "the IRC-30 logo URL must be an http or https one: \"{logo_url}\""
+            )));
+        }
+    }
+
+    let mut metadata = serde_json::json!({
+        "standard": "IRC30",
+        "name": name,
+        "symbol": symbol,
+        "decimals": decimals,
+    });
+
+    if let Some(description) = description {
+        metadata["description"] = description.into();
+    }
+    if let Some(logo_url) = logo_url {
+        metadata["logoUrl"] = logo_url.into();
+    }
+
+    log::info!("IRC-30 metadata: {metadata}");
+
+    Ok(metadata.to_string().into_bytes())
+}
+
+/// Reads the IRC-27 attributes of an NFT from a JSON file.
+pub async fn irc27_attributes(file: &str) -> Result<serde_json::Value, Error> {
+    serde_json::from_slice(&tokio::fs::read(file).await?)
+        .map_err(|e| Error::Miscellaneous(format!("invalid JSON in \"{file}\": {e}")))
+}
+
+/// Maximum IRC-30 decimals, leaving a single integer digit to the largest native token amount, `U256::MAX`.
+pub const IRC30_MAX_DECIMALS: u32 = 77;
+
+/// IRC-30 metadata of a native token, as needed to show its amounts.
+pub struct Irc30 {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+/// Decodes the IRC-30 metadata of a foundry output, from its immutable metadata feature.
+pub fn foundry_irc30(output: &Output) -> Option<Irc30> {
+    let Output::Foundry(foundry) = output else {
+        return None;
+    };
+
+    parse_irc30(foundry.immutable_features().metadata()?.data())
+}
+
+// Anyone can mint a token with any metadata, the decimals are bounded as they end up as a padding width.
+fn parse_irc30(data: &[u8]) -> Option<Irc30> {
+    let metadata = serde_json::from_slice::<serde_json::Value>(data).ok()?;
+
+    if metadata["standard"] != "IRC30" {
+        return None;
+    }
+
+    Some(Irc30 {
+        name: metadata["name"].as_str()?.to_string(),
+        symbol: metadata["symbol"].as_str()?.to_string(),
+        decimals: u32::try_from(metadata["decimals"].as_u64()?)
+            .ok()
+            .filter(|decimals| *decimals <= IRC30_MAX_DECIMALS)?,
+    })
+}
+
+/// Builds IRC-27 NFT metadata, validated against the schema of the standard and the maximum size of a metadata
+/// feature.
+pub fn irc27_metadata(
+    name: String,
+    uri: String,
+    mime_type: Option<String>,
+    collection: Option<String>,
+    attributes: Option<serde_json::Value>,
+    royalties: Vec<String>,
+) -> Result<Vec<u8>, Error> {
+    if name.trim().is_empty() {
+        return Err(Error::Miscellaneous("the IRC-27 name can't be empty".to_string()));
+    }
+    reqwest::Url::parse(&uri).map_err(|e| Error::Miscellaneous(format!("invalid IRC-27 URI \"{uri}\": {e}")))?;
+
+    let mime_type = match mime_type {
+        Some(mime_type) => mime_type,
+        None => mime_type_of(&uri).ok_or_else(|| {
+            Error::Miscellaneous(format!(
+                "can't guess the MIME type of \"{uri}\", provide it with `--mime-type`"
+            ))
+        })?,
+    };
+    if !mime_type
+        .split_once('/')
+        .is_some_and(|(kind, subtype)| !kind.is_empty() && !subtype.is_empty())
+    {
+        return Err(Error::Miscellaneous(format!(
+            "invalid IRC-27 MIME type: \"{mime_type}\""
+        )));
+    }
+
+    let mut metadata = serde_json::json!({
+        "standard": "IRC27",
+        "version": "v1.0",
+        "type": mime_type,
+        "uri": uri,
+        "name": name,
+    });
+
+    if let Some(collection) = collection {
+        metadata["collectionName"] = collection.into();
+    }
+    if let Some(attributes) = attributes {
+        let valid = attributes.as_array().is_some_and(|attributes| {
+            attributes.iter().all(|attribute| {
+                attribute.get("trait_type").is_some_and(serde_json::Value::is_string)
+                    && attribute.get("value").is_some_and(|value| !value.is_null())
+            })
+        });
+
+        if !valid {
+            return Err(Error::Miscellaneous(
+                "the IRC-27 attributes must be an array of objects with a `trait_type` string and a `value`"
+                    .to_string(),
+            ));
+        }
+
+        metadata["attributes"] = attributes;
+    }
+
+    if !royalties.is_empty() {
+        metadata["royalties"] = irc27_royalties(royalties)?;
+    }
+
+    let metadata = metadata.to_string().into_bytes();
+    let maximum_size = *MetadataFeature::LENGTH_RANGE.end();
+
+    if metadata.len() > maximum_size as usize {
+        return Err(Error::Miscellaneous(format!(
+            "the IRC-27 metadata is {} bytes, more than the {maximum_size} bytes of a metadata feature",
+            metadata.len()
+        )));
+    }
+
+    Ok(metadata)
+}
+
+// IRC-27 royalties from `<address>:<percent>` ones, as shares of at most 1 in total by bech32 encoded address.
+fn irc27_royalties(royalties: Vec<String>) -> Result<serde_json::Value, Error> {
+    let mut shares = serde_json::Map::new();
+    let mut total = 0.0;
+
+    for royalty in royalties {
+        let (address, percent) = royalty
+            .rsplit_once(':')
+            .ok_or_else(|| Error::Miscellaneous(format!("royalty \"{royalty}\" isn't `<address>:<percent>`")))?;
+
+        Address::try_from_bech32(address)
+            .map_err(|e| Error::Miscellaneous(format!("invalid royalty address \"{address}\": {e}")))?;
+
+        let percent = percent
+            .parse::<f64>()
+            .ok()
+            .filter(|percent| *percent > 0.0 && *percent <= 100.0)
+            .ok_or_else(|| Error::Miscellaneous(format!("royalty percent \"{percent}\" isn't a number in ]0, 100]")))?;
+
+        if shares.insert(address.to_string(), (percent / 100.0).into()).is_some() {
+            return Err(Error::Miscellaneous(format!(
+                "royalty address \"{address}\" given twice"
+            )));
+        }
+
+        total += percent;
+    }
+
+    if total > 100.0 {
+        return Err(Error::Miscellaneous(format!(
+            "royalties add up to {total}%, more than 100%"
+        )));
+    }
+
+    Ok(shares.into())
+}
+
+// MIME type of a file from the extension of its URI.
+fn mime_type_of(uri: &str) -> Option<String> {
+    let path = uri.split(['?', '#']).next()?;
+    let (_, extension) = path.rsplit_once('.')?;
+
+    Some(
+        match extension.to_lowercase().as_str() {
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            "webp" => "image/webp",
+            "svg" => "image/svg+xml",
+            "mp4" => "video/mp4",
+            "webm" => "video/webm",
+            "mp3" => "audio/mpeg",
+            "wav" => "audio/wav",
+            "glb" => "model/gltf-binary",
+            "gltf" => "model/gltf+json",
+            "json" => "application/json",
+            "pdf" => "application/pdf",
+            _ => return None,
+        }
+        .to_string(),
+    )
+}
+
+/// Returns `address`, or the bech32 encoded address on the system clipboard with `from_clipboard`.
+pub fn address_or_clipboard(address: Option<String>, from_clipboard: bool) -> Result<String, Error> {
+    if !from_clipboard {
+        return address.ok_or_else(|| Error::Miscellaneous("missing address".to_string()));
+    }
+
+    let text = Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(|e| Error::Miscellaneous(format!("can't read the clipboard: {e}")))?;
+    // Copied text often comes with surrounding whitespace or a newline.
+    let address = text.trim();
+
+    if Address::try_from_bech32(address).is_err() {
+        return Err(Error::Miscellaneous(format!(
+            "the clipboard doesn't hold a bech32 address: \"{}\"",
+            address.chars().take(100).collect::<String>()
+        )));
+    }
+
+    // Shown to be checked against the intended one.
+    log::info!("Address from the clipboard: {address}");
+
+    Ok(address.to_string())
+}
+
+/// Decodes the QR code of an image file holding a bech32 encoded address or a payment URI, checking that the amount
+/// requested by the URI, if any, is `amount`.
+pub fn address_from_qr_image(path: &str, amount: u64) -> Result<String, Error> {
+    let contents = decode_qr_image(path)?;
+    let content = match contents.as_slice() {
+        [content] => content,
+        [] => return Err(Error::Miscellaneous(format!("no QR code found in \"{path}\""))),
+        _ => {
+            return Err(Error::Miscellaneous(format!(
+                "{} QR codes found in \"{path}\", crop the image to keep only one",
+                contents.len()
+            )));
+        }
+    };
+    let (address, requested) = parse_payment_uri(content).ok_or_else(|| {
+        Error::Miscellaneous(format!(
+            "the QR code doesn't hold an address or a payment URI: \"{}\"",
+            content.chars().take(100).collect::<String>()
+        ))
+    })?;
+
+    if let Some(requested) = requested.filter(|requested| *requested != amount) {
+        return Err(Error::Miscellaneous(format!(
+            "the QR code requests an amount of {requested}, not {amount}"
+        )));
+    }
+
+    // Shown to be checked against the intended one.
+    log::info!("Address from the QR code: {address}");
+
+    Ok(address)
+}
+
+// Extracts the address, and the amount if any, from a bech32 encoded address or a payment URI holding one, e.g.
+// `iota://wallet/send/smr1...?amount=1000000` or `smr:smr1...?amount=1000000`.
+fn parse_payment_uri(text: &str) -> Option<(String, Option<u64>)> {
+    let text = text.trim();
+    let (path, query) = text.split_once('?').unwrap_or((text, ""));
+    let address = path
+        .split([':', '/'])
+        .find(|part| Address::try_from_bech32(part).is_ok())?;
+    let amount = query
+        .split('&')
+        .find_map(|parameter| parameter.strip_prefix("amount="))
+        .map(str::parse)
+        .transpose()
+        .ok()?;
+
+    Some((address.to_string(), amount))
+}
+
+/// Checks that a bech32 encoded address belongs to the network of the account, the library doesn't.
+pub async fn check_network(account_handle: &AccountHandle, address: &str) -> Result<(), Error> {
+    let (bech32_hrp, _) = Address::try_from_bech32(address)?;
+    let account = account_handle.read().await;
+
+    // Without addresses, the network of the account is unknown.
+    if let Some(expected) = account.public_addresses().first().map(|a| a.address().bech32_hrp()) {
+        if bech32_hrp != expected {
+            return Err(Error::WrongNetwork {
+                address: address.to_string(),
+                expected: expected.to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Encrypts `plaintext` with a key derived from `passphrase` using Argon2id and returns a JSON document holding
+/// everything but the passphrase needed to decrypt it.
+pub fn encrypt_with_passphrase(passphrase: &str, plaintext: &[u8]) -> Result<String, Error> {
+    let mut salt = [0u8; 16];
+    rand::fill(&mut salt).map_err(|e| Error::Miscellaneous(e.to_string()))?;
+
+    let config = Config {
+        variant: Variant::Argon2id,
+        mem_cost: 65536,
+        time_cost: 3,
+        ..Default::default()
+    };
+    let key =
+        argon2::hash_raw(passphrase.as_bytes(), &salt, &config).map_err(|e| Error::Miscellaneous(e.to_string()))?;
+    let ciphertext = chacha::aead_encrypt(&key, plaintext).map_err(|e| Error::Miscellaneous(e.to_string()))?;
+
+    Ok(serde_json::to_string_pretty(&serde_json::json!({
+        "version": 1,
+        "kdf": {
+            "algorithm": "argon2id",
+            "memoryCost": config.mem_cost,
+            "timeCost": config.time_cost,
+            "lanes": config.lanes,
+            "salt": prefix_hex::encode(salt),
+        },
+        "cipher": "xchacha20-poly1305",
+        // `nonce || tag || ciphertext`
+        "ciphertext": prefix_hex::encode(ciphertext),
+    }))?)
+}
+
+/// Decrypts a JSON document written by `encrypt_with_passphrase`.
+pub fn decrypt_with_passphrase(passphrase: &str, document: &str) -> Result<Vec<u8>, Error> {
+    let invalid = || Error::Miscellaneous("not a document encrypted by cli-wallet".to_string());
+    let document = serde_json::from_str::<serde_json::Value>(document).map_err(|_| invalid())?;
+
+    if document["version"] != 1
+        || document["kdf"]["algorithm"] != "argon2id"
+        || document["cipher"] != "xchacha20-poly1305"
+    {
+        return Err(invalid());
+    }
+
+    let kdf_param = |name: &str| {
+        document["kdf"][name]
+            .as_u64()
+            .and_then(|value| u32::try_from(value).ok())
+            .ok_or_else(invalid)
+    };
+    let hex_field = |value: &serde_json::Value| {
+        value
+            .as_str()
+            .and_then(|value| prefix_hex::decode::<Vec<u8>>(value).ok())
+            .ok_or_else(invalid)
+    };
+
+    let config = Config {
+        variant: Variant::Argon2id,
+        mem_cost: kdf_param("memoryCost")?,
+        time_cost: kdf_param("timeCost")?,
+        lanes: kdf_param("lanes")?,
+        ..Default::default()
+    };
+    let salt = hex_field(&document["kdf"]["salt"])?;
+    let ciphertext = hex_field(&document["ciphertext"])?;
+
+    // At least `nonce || tag`, the library would panic otherwise.
+    if ciphertext.len() < 24 + 16 {
+        return Err(invalid());
+    }
+
+    let key =
+        argon2::hash_raw(passphrase.as_bytes(), &salt, &config).map_err(|e| Error::Miscellaneous(e.to_string()))?;
+
+    chacha::aead_decrypt(&key, &ciphertext)
+        .map_err(|_| Error::Miscellaneous("wrong passphrase or altered document".to_string()))
+}
+
+/// Writes `contents` to a new file only readable and writable by its owner.
+pub fn write_private_file(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<(), Error> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    options.open(path)?.write_all(contents.as_ref())?;
+
+    Ok(())
+}
+
+/// Parses a `YYYY-MM-DD` date into the number of seconds since the Unix epoch at midnight UTC.
+pub fn parse_date(date: &str) -> Result<u64, Error> {
+    let invalid = || Error::Miscellaneous(format!("invalid date \"{date}\", expected YYYY-MM-DD"));
+    let mut parts = date
+        .splitn(3, '-')
+        .map(|part| part.parse::<u64>().map_err(|_| invalid()));
+    let (year, month, day) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(year), Some(month), Some(day)) => (year?, month?, day?),
+        _ => return Err(invalid()),
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return Err(invalid()),
+    };
+
+    if year < 1970 || day == 0 || day > days_in_month {
+        return Err(invalid());
+    }
+
+    // Days since 0000-03-01, years starting in March so that the leap day comes last.
+    let days_from_civil = |year: u64, month: u64, day: u64| {
+        let year = if month <= 2 { year - 1 } else { year };
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+
+        year * 365 + year / 4 - year / 100 + year / 400 + day_of_year
+    };
+
+    Ok((days_from_civil(year, month, day) - days_from_civil(1970, 1, 1)) * 24 * 60 * 60)
+}
+
+/// Parses a duration of seconds, minutes, hours, days or weeks, e.g. `30d`, into a number of seconds.
+pub fn parse_duration(duration: &str) -> Result<u64, Error> {
+    let invalid = || {
+        Error::Miscellaneous(format!(
+            "invalid duration \"{duration}\", expected e.g. 90s, 30m, 12h, 30d or 2w"
+        ))
+    };
+    let split = duration.len().checked_sub(1).ok_or_else(invalid)?;
+    let (value, unit) = duration.split_at(split);
+    let unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+
+    value
+        .parse::<u64>()
+        .ok()
+        .filter(|value| *value > 0)
+        .and_then(|value| value.checked_mul(unit))
+        .ok_or_else(invalid)
+}
+
+/// Formats a number of seconds since the Unix epoch as a `YYYY-MM-DD HH:MM:SS UTC` date.
+pub fn format_timestamp(timestamp: u64) -> String {
+    // Inverse of the computation in `parse_date`, shifted to days since 0000-03-01.
+    let days = timestamp / (24 * 60 * 60) + 719_468;
+    let seconds = timestamp % (24 * 60 * 60);
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = (shifted_month + 2) % 12 + 1;
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+/// Formats an amount of the smallest unit of the base coin in the base coin unit, e.g. `1.5 SMR` for 1500000.
+pub fn format_amount(amount: u64, coin_type: u32) -> String {
+    let unit = match coin_type {
+        IOTA_COIN_TYPE => "IOTA",
+        SHIMMER_COIN_TYPE => "SMR",
+        // Unknown decimals, left as is.
+        _ => return amount.to_string(),
+    };
+    // Both IOTA and Shimmer have 6 decimals.
+    let fraction = format!("{:06}", amount % 1_000_000);
+    let fraction = fraction.trim_end_matches('0');
+
+    if fraction.is_empty() {
+        format!("{} {unit}", amount / 1_000_000)
+    } else {
+        format!("{}.{fraction} {unit}", amount / 1_000_000)
+    }
+}
+
+/// Formats an amount of a native token with its IRC-30 decimals and symbol, e.g. `1.5 MTK` for 1500000 with 6
+/// decimals.
+pub fn format_token_amount(amount: U256, irc30: &Irc30) -> String {
+    let digits = format!("{:0>width$}", amount.to_string(), width = irc30.decimals as usize + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - irc30.decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+
+    if fraction.is_empty() {
+        format!("{integer} {}", irc30.symbol)
+    } else {
+        format!("{integer}.{fraction} {}", irc30.symbol)
+    }
+}
+
+/// Amount actually received with an output, without the storage deposit to return to its sender.
+pub fn received_amount(output: &Output) -> u64 {
+    let returned = output
+        .unlock_conditions()
+        .and_then(|unlock_conditions| unlock_conditions.storage_deposit_return())
+        .map_or(0, |storage_deposit_return| storage_deposit_return.amount());
+
+    output.amount().saturating_sub(returned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_irc30_bounds_decimals() {
+        let metadata = |decimals: u64| {
+            format!(r#"{{"standard":"IRC30","name":"My Token","symbol":"MTK","decimals":{decimals}}}"#).into_bytes()
+        };
+
+        let irc30 = parse_irc30(&metadata(6)).unwrap();
+        assert_eq!(format_token_amount(U256::from(1_500_000), &irc30), "1.5 MTK");
+
+        let irc30 = parse_irc30(&metadata(u64::from(IRC30_MAX_DECIMALS))).unwrap();
+        assert_eq!(
+            format_token_amount(U256::MAX, &irc30),
+            format!("1.{} MTK", &U256::MAX.to_string()[1..])
+        );
+
+        assert!(parse_irc30(&metadata(u64::from(IRC30_MAX_DECIMALS) + 1)).is_none());
+        assert!(parse_irc30(&metadata(u64::from(u32::MAX))).is_none());
+        assert!(parse_irc30(&metadata(u64::MAX)).is_none());
+    }
+
+    #[test]
+    fn irc30_metadata_rejects_oversized_decimals() {
+        let metadata = |decimals| irc30_metadata("My Token".to_string(), "MTK".to_string(), decimals, None, None);
+
+        assert!(metadata(IRC30_MAX_DECIMALS).is_ok());
+        assert!(metadata(IRC30_MAX_DECIMALS + 1).is_err());
+    }
+}
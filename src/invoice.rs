@@ -0,0 +1,264 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use iota_wallet::{account::AccountHandle, iota_client::block::address::Address};
+
+use crate::{
+    error::Error,
+    helper::{parse_duration, received_amount, storage_path},
+    timings::{timed, Phase},
+};
+
+/// State of an invoice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvoiceStatus {
+    Pending,
+    Paid,
+    Expired,
+}
+
+impl InvoiceStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Paid => "paid",
+            Self::Expired => "expired",
+        }
+    }
+
+    fn from_str(status: &str) -> Self {
+        match status {
+            "paid" => Self::Paid,
+            "expired" => Self::Expired,
+            _ => Self::Pending,
+        }
+    }
+}
+
+/// A payment expected on an address of the account.
+#[derive(Debug, Clone)]
+pub struct Invoice {
+    pub id: u32,
+    pub amount: u64,
+    pub address: String,
+    pub created_at: u64,
+    pub expires_at: Option<u64>,
+    pub status: InvoiceStatus,
+    /// Amount received for the invoice, paid once it reaches the invoiced amount.
+    pub received: u64,
+    /// Outputs that paid the invoice.
+    pub paid_by: Vec<String>,
+    pub paid_at: Option<u64>,
+}
+
+impl Invoice {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.id,
+            "amount": self.amount,
+            "address": self.address,
+            "createdAt": self.created_at,
+            "expiresAt": self.expires_at,
+            "status": self.status.as_str(),
+            "received": self.received,
+            "paidBy": self.paid_by,
+            "paidAt": self.paid_at,
+        })
+    }
+
+    fn from_json(invoice: &serde_json::Value) -> Option<Self> {
+        Some(Self {
+            id: u32::try_from(invoice["id"].as_u64()?).ok()?,
+            amount: invoice["amount"].as_u64()?,
+            address: invoice["address"].as_str()?.to_string(),
+            created_at: invoice["createdAt"].as_u64()?,
+            expires_at: invoice["expiresAt"].as_u64(),
+            status: InvoiceStatus::from_str(invoice["status"].as_str()?),
+            received: invoice["received"].as_u64().unwrap_or_default(),
+            paid_by: serde_json::from_value(invoice["paidBy"].clone()).unwrap_or_default(),
+            paid_at: invoice["paidAt"].as_u64(),
+        })
+    }
+}
+
+// The invoices are kept next to the database, by account index.
+fn invoices_path() -> String {
+    format!("{}-invoices.json", storage_path())
+}
+
+fn read_invoices() -> Result<BTreeMap<u32, Vec<Invoice>>, Error> {
+    let invoices = match std::fs::read_to_string(invoices_path()) {
+        Ok(invoices) => serde_json::from_str::<BTreeMap<u32, Vec<serde_json::Value>>>(&invoices)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(invoices
+        .into_iter()
+        .map(|(account_index, invoices)| (account_index, invoices.iter().filter_map(Invoice::from_json).collect()))
+        .collect())
+}
+
+fn write_invoices(invoices: &BTreeMap<u32, Vec<Invoice>>) -> Result<(), Error> {
+    let invoices = invoices
+        .iter()
+        .map(|(account_index, invoices)| (account_index, invoices.iter().map(Invoice::to_json).collect::<Vec<_>>()))
+        .collect::<BTreeMap<_, _>>();
+
+    std::fs::write(invoices_path(), serde_json::to_string_pretty(&invoices)?)?;
+
+    Ok(())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Creates an invoice of an amount, to be paid on the given address of the account or on a new one, optionally expiring
+/// after a duration.
+pub async fn create_invoice(
+    account_handle: &AccountHandle,
+    amount: u64,
+    address: Option<String>,
+    expires: Option<String>,
+) -> Result<Invoice, Error> {
+    if amount == 0 {
+        return Err(Error::Miscellaneous("the invoiced amount can't be zero".to_string()));
+    }
+
+    let expires_in = expires.as_deref().map(parse_duration).transpose()?;
+    let address = match address {
+        Some(address) => {
+            let (_, parsed) = Address::try_from_bech32(&address)?;
+            let account = account_handle.read().await;
+
+            if !account
+                .public_addresses()
+                .iter()
+                .chain(account.internal_addresses())
+                .any(|known| known.address().as_ref() == &parsed)
+            {
+                return Err(Error::Miscellaneous(format!(
+                    "\"{address}\" isn't an address of the account"
+                )));
+            }
+
+            address
+        }
+        None => {
+            // A new address per invoice, so that its payments can't be mistaken for others.
+            let addresses = timed(Phase::SecretManager, account_handle.generate_addresses(1, None)).await?;
+
+            // Without the keys, with an external signer, no address is generated.
+            addresses
+                .first()
+                .ok_or(Error::ExternalSignerUnsupported)?
+                .address()
+                .to_bech32()
+        }
+    };
+
+    let account_index = *account_handle.read().await.index();
+    let mut all_invoices = read_invoices()?;
+    let invoices = all_invoices.entry(account_index).or_default();
+    let created_at = now();
+    let invoice = Invoice {
+        id: invoices.iter().map(|invoice| invoice.id).max().unwrap_or_default() + 1,
+        amount,
+        address,
+        created_at,
+        expires_at: expires_in.map(|expires_in| created_at + expires_in),
+        status: InvoiceStatus::Pending,
+        received: 0,
+        paid_by: Vec::new(),
+        paid_at: None,
+    };
+
+    invoices.push(invoice.clone());
+    write_invoices(&all_invoices)?;
+
+    Ok(invoice)
+}
+
+/// Matches the pending invoices of the account against its outputs, oldest invoice first, and marks them paid or
+/// expired. Returns all the invoices and the ids of the ones whose status changed.
+pub async fn update_invoices(account_handle: &AccountHandle) -> Result<(Vec<Invoice>, Vec<u32>), Error> {
+    let account_index = *account_handle.read().await.index();
+    let mut all_invoices = read_invoices()?;
+
+    let Some(invoices) = all_invoices.get_mut(&account_index) else {
+        return Ok((Vec::new(), Vec::new()));
+    };
+
+    let outputs = account_handle.outputs(None).await?;
+    let now = now();
+    // An output only pays one invoice.
+    let mut used = invoices
+        .iter()
+        .flat_map(|invoice| invoice.paid_by.iter().cloned())
+        .collect::<HashSet<_>>();
+    let mut changed = Vec::new();
+    let mut modified = false;
+
+    for invoice in invoices
+        .iter_mut()
+        .filter(|invoice| invoice.status == InvoiceStatus::Pending)
+    {
+        let address = Address::try_from_bech32(&invoice.address)?.1;
+        let mut payments = outputs
+            .iter()
+            .filter(|output_data| {
+                let booked = u64::from(output_data.metadata.milestone_timestamp_booked);
+
+                !output_data.remainder
+                    && output_data.address == address
+                    && booked >= invoice.created_at
+                    && invoice.expires_at.is_none_or(|expires_at| booked <= expires_at)
+                    && !used.contains(&output_data.output_id.to_string())
+            })
+            .collect::<Vec<_>>();
+
+        payments.sort_by_key(|output_data| output_data.metadata.milestone_timestamp_booked);
+
+        let received = payments
+            .iter()
+            .map(|output_data| received_amount(&output_data.output))
+            .sum::<u64>();
+
+        if received != invoice.received {
+            invoice.received = received;
+            modified = true;
+        }
+
+        if received >= invoice.amount {
+            invoice.status = InvoiceStatus::Paid;
+            invoice.paid_by = payments
+                .iter()
+                .map(|output_data| output_data.output_id.to_string())
+                .collect();
+            invoice.paid_at = payments
+                .last()
+                .map(|output_data| u64::from(output_data.metadata.milestone_timestamp_booked));
+            used.extend(invoice.paid_by.iter().cloned());
+            changed.push(invoice.id);
+        } else if invoice.expires_at.is_some_and(|expires_at| expires_at < now) {
+            invoice.status = InvoiceStatus::Expired;
+            changed.push(invoice.id);
+        }
+    }
+
+    let invoices = invoices.clone();
+
+    if modified || !changed.is_empty() {
+        write_invoices(&all_invoices)?;
+    }
+
+    Ok((invoices, changed))
+}
@@ -0,0 +1,185 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use iota_wallet::{iota_client::block::output::NativeToken, U256};
+
+// Requests to ISC smart contract chains are held as metadata by the outputs sent to and from them. Hnames are
+// little-endian, sizes and amounts are LEB128 encoded, as serialized by Wasp.
+//
+// Hnames of the `accounts` core contract of ISC chains and of its `transferAllowanceTo` entry point.
+const ACCOUNTS_HNAME: u32 = 0x3c4b5e02;
+const TRANSFER_ALLOWANCE_TO_HNAME: u32 = 0x23f4e3a1;
+// Agent ID kind of EVM addresses.
+const ETHEREUM_ADDRESS_AGENT_ID_KIND: u8 = 3;
+// Allowance flags of the assets it holds.
+const ALLOWANCE_BASE_TOKENS: u8 = 0x80;
+const ALLOWANCE_NATIVE_TOKENS: u8 = 0x40;
+const ALLOWANCE_NFTS: u8 = 0x20;
+// Size of token ids and NFT ids.
+const TOKEN_ID_LENGTH: usize = 38;
+const NFT_ID_LENGTH: usize = 32;
+
+/// Gas budget of L2 deposits.
+pub const GAS_BUDGET: u64 = 10_000_000;
+/// Base tokens added to a deposit output to pay for its gas budget, at the default fee policy of 100 gas per base
+/// token.
+pub const GAS_FEE: u64 = GAS_BUDGET / 100;
+
+/// Metadata of a request calling `accounts::transferAllowanceTo` with the EVM address as agent, moving the allowance
+/// from the L2 account of the sender address to the one of the EVM address.
+pub fn deposit_metadata(evm_address: [u8; 20], base_tokens: u64, native_token: Option<&NativeToken>) -> Vec<u8> {
+    let mut metadata = Vec::new();
+
+    // Sender contract, none.
+    metadata.extend(0u32.to_le_bytes());
+    metadata.extend(ACCOUNTS_HNAME.to_le_bytes());
+    metadata.extend(TRANSFER_ALLOWANCE_TO_HNAME.to_le_bytes());
+    // The gas budget is written plus one, zero meaning none.
+    write_compact(&mut metadata, GAS_BUDGET + 1);
+
+    // Parameters, a dictionary with the agent ID of the EVM address as `a`.
+    write_compact(&mut metadata, 1);
+    write_compact(&mut metadata, 1);
+    metadata.push(b'a');
+    write_compact(&mut metadata, 1 + evm_address.len() as u64);
+    metadata.push(ETHEREUM_ADDRESS_AGENT_ID_KIND);
+    metadata.extend(evm_address);
+
+    let mut flags = 0;
+
+    if base_tokens > 0 {
+        flags |= ALLOWANCE_BASE_TOKENS;
+    }
+    if native_token.is_some() {
+        flags |= ALLOWANCE_NATIVE_TOKENS;
+    }
+
+    metadata.push(flags);
+
+    if base_tokens > 0 {
+        write_compact(&mut metadata, base_tokens);
+    }
+    if let Some(native_token) = native_token {
+        let mut amount = [0u8; 32];
+        native_token.amount().to_big_endian(&mut amount);
+        let amount = &amount[amount.iter().take_while(|byte| **byte == 0).count()..];
+
+        write_compact(&mut metadata, 1);
+        metadata.extend(native_token.token_id().as_ref());
+        write_compact(&mut metadata, amount.len() as u64);
+        metadata.extend(amount);
+    }
+
+    metadata
+}
+
+/// Describes the request held as metadata by an output, `None` if it isn't one.
+pub fn describe_request(metadata: &[u8]) -> Option<String> {
+    let mut reader = Reader(metadata);
+    let sender_contract = reader.u32()?;
+    let target_contract = reader.u32()?;
+    let entry_point = reader.u32()?;
+    let gas_budget = reader.compact()?.checked_sub(1);
+    let mut parameters = Vec::new();
+
+    for _ in 0..reader.compact()? {
+        let key = String::from_utf8_lossy(reader.sized()?).into_owned();
+        let value = reader.sized()?;
+
+        parameters.push(format!("{key}: {}", prefix_hex::encode(value)));
+    }
+
+    let flags = reader.bytes(1)?[0];
+    let mut allowance = Vec::new();
+
+    if flags & ALLOWANCE_BASE_TOKENS != 0 {
+        allowance.push(format!("{} base tokens", reader.compact()?));
+    }
+    if flags & ALLOWANCE_NATIVE_TOKENS != 0 {
+        for _ in 0..reader.compact()? {
+            let token_id = prefix_hex::encode(reader.bytes(TOKEN_ID_LENGTH)?);
+            let amount = U256::from_big_endian(reader.sized()?);
+
+            allowance.push(format!("{amount} of {token_id}"));
+        }
+    }
+    if flags & ALLOWANCE_NFTS != 0 {
+        for _ in 0..reader.compact()? {
+            allowance.push(format!("NFT {}", prefix_hex::encode(reader.bytes(NFT_ID_LENGTH)?)));
+        }
+    }
+
+    if !reader.0.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "sender contract {}, target contract {}, entry point {}, gas budget {}, parameters [{}], allowance [{}]",
+        hname(sender_contract),
+        hname(target_contract),
+        hname(entry_point),
+        gas_budget.map_or_else(|| "none".to_string(), |gas_budget| gas_budget.to_string()),
+        parameters.join(", "),
+        allowance.join(", ")
+    ))
+}
+
+fn hname(hname: u32) -> String {
+    match hname {
+        ACCOUNTS_HNAME => "accounts".to_string(),
+        TRANSFER_ALLOWANCE_TO_HNAME => "transferAllowanceTo".to_string(),
+        _ => format!("{hname:#010x}"),
+    }
+}
+
+fn write_compact(metadata: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            metadata.push(byte);
+            break;
+        }
+        metadata.push(byte | 0x80);
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, length: usize) -> Option<&'a [u8]> {
+        if self.0.len() < length {
+            return None;
+        }
+
+        let (bytes, rest) = self.0.split_at(length);
+        self.0 = rest;
+
+        Some(bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.bytes(4)?.try_into().ok()?))
+    }
+
+    fn compact(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.bytes(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+
+        None
+    }
+
+    fn sized(&mut self) -> Option<&'a [u8]> {
+        let length = self.compact()?;
+
+        self.bytes(usize::try_from(length).ok()?)
+    }
+}
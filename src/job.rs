@@ -0,0 +1,183 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::BTreeMap,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use crate::{error::Error, helper::storage_path};
+
+// Number of commands running, that an interruption lets finish their current step.
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// A batch operation of an account, recorded as it progresses so that it can be resumed if interrupted.
+#[derive(Debug, Clone)]
+pub enum Job {
+    /// Vesting installments still to be sent to an address, as amounts and unlock timestamps, after the transaction
+    /// of the block waiting to be confirmed.
+    Vest {
+        address: String,
+        installments: Vec<(u64, u64)>,
+        pending_block_id: Option<String>,
+    },
+    /// Claiming the claimable outputs.
+    Claim,
+}
+
+impl Job {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Vest { .. } => "vest",
+            Self::Claim => "claim",
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::Vest {
+                address,
+                installments,
+                pending_block_id,
+            } => serde_json::json!({
+                "kind": "vest",
+                "address": address,
+                "installments": installments
+                    .iter()
+                    .map(|(amount, unlocks_at)| serde_json::json!({ "amount": amount, "unlocksAt": unlocks_at }))
+                    .collect::<Vec<_>>(),
+                "pendingBlockId": pending_block_id,
+            }),
+            Self::Claim => serde_json::json!({ "kind": "claim" }),
+        }
+    }
+
+    fn from_json(job: &serde_json::Value) -> Option<Self> {
+        match job["kind"].as_str()? {
+            "vest" => Some(Self::Vest {
+                address: job["address"].as_str()?.to_string(),
+                installments: job["installments"]
+                    .as_array()?
+                    .iter()
+                    .map(|installment| Some((installment["amount"].as_u64()?, installment["unlocksAt"].as_u64()?)))
+                    .collect::<Option<_>>()?,
+                pending_block_id: job["pendingBlockId"].as_str().map(str::to_string),
+            }),
+            "claim" => Some(Self::Claim),
+            _ => None,
+        }
+    }
+}
+
+// The jobs are kept next to the database, by account index.
+fn jobs_path() -> String {
+    format!("{}-jobs.json", storage_path())
+}
+
+fn read_jobs() -> Result<BTreeMap<u32, serde_json::Value>, Error> {
+    match std::fs::read_to_string(jobs_path()) {
+        Ok(jobs) => Ok(serde_json::from_str(&jobs)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The unfinished job of an account, if any.
+pub fn job(account_index: u32) -> Result<Option<Job>, Error> {
+    Ok(read_jobs()?.get(&account_index).and_then(Job::from_json))
+}
+
+/// Records the progress of the job of an account, or that it's done.
+pub fn set_job(account_index: u32, job: Option<&Job>) -> Result<(), Error> {
+    let mut jobs = read_jobs()?;
+
+    match job {
+        Some(job) => {
+            jobs.insert(account_index, job.to_json());
+        }
+        None => {
+            jobs.remove(&account_index);
+        }
+    }
+
+    std::fs::write(jobs_path(), serde_json::to_string_pretty(&jobs)?)?;
+
+    Ok(())
+}
+
+/// Fails if the account has an unfinished job, which a new one would overwrite.
+pub fn check_no_job(account_index: u32) -> Result<(), Error> {
+    match job(account_index)? {
+        Some(job) => Err(Error::Miscellaneous(format!(
+            "an interrupted {} is unfinished, continue it with `resume` first",
+            job.name()
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Marks a command as running until dropped, an interruption then letting it finish its current step.
+pub struct Running;
+
+impl Running {
+    pub fn start() -> Self {
+        // A new command isn't interrupted by the interruption of a previous one.
+        if RUNNING.fetch_add(1, Ordering::SeqCst) == 0 {
+            INTERRUPTED.store(false, Ordering::SeqCst);
+        }
+
+        Self
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        RUNNING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Whether the running command was interrupted and should stop after its current step.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Waits for the running command to be interrupted, for the commands running until then.
+pub async fn interruption() -> Result<(), Error> {
+    wait_for_signal().await?;
+
+    Ok(())
+}
+
+/// Handles Ctrl-C and SIGTERM: without a running command, or on a second interruption, the wallet exits right away,
+/// otherwise the running command is asked to stop after its current step, e.g. a transaction being sent.
+pub fn listen_for_shutdown() {
+    tokio::spawn(async {
+        loop {
+            if wait_for_signal().await.is_err() {
+                return;
+            }
+
+            if RUNNING.load(Ordering::SeqCst) == 0 || INTERRUPTED.swap(true, Ordering::SeqCst) {
+                std::process::exit(130);
+            }
+
+            log::warn!("Interrupted, stopping after the current step, interrupt again to stop right away.");
+        }
+    });
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> std::io::Result<()> {
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result,
+        _ = terminate.recv() => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> std::io::Result<()> {
+    tokio::signal::ctrl_c().await
+}
@@ -4,21 +4,52 @@
 mod account;
 mod account_manager;
 mod command;
+mod config;
+mod deposit;
 mod error;
 mod helper;
+mod invoice;
+mod isc;
+mod job;
+mod participation;
+mod plugin;
+mod price;
+mod prompt;
+mod qr;
+mod report;
+mod script;
+mod signer;
+mod timings;
+mod tui;
 
-use clap::Parser;
+use std::time::Duration;
+
+use clap::{CommandFactory, FromArgMatches};
 use fern_logger::{LoggerConfigBuilder, LoggerOutputConfigBuilder};
+use iota_wallet::events::types::{WalletEvent, WalletEventType};
 use log::LevelFilter;
 
 use self::{
-    account_manager::new_account_manager, command::account_manager::AccountManagerCli, error::Error,
-    helper::pick_account,
+    account_manager::new_account_manager,
+    command::{
+        account::{print_error, OutputFormat},
+        account_manager::{plugin_context, AccountManagerCli, AccountManagerCommand},
+    },
+    config::load_config,
+    error::Error,
+    helper::{parse_duration, pick_account, storage_path},
+    job::{listen_for_shutdown, Running},
+    plugin::{find_plugin, node_urls, run_plugin},
+    signer::ExternalSigner,
+    timings::{enable_timings, report_timings, start_timings, transaction_progress},
 };
 
 fn logger_init(cli: &AccountManagerCli) -> Result<(), Error> {
     let stdout_level_filter = if let Some(log_level) = cli.log_level {
         log_level
+    } else if cli.output == OutputFormat::Json {
+        // Keeps stdout parseable, the logs are still in the archive.
+        LevelFilter::Off
     } else {
         LevelFilter::Info
     };
@@ -43,14 +74,77 @@ fn logger_init(cli: &AccountManagerCli) -> Result<(), Error> {
 }
 
 async fn run(cli: AccountManagerCli) -> Result<(), Error> {
-    let (account_manager, account) = new_account_manager(cli.clone()).await?;
+    let signer = ExternalSigner::from_secret_manager(cli.secret_manager.as_deref())?;
+    // Named after the subcommand, opening the wallet otherwise.
+    let command = std::env::args()
+        .skip(1)
+        .find(|arg| AccountManagerCli::command().find_subcommand(arg).is_some())
+        .unwrap_or_else(|| "open".to_string());
+    let start = start_timings();
+    let (account_manager, account) = new_account_manager(cli.clone(), signer.as_ref()).await?;
+
+    report_timings(&command, start);
 
     if let Some(account_manager) = account_manager {
+        if cli.timings {
+            account_manager
+                .listen(vec![WalletEventType::TransactionProgress], |event| {
+                    if let WalletEvent::TransactionProgress(progress) = &event.event {
+                        transaction_progress(progress);
+                    }
+                })
+                .await;
+        }
+
+        // A single account command, without the prompt.
+        if let Some(AccountManagerCommand::Account(parameters)) = cli.command {
+            let account_handle = account_manager.get_account(parameters.account).await?;
+            let command = parameters.command.name();
+            let start = start_timings();
+            let _running = Running::start();
+
+            account::account_command(
+                &account_manager,
+                &account_handle,
+                parameters.command,
+                signer.as_ref(),
+                cli.output,
+            )
+            .await?;
+            report_timings(&command, start);
+
+            return Ok(());
+        }
+
+        let nodes = node_urls(&account_manager.get_client_options().await);
+
+        if let Some(timeout) = &cli.password_timeout {
+            account_manager
+                .set_stronghold_password_clear_interval(Some(Duration::from_secs(parse_duration(timeout)?)))
+                .await?;
+        }
+
         match cli.account.or(account) {
-            Some(account) => account::account_prompt(account_manager.get_account(account).await?).await?,
+            Some(account) => {
+                account::account_prompt(
+                    &account_manager,
+                    account_manager.get_account(account).await?,
+                    &nodes,
+                    signer.as_ref(),
+                    cli.output,
+                )
+                .await?
+            }
             None => {
                 if let Some(account) = pick_account(&account_manager).await? {
-                    account::account_prompt(account_manager.get_account(account).await?).await?;
+                    account::account_prompt(
+                        &account_manager,
+                        account_manager.get_account(account).await?,
+                        &nodes,
+                        signer.as_ref(),
+                        cli.output,
+                    )
+                    .await?;
                 }
             }
         }
@@ -61,7 +155,38 @@ async fn run(cli: AccountManagerCli) -> Result<(), Error> {
 
 #[tokio::main]
 async fn main() {
-    let cli = match AccountManagerCli::try_parse() {
+    // Unknown commands are forwarded to plugins, git-style, before being taken as an account name.
+    let args = std::env::args().collect::<Vec<_>>();
+    if let Some(plugin) = args
+        .get(1)
+        .filter(|name| AccountManagerCli::command().find_subcommand(name.as_str()).is_none())
+        .and_then(|name| find_plugin(name))
+    {
+        let args = args[2..].iter().map(String::as_str).collect::<Vec<_>>();
+
+        // Plugins only get the profile from the environment, their options being their own.
+        if let Err(e) = load_config(std::env::var("WALLET_PROFILE").ok().as_deref()) {
+            println!("{e}");
+            std::process::exit(1);
+        }
+        if let Err(e) = run_plugin(&plugin, &args, &plugin_context(&storage_path())) {
+            // The exit code of a failed plugin is passed on, the plugin having reported the failure itself.
+            let code = match &e {
+                Error::PluginFailed { status, .. } => status.code(),
+                _ => None,
+            };
+            if code.is_none() {
+                println!("{e}");
+            }
+            std::process::exit(code.unwrap_or(1));
+        }
+        return;
+    }
+
+    let (mut cli, matches) = match AccountManagerCli::command()
+        .try_get_matches()
+        .and_then(|matches| Ok((AccountManagerCli::from_arg_matches(&matches)?, matches)))
+    {
         Ok(cli) => cli,
         Err(e) => {
             println!("{e}");
@@ -69,12 +194,32 @@ async fn main() {
         }
     };
 
+    // Settings of the config file apply where no option was given, except to `config` which edits them.
+    if !matches!(cli.command, Some(AccountManagerCommand::Config(_))) {
+        match load_config(cli.profile.as_deref()) {
+            Ok(config) => config.apply(&mut cli, &matches),
+            Err(e) => {
+                println!("{e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
     if let Err(e) = logger_init(&cli) {
         println!("{e}");
         return;
     }
 
+    if cli.timings {
+        enable_timings();
+    }
+
+    listen_for_shutdown();
+
+    let output = cli.output;
+
     if let Err(e) = run(cli).await {
-        log::error!("{e}");
+        print_error(&e, output);
+        std::process::exit(1);
     }
 }
@@ -0,0 +1,709 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+
+use iota_wallet::{
+    account::{
+        types::{OutputData, Transaction},
+        AccountHandle, RemainderValueStrategy, TransactionOptions,
+    },
+    iota_client::block::{
+        address::Address,
+        output::{
+            feature::{Feature, MetadataFeature, TagFeature},
+            unlock_condition::AddressUnlockCondition,
+            BasicOutputBuilder, Output, UnlockCondition,
+        },
+        payload::TaggedDataPayload,
+    },
+};
+
+use crate::{error::Error, helper::storage_path};
+
+// Tag of the payloads and outputs counted by the participation plugin of the nodes.
+const PARTICIPATION_TAG: &str = "PARTICIPATE";
+// Event payload types of the participation plugin.
+const BALLOT_EVENT_TYPE: u64 = 0;
+const STAKING_EVENT_TYPE: u64 = 1;
+
+// The events an account participates in, with the answers to the ballots among them and the amount staked or voting,
+// `None` for the whole balance, and whether `sync` may tag the outputs again without asking once the voting output is
+// spent.
+#[derive(Debug, Default, Clone)]
+struct Participation {
+    events: Vec<String>,
+    answers: BTreeMap<String, Vec<u8>>,
+    amount: Option<u64>,
+    maintain: bool,
+}
+
+/// An event of the participation plugin of the node, as described by it, with its status.
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub id: String,
+    pub info: serde_json::Value,
+    pub status: serde_json::Value,
+}
+
+impl Event {
+    pub fn name(&self) -> &str {
+        self.info["name"].as_str().unwrap_or(&self.id)
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self.info["payload"]["type"].as_u64() {
+            Some(BALLOT_EVENT_TYPE) => "ballot",
+            Some(STAKING_EVENT_TYPE) => "staking",
+            _ => "unknown",
+        }
+    }
+
+    /// `upcoming`, `commencing`, `holding` or `ended`.
+    pub fn status(&self) -> &str {
+        self.status["status"].as_str().unwrap_or("unknown")
+    }
+
+    /// The questions of a ballot, none for staking events.
+    pub fn questions(&self) -> Vec<serde_json::Value> {
+        self.info["payload"]["questions"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+// The participations are kept next to the database, by account index.
+fn participations_path() -> String {
+    format!("{}-participations.json", storage_path())
+}
+
+fn read_participations() -> Result<BTreeMap<u32, Participation>, Error> {
+    let participations = match std::fs::read_to_string(participations_path()) {
+        Ok(participations) => serde_json::from_str::<BTreeMap<u32, serde_json::Value>>(&participations)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(participations
+        .into_iter()
+        .map(|(account_index, participation)| {
+            (
+                account_index,
+                Participation {
+                    events: serde_json::from_value(participation["events"].clone()).unwrap_or_default(),
+                    answers: serde_json::from_value(participation["answers"].clone()).unwrap_or_default(),
+                    amount: participation["amount"].as_u64(),
+                    maintain: participation["maintain"].as_bool().unwrap_or_default(),
+                },
+            )
+        })
+        .collect())
+}
+
+fn write_participations(participations: &BTreeMap<u32, Participation>) -> Result<(), Error> {
+    let participations = participations
+        .iter()
+        .map(|(account_index, participation)| {
+            (
+                account_index,
+                serde_json::json!({
+                    "events": participation.events,
+                    "answers": participation.answers,
+                    "amount": participation.amount,
+                    "maintain": participation.maintain,
+                }),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    std::fs::write(participations_path(), serde_json::to_string_pretty(&participations)?)?;
+
+    Ok(())
+}
+
+/// Registers the account for a staking event, the amount replacing the one staked for its other events, and tags its
+/// outputs.
+pub async fn participate(
+    account_handle: &AccountHandle,
+    event_id: &str,
+    amount: Option<u64>,
+    maintain: bool,
+) -> Result<Transaction, Error> {
+    let event = event(account_handle, event_id).await?;
+
+    if event.kind() != "staking" {
+        return Err(Error::Miscellaneous(format!(
+            "event \"{}\" isn't a staking event",
+            event.name()
+        )));
+    }
+    if event.status() == "ended" {
+        return Err(Error::Miscellaneous(format!(
+            "staking event \"{}\" has ended",
+            event.name()
+        )));
+    }
+
+    let account_index = *account_handle.read().await.index();
+    let mut participations = read_participations()?;
+    let participation = participations.entry(account_index).or_default();
+
+    if !participation.events.iter().any(|known| known == event_id) {
+        participation.events.push(event_id.to_string());
+    }
+    participation.amount = amount;
+    participation.maintain = maintain;
+
+    let participation = participation.clone();
+
+    write_participations(&participations)?;
+
+    log::info!(
+        "Staking for \"{}\", {} event(s) in total.",
+        event.name(),
+        participation.events.len()
+    );
+
+    tag_outputs(account_handle, &participation).await
+}
+
+/// Votes in a ballot with the value of an answer to each of its questions, replacing a previous vote, and tags the
+/// outputs of the account with its voting power, the whole balance if it has none yet.
+pub async fn vote(account_handle: &AccountHandle, event_id: &str, answers: Vec<u8>) -> Result<Transaction, Error> {
+    let event = event(account_handle, event_id).await?;
+
+    if event.kind() != "ballot" {
+        return Err(Error::Miscellaneous(format!(
+            "event \"{}\" isn't a ballot, stake for it with `participate`",
+            event.name()
+        )));
+    }
+    if event.status() == "ended" {
+        return Err(Error::Miscellaneous(format!("ballot \"{}\" has ended", event.name())));
+    }
+
+    let questions = event.questions();
+
+    if answers.len() != questions.len() {
+        return Err(Error::Miscellaneous(format!(
+            "ballot \"{}\" has {} question(s), {} answer(s) given",
+            event.name(),
+            questions.len(),
+            answers.len()
+        )));
+    }
+
+    for (index, (question, answer)) in questions.iter().zip(&answers).enumerate() {
+        let known = question["answers"].as_array().is_some_and(|known_answers| {
+            known_answers
+                .iter()
+                .any(|known_answer| known_answer["value"].as_u64() == Some(u64::from(*answer)))
+        });
+
+        if !known {
+            return Err(Error::Miscellaneous(format!(
+                "{answer} isn't an answer to question {} of \"{}\", see `participation events`",
+                index + 1,
+                event.name()
+            )));
+        }
+    }
+
+    let voting_power = current_voting_power(account_handle).await?;
+    let account_index = *account_handle.read().await.index();
+    let mut participations = read_participations()?;
+    let participation = participations.entry(account_index).or_insert_with(|| Participation {
+        amount: (voting_power > 0).then_some(voting_power),
+        ..Default::default()
+    });
+
+    if !participation.events.iter().any(|known| known == event_id) {
+        participation.events.push(event_id.to_string());
+    }
+    participation.answers.insert(event_id.to_string(), answers);
+
+    let participation = participation.clone();
+
+    write_participations(&participations)?;
+
+    log::info!(
+        "Voting in \"{}\", {} event(s) in total.",
+        event.name(),
+        participation.events.len()
+    );
+
+    tag_outputs(account_handle, &participation).await
+}
+
+/// Stops participating in an event, the voting output being tagged with the other events only and its amount kept.
+/// Returns `None` if there is no voting output to tag, `sync` doing it once there is one again.
+pub async fn stop_participating(account_handle: &AccountHandle, event_id: &str) -> Result<Option<Transaction>, Error> {
+    let account_index = *account_handle.read().await.index();
+    let mut participations = read_participations()?;
+    let participation = participations
+        .get_mut(&account_index)
+        .filter(|participation| participation.events.iter().any(|known| known == event_id))
+        .ok_or_else(|| Error::Miscellaneous(format!("the account doesn't participate in event {event_id}")))?;
+
+    participation.events.retain(|known| known != event_id);
+    participation.answers.remove(event_id);
+
+    let participation = participation.clone();
+
+    if participation.events.is_empty() {
+        participations.remove(&account_index);
+    }
+
+    write_participations(&participations)?;
+
+    let voting_power = current_voting_power(account_handle).await?;
+
+    if voting_power == 0 {
+        return Ok(None);
+    }
+
+    let participations = if participation.events.is_empty() {
+        None
+    } else {
+        Some(participation_data(&participation)?)
+    };
+
+    Ok(Some(
+        send_voting_output(account_handle, voting_power, participations).await?,
+    ))
+}
+
+/// The events of the participation plugin of the node.
+pub async fn events(account_handle: &AccountHandle) -> Result<Vec<Event>, Error> {
+    let event_ids = participation_api(account_handle, "events").await?["eventIds"]
+        .as_array()
+        .map(|event_ids| {
+            event_ids
+                .iter()
+                .filter_map(|event_id| event_id.as_str().map(str::to_string))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let mut events = Vec::new();
+
+    for event_id in event_ids {
+        events.push(event(account_handle, &event_id).await?);
+    }
+
+    Ok(events)
+}
+
+/// An event of the participation plugin of the node.
+pub async fn event(account_handle: &AccountHandle, event_id: &str) -> Result<Event, Error> {
+    prefix_hex::decode::<[u8; 32]>(event_id)
+        .map_err(|e| Error::Miscellaneous(format!("invalid event id \"{event_id}\": {e}")))?;
+
+    let info = participation_api_optional(account_handle, &format!("events/{event_id}"))
+        .await?
+        .ok_or_else(|| Error::Miscellaneous(format!("the node doesn't know event {event_id}")))?;
+
+    Ok(Event {
+        id: event_id.to_string(),
+        info,
+        status: participation_api(account_handle, &format!("events/{event_id}/status")).await?,
+    })
+}
+
+/// The events the account participates in, with the answers given to the ballots, none for staking events.
+pub async fn participations(account_handle: &AccountHandle) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let account_index = *account_handle.read().await.index();
+    let participation = read_participations()?.remove(&account_index).unwrap_or_default();
+
+    Ok(participation
+        .events
+        .into_iter()
+        .map(|event_id| {
+            let answers = participation.answers.get(&event_id).cloned().unwrap_or_default();
+
+            (event_id, answers)
+        })
+        .collect())
+}
+
+/// The participations counted by the node for the voting output of the account, by event id, with the amount and the
+/// milestones they started and ended at. `None` without voting output.
+pub async fn counted_participations(account_handle: &AccountHandle) -> Result<Option<serde_json::Value>, Error> {
+    let Some(output_data) = voting_output_data(account_handle).await? else {
+        return Ok(None);
+    };
+    let counted = participation_api_optional(account_handle, &format!("outputs/{}", output_data.output_id)).await?;
+
+    Ok(Some(counted.map_or(serde_json::Value::Null, |counted| {
+        counted["participations"].clone()
+    })))
+}
+
+/// Tags the outputs of the account again if its voting output has been spent, e.g. by a transaction selecting it as an
+/// input, dropping the events that ended, if agreed to with `participate --maintain`.
+///
+/// Meant to be run after a sync, the output being known as spent only then.
+pub async fn maintain_participation(account_handle: &AccountHandle) -> Result<Option<Transaction>, Error> {
+    let account_index = *account_handle.read().await.index();
+    let mut participations = read_participations()?;
+    let participation = match participations.get_mut(&account_index) {
+        Some(participation) => participation,
+        None => return Ok(None),
+    };
+
+    if voting_output(account_handle).await?.is_some() {
+        return Ok(None);
+    }
+    // Outputs of pending transactions can't be used yet, it's done once they're confirmed.
+    if !account_handle.read().await.pending_transactions().is_empty() {
+        return Ok(None);
+    }
+
+    let mut events = Vec::new();
+
+    for event_id in &participation.events {
+        if event_status(account_handle, event_id).await?.as_deref() == Some("ended") {
+            log::info!("Staking event {event_id} has ended.");
+        } else {
+            events.push(event_id.clone());
+        }
+    }
+
+    participation.events = events;
+
+    let participation = participation.clone();
+
+    if participation.events.is_empty() {
+        participations.remove(&account_index);
+    }
+
+    write_participations(&participations)?;
+
+    if participation.events.is_empty() {
+        return Ok(None);
+    }
+
+    // Tagging the outputs again moves funds, only done without asking if agreed to with `participate --maintain`.
+    if !participation.maintain {
+        log::warn!(
+            "The voting output has been spent, run `participate` again to keep staking, with `--maintain` to let `sync` \
+             tag the outputs again."
+        );
+        return Ok(None);
+    }
+
+    log::info!("The voting output has been spent, tagging the outputs again.");
+
+    Ok(Some(tag_outputs(account_handle, &participation).await?))
+}
+
+/// Increases the voting power of the account, the amount of its voting output, creating it if needed, and returns the
+/// transaction with the new voting power.
+pub async fn increase_voting_power(account_handle: &AccountHandle, amount: u64) -> Result<(Transaction, u64), Error> {
+    let voting_output = voting_output(account_handle).await?;
+    let voting_power = voting_output.as_ref().map_or(0, |output| output.amount()) + amount;
+    let available = voting_inputs(account_handle)
+        .await?
+        .iter()
+        .map(|output_data| output_data.output.amount())
+        .sum::<u64>();
+
+    if voting_power > available {
+        return Err(Error::Miscellaneous(format!(
+            "only {available} available for a voting power of {voting_power}"
+        )));
+    }
+
+    set_staked_amount(account_handle, voting_power).await?;
+
+    let transaction = send_voting_output(
+        account_handle,
+        voting_power,
+        voting_output.as_ref().and_then(participation_metadata),
+    )
+    .await?;
+
+    Ok((transaction, voting_power))
+}
+
+/// Decreases the voting power of the account, dissolving its voting output into a basic one when it drops to zero,
+/// which also ends the participations, and returns the transaction with the new voting power.
+pub async fn decrease_voting_power(account_handle: &AccountHandle, amount: u64) -> Result<(Transaction, u64), Error> {
+    let voting_output = voting_output(account_handle)
+        .await?
+        .ok_or_else(|| Error::Miscellaneous("the account has no voting power".to_string()))?;
+    let voting_power = voting_output.amount().checked_sub(amount).ok_or_else(|| {
+        Error::Miscellaneous(format!(
+            "the voting power is only {}, it can't be decreased by {amount}",
+            voting_output.amount()
+        ))
+    })?;
+
+    if voting_power > 0 {
+        set_staked_amount(account_handle, voting_power).await?;
+
+        let transaction =
+            send_voting_output(account_handle, voting_power, participation_metadata(&voting_output)).await?;
+
+        return Ok((transaction, voting_power));
+    }
+
+    // Without a voting output, `sync` would otherwise create a new one for the staking events.
+    let account_index = *account_handle.read().await.index();
+    let mut participations = read_participations()?;
+
+    if let Some(participation) = participations.remove(&account_index) {
+        write_participations(&participations)?;
+        log::info!("No longer staking for {} event(s).", participation.events.len());
+    }
+
+    let output_id = voting_inputs(account_handle)
+        .await?
+        .into_iter()
+        .find(|output_data| is_voting_output(&output_data.output))
+        .map(|output_data| output_data.output_id)
+        .ok_or_else(|| Error::Miscellaneous("the account has no voting power".to_string()))?;
+    let output = BasicOutputBuilder::new_with_amount(voting_output.amount())?
+        .add_unlock_condition(UnlockCondition::Address(AddressUnlockCondition::new(
+            first_address(account_handle).await?,
+        )))
+        .finish_output(account_handle.client().get_token_supply()?)?;
+    let options = TransactionOptions {
+        custom_inputs: Some(vec![output_id]),
+        ..Default::default()
+    };
+
+    Ok((account_handle.send(vec![output], Some(options)).await?, 0))
+}
+
+/// Returns the voting power of the account, the amount of its unspent voting output.
+pub async fn current_voting_power(account_handle: &AccountHandle) -> Result<u64, Error> {
+    Ok(voting_output(account_handle).await?.map_or(0, |output| output.amount()))
+}
+
+// Keeps the amount staked for the events of the account in line with its voting power.
+async fn set_staked_amount(account_handle: &AccountHandle, amount: u64) -> Result<(), Error> {
+    let account_index = *account_handle.read().await.index();
+    let mut participations = read_participations()?;
+
+    if let Some(participation) = participations.get_mut(&account_index) {
+        participation.amount = Some(amount);
+        write_participations(&participations)?;
+    }
+
+    Ok(())
+}
+
+// Sends the staked amount of the participation to the voting output.
+async fn tag_outputs(account_handle: &AccountHandle, participation: &Participation) -> Result<Transaction, Error> {
+    let available = voting_inputs(account_handle)
+        .await?
+        .iter()
+        .map(|output_data| output_data.output.amount())
+        .sum::<u64>();
+    let amount = match participation.amount {
+        Some(amount) if amount > available => {
+            log::warn!("Only {available} available to stake instead of {amount}.");
+            available
+        }
+        Some(amount) => amount,
+        None => available,
+    };
+
+    if amount == 0 {
+        return Err(Error::Miscellaneous("no funds available to stake".to_string()));
+    }
+
+    send_voting_output(account_handle, amount, Some(participation_data(participation)?)).await
+}
+
+// Sends the amount to the first address of the account in the voting output, tagged as such and with the
+// participations as metadata, along with them as a tagged data payload, as expected by the participation plugin. The
+// current voting output is spent first, then the largest basic outputs, the remainder staying on the address of the
+// inputs.
+async fn send_voting_output(
+    account_handle: &AccountHandle,
+    amount: u64,
+    participations: Option<Vec<u8>>,
+) -> Result<Transaction, Error> {
+    let mut inputs = Vec::new();
+    let mut selected = 0;
+
+    for output_data in voting_inputs(account_handle).await? {
+        if selected >= amount {
+            break;
+        }
+        selected += output_data.output.amount();
+        inputs.push(output_data.output_id);
+    }
+
+    let mut output = BasicOutputBuilder::new_with_amount(amount)?
+        .add_unlock_condition(UnlockCondition::Address(AddressUnlockCondition::new(
+            first_address(account_handle).await?,
+        )))
+        .add_feature(Feature::Tag(TagFeature::new(PARTICIPATION_TAG.as_bytes().to_vec())?));
+    let mut tagged_data_payload = None;
+
+    if let Some(participations) = participations {
+        output = output.add_feature(Feature::Metadata(MetadataFeature::new(participations.clone())?));
+        tagged_data_payload = Some(TaggedDataPayload::new(
+            PARTICIPATION_TAG.as_bytes().to_vec(),
+            participations,
+        )?);
+    }
+
+    let options = TransactionOptions {
+        remainder_value_strategy: RemainderValueStrategy::ReuseAddress,
+        tagged_data_payload,
+        custom_inputs: Some(inputs),
+        ..Default::default()
+    };
+
+    Ok(account_handle
+        .send(
+            vec![output.finish_output(account_handle.client().get_token_supply()?)?],
+            Some(options),
+        )
+        .await?)
+}
+
+// Outputs that can be spent into the voting output, the current one first, then the basic ones holding only base coins
+// by decreasing amount.
+async fn voting_inputs(account_handle: &AccountHandle) -> Result<Vec<OutputData>, Error> {
+    let mut candidates = account_handle
+        .unspent_outputs(None)
+        .await?
+        .into_iter()
+        .filter(|output_data| match &output_data.output {
+            Output::Basic(output) => {
+                output.native_tokens().is_empty()
+                    && output.unlock_conditions().len() == 1
+                    && output.unlock_conditions().address().is_some()
+            }
+            _ => false,
+        })
+        .collect::<Vec<_>>();
+
+    candidates.sort_by_key(|output_data| {
+        (
+            !is_voting_output(&output_data.output),
+            std::cmp::Reverse(output_data.output.amount()),
+        )
+    });
+
+    Ok(candidates)
+}
+
+async fn first_address(account_handle: &AccountHandle) -> Result<Address, Error> {
+    Ok(*account_handle
+        .read()
+        .await
+        .public_addresses()
+        .first()
+        .ok_or_else(|| Error::Miscellaneous("the account has no address".to_string()))?
+        .address()
+        .as_ref())
+}
+
+// The unspent voting output of the account, if any.
+async fn voting_output(account_handle: &AccountHandle) -> Result<Option<Output>, Error> {
+    Ok(voting_output_data(account_handle)
+        .await?
+        .map(|output_data| output_data.output))
+}
+
+async fn voting_output_data(account_handle: &AccountHandle) -> Result<Option<OutputData>, Error> {
+    Ok(account_handle
+        .unspent_outputs(None)
+        .await?
+        .into_iter()
+        .find(|output_data| is_voting_output(&output_data.output)))
+}
+
+fn is_voting_output(output: &Output) -> bool {
+    output
+        .features()
+        .and_then(|features| features.tag())
+        .is_some_and(|tag| tag.tag() == PARTICIPATION_TAG.as_bytes())
+}
+
+// The participations held as metadata by a voting output, kept as is when changing its amount.
+fn participation_metadata(output: &Output) -> Option<Vec<u8>> {
+    output
+        .features()
+        .and_then(|features| features.metadata())
+        .map(|metadata| metadata.data().to_vec())
+}
+
+// Participations as serialized for the participation plugin: their count, then for each one the event id and the
+// answers, prefixed by their count, none for staking events.
+fn participation_data(participation: &Participation) -> Result<Vec<u8>, Error> {
+    let mut data = vec![participation.events.len() as u8];
+
+    for event_id in &participation.events {
+        let answers = participation.answers.get(event_id).map_or(&[][..], Vec::as_slice);
+
+        data.extend(
+            prefix_hex::decode::<[u8; 32]>(event_id)
+                .map_err(|e| Error::Miscellaneous(format!("invalid event id \"{event_id}\": {e}")))?,
+        );
+        data.push(answers.len() as u8);
+        data.extend(answers);
+    }
+
+    Ok(data)
+}
+
+async fn event_status(account_handle: &AccountHandle, event_id: &str) -> Result<Option<String>, Error> {
+    Ok(
+        participation_api(account_handle, &format!("events/{event_id}/status")).await?["status"]
+            .as_str()
+            .map(str::to_string),
+    )
+}
+
+// Queries the participation plugin of the node, not covered by the client.
+async fn participation_api(account_handle: &AccountHandle, route: &str) -> Result<serde_json::Value, Error> {
+    participation_api_optional(account_handle, route)
+        .await?
+        .ok_or_else(|| Error::Miscellaneous(format!("participation plugin of the node: {route} not found")))
+}
+
+// Same as `participation_api`, `None` if the node doesn't know what is queried.
+async fn participation_api_optional(
+    account_handle: &AccountHandle,
+    route: &str,
+) -> Result<Option<serde_json::Value>, Error> {
+    // The node of the client, with its credentials.
+    let node = account_handle.client().get_node()?;
+    let mut request = reqwest::Client::new().get(format!(
+        "{}/api/participation/v1/{route}",
+        node.url.as_str().trim_end_matches('/')
+    ));
+
+    if let Some(auth) = &node.auth {
+        if let Some(jwt) = &auth.jwt {
+            request = request.bearer_auth(jwt);
+        }
+        if let Some((name, password)) = &auth.basic_auth_name_pwd {
+            request = request.basic_auth(name, Some(password));
+        }
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| Error::Miscellaneous(format!("participation plugin of the node: {e}")))?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    response
+        .error_for_status()
+        .map_err(|e| Error::Miscellaneous(format!("participation plugin of the node: {e}")))?
+        .json::<serde_json::Value>()
+        .await
+        .map(Some)
+        .map_err(|e| Error::Miscellaneous(format!("participation plugin of the node: {e}")))
+}
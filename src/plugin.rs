@@ -0,0 +1,124 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    env::var_os,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use iota_wallet::{account::AccountHandle, ClientOptions};
+
+use crate::{error::Error, helper::storage_path};
+
+// Plugins are executables named `cli-wallet-<name>` found on the `PATH`, run as `<name>`.
+const PLUGIN_PREFIX: &str = "cli-wallet-";
+
+/// Finds the executable of the plugin `name` on the `PATH`.
+pub fn find_plugin(name: &str) -> Option<PathBuf> {
+    // Only plain names, a plugin can't be picked from elsewhere with a path.
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return None;
+    }
+
+    let file_name = format!("{PLUGIN_PREFIX}{name}{}", std::env::consts::EXE_SUFFIX);
+
+    std::env::split_paths(&var_os("PATH")?)
+        .map(|directory| directory.join(&file_name))
+        .find(|path| path.is_file())
+}
+
+/// Runs a plugin with its arguments, giving it the wallet context as JSON on its stdin and its main fields as
+/// `CLI_WALLET_*` environment variables.
+pub fn run_plugin(path: &Path, args: &[&str], context: &serde_json::Value) -> Result<(), Error> {
+    let mut command = Command::new(path);
+    command
+        .args(args)
+        .stdin(Stdio::piped())
+        .env("WALLET_DATABASE_PATH", storage_path());
+
+    if let Some(node) = context["nodes"].get(0).and_then(|node| node.as_str()) {
+        command.env("CLI_WALLET_NODE", node);
+    }
+    if let Some(account) = context["account"].as_object() {
+        command.env("CLI_WALLET_ACCOUNT", account["alias"].as_str().unwrap_or_default());
+        command.env("CLI_WALLET_ACCOUNT_INDEX", account["index"].to_string());
+        command.env("CLI_WALLET_COIN_TYPE", account["coinType"].to_string());
+        if let Some(bech32_hrp) = account["bech32Hrp"].as_str() {
+            command.env("CLI_WALLET_BECH32_HRP", bech32_hrp);
+        }
+    }
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| Error::Miscellaneous(format!("can't run plugin \"{}\": {e}", path.display())))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The plugin may exit without reading its stdin.
+        let _ = writeln!(stdin, "{context}");
+    }
+
+    let status = child.wait()?;
+
+    if !status.success() {
+        return Err(Error::PluginFailed {
+            plugin: path.display().to_string(),
+            status,
+        });
+    }
+
+    Ok(())
+}
+
+/// Returns the URLs of the nodes set in the client options.
+pub fn node_urls(client_options: &ClientOptions) -> Vec<String> {
+    // The node manager isn't exposed, the URLs are taken from its serialized form.
+    fn collect(value: &serde_json::Value, urls: &mut Vec<String>) {
+        match value {
+            serde_json::Value::Object(object) => {
+                for (key, value) in object {
+                    match value.as_str() {
+                        Some(url) if key == "url" && !urls.iter().any(|known| known == url) => {
+                            urls.push(url.to_string())
+                        }
+                        _ => collect(value, urls),
+                    }
+                }
+            }
+            serde_json::Value::Array(array) => array.iter().for_each(|value| collect(value, urls)),
+            _ => {}
+        }
+    }
+
+    let mut urls = Vec::new();
+
+    if let Ok(value) = serde_json::to_value(client_options) {
+        collect(&value, &mut urls);
+    }
+
+    urls
+}
+
+/// Returns the context given to a plugin run from the account prompt.
+pub async fn account_context(account_handle: &AccountHandle, nodes: &[String]) -> serde_json::Value {
+    let account = account_handle.read().await;
+    let addresses = account
+        .public_addresses()
+        .iter()
+        .chain(account.internal_addresses())
+        .map(|address| address.address().to_bech32())
+        .collect::<Vec<_>>();
+
+    serde_json::json!({
+        "databasePath": storage_path(),
+        "nodes": nodes,
+        "account": {
+            "index": account.index(),
+            "alias": account.alias(),
+            "coinType": account.coin_type(),
+            "bech32Hrp": account.public_addresses().first().map(|address| address.address().bech32_hrp()),
+            "addresses": addresses,
+        },
+    })
+}
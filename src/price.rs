@@ -0,0 +1,265 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use iota_wallet::iota_client::constants::{IOTA_COIN_TYPE, SHIMMER_COIN_TYPE};
+
+use crate::{
+    config::config,
+    error::Error,
+    helper::{format_timestamp, storage_path},
+};
+
+const COINGECKO_API_URL: &str = "https://api.coingecko.com/api/v3";
+const DAY: u64 = 24 * 60 * 60;
+
+/// Where the fiat prices of the base coin come from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PriceSource {
+    /// The CoinGecko API.
+    CoinGecko,
+    /// An HTTP endpoint giving the price of a day, as a number or a `price` field, with `{coin}`, `{currency}` and
+    /// `{date}` (`YYYY-MM-DD`) replaced in its URL.
+    Url(String),
+    /// The same rate for every day.
+    Static(f64),
+}
+
+impl FromStr for PriceSource {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        match source.split_once(':') {
+            None if source == "coingecko" => Ok(Self::CoinGecko),
+            Some(("url", url)) => {
+                reqwest::Url::parse(url)
+                    .map_err(|e| Error::Miscellaneous(format!("invalid price source URL \"{url}\": {e}")))?;
+
+                Ok(Self::Url(url.to_string()))
+            }
+            Some(("static", rate)) => rate
+                .parse::<f64>()
+                .ok()
+                .filter(|rate| rate.is_finite() && *rate >= 0.0)
+                .map(Self::Static)
+                .ok_or_else(|| Error::Miscellaneous(format!("invalid static rate \"{rate}\""))),
+            _ => Err(Error::Miscellaneous(format!(
+                "unknown price source \"{source}\", expected coingecko, url:<url> or static:<rate>"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for PriceSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CoinGecko => write!(f, "coingecko"),
+            Self::Url(url) => write!(f, "url:{url}"),
+            Self::Static(rate) => write!(f, "static:{rate}"),
+        }
+    }
+}
+
+/// The price source, the fiat currency and whether prices are only read from the cache.
+#[derive(Debug, Clone)]
+pub struct PriceConfig {
+    pub source: PriceSource,
+    pub currency: String,
+    pub offline: bool,
+}
+
+impl Default for PriceConfig {
+    fn default() -> Self {
+        Self {
+            source: PriceSource::CoinGecko,
+            currency: "usd".to_string(),
+            offline: false,
+        }
+    }
+}
+
+// The price configuration and the daily prices are kept next to the database.
+fn config_path() -> String {
+    format!("{}-price-source.json", storage_path())
+}
+
+fn prices_path() -> String {
+    format!("{}-prices.json", storage_path())
+}
+
+/// Reads the price configuration, the one of the config file or the default one if it was never set.
+pub fn price_config() -> Result<PriceConfig, Error> {
+    let config = match std::fs::read_to_string(config_path()) {
+        Ok(config) => serde_json::from_str::<serde_json::Value>(&config)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(match &config().price_source {
+                Some(source) => PriceConfig {
+                    source: source.parse()?,
+                    ..PriceConfig::default()
+                },
+                None => PriceConfig::default(),
+            });
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(PriceConfig {
+        source: config["source"].as_str().unwrap_or("coingecko").parse()?,
+        currency: config["currency"].as_str().unwrap_or("usd").to_string(),
+        offline: config["offline"].as_bool().unwrap_or_default(),
+    })
+}
+
+/// Persists the price configuration.
+pub fn set_price_config(config: &PriceConfig) -> Result<(), Error> {
+    std::fs::write(
+        config_path(),
+        serde_json::to_string_pretty(&serde_json::json!({
+            "source": config.source.to_string(),
+            "currency": config.currency,
+            "offline": config.offline,
+        }))?,
+    )?;
+
+    Ok(())
+}
+
+/// Day of a timestamp, as `YYYY-MM-DD`, by which prices are kept.
+pub fn day(timestamp: u64) -> String {
+    format_timestamp(timestamp)[..10].to_string()
+}
+
+fn coin_name(coin_type: u32) -> Option<&'static str> {
+    match coin_type {
+        IOTA_COIN_TYPE => Some("iota"),
+        SHIMMER_COIN_TYPE => Some("shimmer"),
+        _ => None,
+    }
+}
+
+// Prices are cached by source, coin and currency, then by day.
+fn cache_key(config: &PriceConfig, coin: &str) -> String {
+    format!("{}|{coin}/{}", config.source, config.currency)
+}
+
+fn read_cache() -> Result<BTreeMap<String, BTreeMap<String, f64>>, Error> {
+    match std::fs::read_to_string(prices_path()) {
+        Ok(cache) => Ok(serde_json::from_str(&cache)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Price of the base coin on the day of a timestamp, from the cache only, for views that shouldn't wait for the
+/// network.
+pub fn cached_price(config: &PriceConfig, coin_type: u32, timestamp: u64) -> Option<f64> {
+    if let PriceSource::Static(rate) = config.source {
+        return Some(rate);
+    }
+
+    read_cache()
+        .ok()?
+        .get(&cache_key(config, coin_name(coin_type)?))?
+        .get(&day(timestamp))
+        .copied()
+}
+
+/// Daily prices of the base coin over a period, fetched for the days missing from the cache unless offline. Failing to
+/// fetch them isn't an error, the cached ones are then returned.
+pub async fn daily_prices(
+    config: &PriceConfig,
+    coin_type: u32,
+    start: u64,
+    end: u64,
+) -> Result<BTreeMap<String, f64>, Error> {
+    let Some(coin) = coin_name(coin_type) else {
+        return Ok(BTreeMap::new());
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let end = end.min(now);
+    let days = (start..end).step_by(DAY as usize).map(day).collect::<Vec<_>>();
+
+    if let PriceSource::Static(rate) = config.source {
+        return Ok(days.into_iter().map(|day| (day, rate)).collect());
+    }
+
+    let key = cache_key(config, coin);
+    let mut cache = read_cache()?;
+    let prices = cache.entry(key.clone()).or_default();
+    let missing = days
+        .iter()
+        .filter(|day| !prices.contains_key(*day))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if !missing.is_empty() && !config.offline {
+        let currency = &config.currency;
+        let fetched = match &config.source {
+            PriceSource::CoinGecko => {
+                let url = format!(
+                    "{COINGECKO_API_URL}/coins/{coin}/market_chart/range?vs_currency={currency}&from={start}&to={end}"
+                );
+
+                fetch(&url).await.map(|response| {
+                    for point in response["prices"].as_array().into_iter().flatten() {
+                        if let (Some(timestamp), Some(price)) = (point[0].as_u64(), point[1].as_f64()) {
+                            // The first price of each day.
+                            prices.entry(day(timestamp / 1000)).or_insert(price);
+                        }
+                    }
+                })
+            }
+            PriceSource::Url(url) => {
+                let mut fetched = Ok(());
+
+                for missing_day in &missing {
+                    let url = url
+                        .replace("{coin}", coin)
+                        .replace("{currency}", currency)
+                        .replace("{date}", missing_day);
+
+                    match fetch(&url).await {
+                        Ok(response) => {
+                            if let Some(price) = response.as_f64().or_else(|| response["price"].as_f64()) {
+                                prices.insert(missing_day.clone(), price);
+                            }
+                        }
+                        Err(e) => {
+                            fetched = Err(e);
+                            break;
+                        }
+                    }
+                }
+
+                fetched
+            }
+            PriceSource::Static(_) => Ok(()),
+        };
+
+        if let Err(e) = fetched {
+            log::warn!("Can't fetch the prices, only the cached ones are used: {e}");
+        }
+
+        std::fs::write(prices_path(), serde_json::to_string_pretty(&cache)?)?;
+    }
+
+    Ok(cache.remove(&key).unwrap_or_default())
+}
+
+async fn fetch(url: &str) -> Result<serde_json::Value, Error> {
+    reqwest::get(url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| Error::Miscellaneous(e.to_string()))?
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| Error::Miscellaneous(e.to_string()))
+}
@@ -0,0 +1,481 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::VecDeque,
+    fs::OpenOptions,
+    io::{stdin, stdout, BufRead, BufReader, IsTerminal, Write},
+};
+
+use clap::{Arg, Command};
+use dialoguer::Input;
+use ratatui::crossterm::{
+    cursor::{MoveToColumn, MoveUp},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, Clear, ClearType},
+};
+
+use crate::{error::Error, helper::storage_path};
+
+// Number of commands kept in the history.
+const HISTORY_SIZE: usize = 1000;
+
+// The history is kept next to the database, one command per line, the most recent last.
+fn history_path() -> String {
+    format!("{}-history", storage_path())
+}
+
+/// Commands entered on the account prompt, persisted across sessions.
+pub struct History {
+    commands: VecDeque<String>,
+}
+
+impl History {
+    /// Loads the history of the previous sessions.
+    pub fn load() -> Result<Self, Error> {
+        let file = match std::fs::File::open(history_path()) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self {
+                    commands: VecDeque::new(),
+                });
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let mut commands = BufReader::new(file).lines().collect::<Result<VecDeque<_>, _>>()?;
+
+        while commands.len() > HISTORY_SIZE {
+            commands.pop_front();
+        }
+
+        Ok(Self { commands })
+    }
+
+    // Records a command, unless it repeats the previous one.
+    fn push(&mut self, command: &str) -> Result<(), Error> {
+        if command.is_empty() || self.commands.back().map(String::as_str) == Some(command) {
+            return Ok(());
+        }
+
+        self.commands.push_back(command.to_string());
+
+        if self.commands.len() > HISTORY_SIZE {
+            self.commands.pop_front();
+            // Rewritten once in a while only, appending otherwise.
+            std::fs::write(
+                history_path(),
+                self.commands
+                    .iter()
+                    .map(|command| format!("{command}\n"))
+                    .collect::<String>(),
+            )?;
+        } else {
+            let mut file = OpenOptions::new().create(true).append(true).open(history_path())?;
+
+            writeln!(file, "{command}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads a command on a prompt, with the history browsed with the up and down arrows, the command names, options and
+/// `values` completed with Tab, and the arguments still expected by the command hinted after the cursor. Without a
+/// terminal, e.g. when commands are piped, it's a plain line reader.
+pub fn read_command(
+    prompt: &str,
+    cli: &Command<'_>,
+    history: &mut History,
+    values: &[String],
+) -> Result<String, Error> {
+    if !stdin().is_terminal() {
+        return Ok(Input::new().with_prompt(prompt).interact()?);
+    }
+
+    let prompt = format!("{prompt}: ");
+
+    terminal::enable_raw_mode()?;
+    let line = edit_line(&prompt, cli, history, values);
+    terminal::disable_raw_mode()?;
+
+    let line = line?;
+
+    history.push(line.trim())?;
+
+    Ok(line)
+}
+
+// The line being edited, in characters so that the cursor moves over non-ASCII text.
+struct Editor<'a> {
+    prompt: &'a str,
+    line: Vec<char>,
+    cursor: usize,
+    // Row of the cursor from the one of the prompt, a long line wrapping over several rows.
+    row: usize,
+}
+
+impl Editor<'_> {
+    fn text(&self) -> String {
+        self.line.iter().collect()
+    }
+
+    fn set(&mut self, text: &str) {
+        self.line = text.chars().collect();
+        self.cursor = self.line.len();
+    }
+
+    fn render(&mut self, hint: &str) -> Result<(), Error> {
+        let mut stdout = stdout();
+        let width = usize::from(terminal::size()?.0.max(1));
+        let position = self.prompt.chars().count() + self.cursor;
+        let end = self.prompt.chars().count() + self.line.len() + hint.chars().count();
+
+        if self.row > 0 {
+            queue!(stdout, MoveUp(u16::try_from(self.row).unwrap_or(u16::MAX)))?;
+        }
+        queue!(
+            stdout,
+            MoveToColumn(0),
+            Clear(ClearType::FromCursorDown),
+            Print(self.prompt),
+            Print(self.text()),
+            SetAttribute(Attribute::Dim),
+            Print(hint),
+            SetAttribute(Attribute::Reset),
+        )?;
+        // At the end of a row, the terminal only wraps once something more is printed.
+        if end.is_multiple_of(width) {
+            queue!(stdout, Print("\r\n"))?;
+        }
+        if end / width > position / width {
+            queue!(
+                stdout,
+                MoveUp(u16::try_from(end / width - position / width).unwrap_or(u16::MAX))
+            )?;
+        }
+        queue!(
+            stdout,
+            MoveToColumn(u16::try_from(position % width).unwrap_or(u16::MAX))
+        )?;
+
+        self.row = position / width;
+
+        Ok(stdout.flush()?)
+    }
+
+    // Moves below the line, e.g. to print something or once it's entered.
+    fn leave(&mut self) -> Result<(), Error> {
+        let cursor = self.cursor;
+
+        self.cursor = self.line.len();
+        self.render("")?;
+        self.cursor = cursor;
+        self.row = 0;
+        print!("\r\n");
+
+        Ok(())
+    }
+}
+
+fn edit_line(prompt: &str, cli: &Command<'_>, history: &History, values: &[String]) -> Result<String, Error> {
+    let mut editor = Editor {
+        prompt,
+        line: Vec::new(),
+        cursor: 0,
+        row: 0,
+    };
+    // Position in the history, from the most recent command, and the line being edited before browsing it.
+    let mut history_position = 0;
+    let mut draft = String::new();
+    let mut last_key_was_tab = false;
+
+    loop {
+        let hint = if editor.cursor == editor.line.len() {
+            hint(cli, &editor.text())
+        } else {
+            String::new()
+        };
+
+        editor.render(&hint)?;
+
+        let Event::Key(KeyEvent {
+            code, modifiers, kind, ..
+        }) = event::read()?
+        else {
+            continue;
+        };
+
+        if kind != KeyEventKind::Press {
+            continue;
+        }
+
+        let tab = code == KeyCode::Tab;
+
+        match (code, modifiers.contains(KeyModifiers::CONTROL)) {
+            // Ctrl-J, a line feed, enters the line like in readline.
+            (KeyCode::Enter, _) | (KeyCode::Char('j'), true) => {
+                editor.leave()?;
+
+                if !editor.line.iter().all(|c| c.is_whitespace()) {
+                    return Ok(editor.text());
+                }
+                editor.line.clear();
+                editor.cursor = 0;
+            }
+            // Same as Ctrl-C outside of the raw mode of the prompt.
+            (KeyCode::Char('c'), true) => {
+                terminal::disable_raw_mode()?;
+                println!();
+                std::process::exit(130);
+            }
+            (KeyCode::Char('d'), true) if editor.line.is_empty() => {
+                editor.leave()?;
+                return Ok("exit".to_string());
+            }
+            (KeyCode::Char('a'), true) | (KeyCode::Home, _) => editor.cursor = 0,
+            (KeyCode::Char('e'), true) | (KeyCode::End, _) => editor.cursor = editor.line.len(),
+            (KeyCode::Char('u'), true) => {
+                editor.line.drain(..editor.cursor);
+                editor.cursor = 0;
+            }
+            (KeyCode::Char('w'), true) => {
+                let start = word_start(&editor.line[..editor.cursor]);
+
+                editor.line.drain(start..editor.cursor);
+                editor.cursor = start;
+            }
+            (KeyCode::Char(c), false) => {
+                editor.line.insert(editor.cursor, c);
+                editor.cursor += 1;
+            }
+            (KeyCode::Backspace, _) if editor.cursor > 0 => {
+                editor.cursor -= 1;
+                editor.line.remove(editor.cursor);
+            }
+            (KeyCode::Delete, _) if editor.cursor < editor.line.len() => {
+                editor.line.remove(editor.cursor);
+            }
+            (KeyCode::Left, _) => editor.cursor = editor.cursor.saturating_sub(1),
+            // At the end of the line, the right arrow accepts the rest of the command name that is hinted.
+            (KeyCode::Right, _)
+                if editor.cursor == editor.line.len()
+                    && !hint.is_empty()
+                    && !editor.line.last().is_some_and(|c| c.is_whitespace()) =>
+            {
+                let text = format!("{}{hint}", editor.text());
+
+                editor.set(&text);
+            }
+            (KeyCode::Right, _) => editor.cursor = (editor.cursor + 1).min(editor.line.len()),
+            (KeyCode::Up, _) => {
+                if let Some(command) = history.commands.iter().rev().nth(history_position) {
+                    if history_position == 0 {
+                        draft = editor.text();
+                    }
+                    history_position += 1;
+                    editor.set(command);
+                }
+            }
+            (KeyCode::Down, _) if history_position > 0 => {
+                history_position -= 1;
+
+                match history_position.checked_sub(1) {
+                    Some(position) => editor.set(&history.commands[history.commands.len() - 1 - position]),
+                    None => editor.set(&draft),
+                }
+            }
+            (KeyCode::Tab, _) => {
+                let before = editor.line[..editor.cursor].iter().collect::<String>();
+                let start = word_start(&editor.line[..editor.cursor]);
+                let word = editor.line[start..editor.cursor].iter().collect::<String>();
+                let candidates = candidates(cli, &before, values)
+                    .into_iter()
+                    .filter(|candidate| candidate.starts_with(&word))
+                    .collect::<Vec<_>>();
+
+                match candidates.as_slice() {
+                    [] => {}
+                    [candidate] => {
+                        let completion = format!("{candidate} ");
+
+                        editor.line.splice(start..editor.cursor, completion.chars());
+                        editor.cursor = start + completion.chars().count();
+                    }
+                    _ => {
+                        let prefix = common_prefix(&candidates);
+
+                        if prefix.len() > word.len() {
+                            editor.line.splice(start..editor.cursor, prefix.chars());
+                            editor.cursor = start + prefix.chars().count();
+                        } else if last_key_was_tab {
+                            // A second Tab lists the candidates above the prompt.
+                            editor.leave()?;
+                            print!("{}\r\n", candidates.join("  "));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        last_key_was_tab = tab;
+    }
+}
+
+// Index of the start of the word ending the text.
+fn word_start(text: &[char]) -> usize {
+    text.iter()
+        .rposition(|c| c.is_whitespace())
+        .map_or(0, |index| index + 1)
+}
+
+fn common_prefix(candidates: &[String]) -> String {
+    let mut prefix = candidates[0].clone();
+
+    for candidate in &candidates[1..] {
+        while !candidate.starts_with(&prefix) {
+            prefix.pop();
+        }
+    }
+
+    prefix
+}
+
+// Walks the subcommands named by the words of a command, returning the deepest one and the words left.
+fn subcommand<'a, 'b>(cli: &'a Command<'b>, words: &'a [&'a str]) -> (&'a Command<'b>, &'a [&'a str]) {
+    match words.split_first() {
+        Some((name, rest)) => match cli.find_subcommand(*name) {
+            Some(command) => subcommand(command, rest),
+            None => (cli, words),
+        },
+        None => (cli, words),
+    }
+}
+
+fn visible_subcommands<'a>(cli: &'a Command<'_>) -> Vec<&'a str> {
+    cli.get_subcommands()
+        .filter(|subcommand| !subcommand.is_hide_set())
+        .map(Command::get_name)
+        .collect()
+}
+
+// The completions of the word being typed at the end of `before`: the subcommand names, the options of the command
+// and the known values otherwise.
+fn candidates(cli: &Command<'_>, before: &str, values: &[String]) -> Vec<String> {
+    let words = before.split_whitespace().collect::<Vec<_>>();
+    // The word being typed isn't a complete one yet.
+    let complete = if before.ends_with(char::is_whitespace) {
+        &words[..]
+    } else {
+        &words[..words.len().saturating_sub(1)]
+    };
+    let word = if complete.len() < words.len() {
+        words[words.len() - 1]
+    } else {
+        ""
+    };
+    let (command, arguments) = subcommand(cli, complete);
+
+    if arguments.is_empty() && command.has_subcommands() {
+        visible_subcommands(command).into_iter().map(str::to_string).collect()
+    } else if word.starts_with('-') {
+        command
+            .get_arguments()
+            .filter(|argument| !argument.is_hide_set())
+            .filter_map(Arg::get_long)
+            .map(|long| format!("--{long}"))
+            .collect()
+    } else if word.is_empty() {
+        // Listing every known value would bury the prompt.
+        Vec::new()
+    } else {
+        values.to_vec()
+    }
+}
+
+// The rest of the command name being typed if only one matches, otherwise the arguments the command still expects.
+fn hint(cli: &Command<'_>, line: &str) -> String {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+
+    if words.is_empty() {
+        return String::new();
+    }
+
+    if !line.ends_with(char::is_whitespace) {
+        let (command, arguments) = subcommand(cli, &words[..words.len() - 1]);
+        let word = words[words.len() - 1];
+
+        if !arguments.is_empty() || !command.has_subcommands() {
+            return String::new();
+        }
+
+        let names = visible_subcommands(command);
+        let mut names = names.iter().filter(|name| name.starts_with(word));
+
+        return match (names.next(), names.next()) {
+            (Some(name), None) => name[word.len()..].to_string(),
+            _ => String::new(),
+        };
+    }
+
+    let (command, arguments) = subcommand(cli, &words);
+
+    if arguments.is_empty() && command.has_subcommands() {
+        return format!("<{}>", visible_subcommands(command).join("|"));
+    }
+
+    // Positional arguments already given, skipping the options and their values.
+    let mut given = 0;
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        match argument.strip_prefix("--") {
+            Some(long) => {
+                if let Some(option) = command
+                    .get_arguments()
+                    .find(|option| option.get_long() == Some(long) && option.is_takes_value_set())
+                {
+                    // The value of the option is expected first.
+                    if arguments.next().is_none() {
+                        return format!("<{}>", option.get_id().to_uppercase());
+                    }
+                }
+            }
+            None => given += 1,
+        }
+    }
+
+    let expected = command
+        .get_positionals()
+        .skip(given)
+        .map(|positional| {
+            let name = positional.get_id().to_uppercase();
+
+            if positional.is_required_set() {
+                format!("<{name}>")
+            } else {
+                format!("[{name}]")
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if expected.is_empty() {
+        let options = command
+            .get_arguments()
+            .filter(|argument| !argument.is_hide_set() && !argument.is_positional())
+            .filter_map(Arg::get_long)
+            .filter(|long| !["help", "version"].contains(long))
+            .map(|long| format!("--{long}"))
+            .collect::<Vec<_>>();
+
+        if options.is_empty() {
+            String::new()
+        } else {
+            format!("[{}]", options.join(" "))
+        }
+    } else {
+        expected.join(" ")
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use dialoguer::Input;
+use image::{GrayImage, ImageFormat, Luma};
+use qrcode::{render::unicode::Dense1x2, Color, EcLevel, QrCode};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    text::Line,
+    widgets::Paragraph,
+};
+
+use crate::error::Error;
+
+// Characters of a payload per QR code, small enough for the codes to fit in a terminal and be scanned from a screen.
+const PART_SIZE: usize = 300;
+// Time each part is shown before the next one.
+const PART_INTERVAL: Duration = Duration::from_millis(1500);
+// Pixels per module of the QR codes written to images, and modules of blank margin around them.
+const PNG_MODULE_SIZE: u32 = 8;
+const PNG_QUIET_ZONE: u32 = 4;
+
+/// Decodes the QR codes of an image file.
+pub fn decode_qr_image(path: &str) -> Result<Vec<String>, Error> {
+    let image = image::open(path)
+        .map_err(|e| Error::Miscellaneous(format!("can't read the image \"{path}\": {e}")))?
+        .to_luma8();
+    let mut prepared =
+        rqrr::PreparedImage::prepare_from_greyscale(image.width() as usize, image.height() as usize, |x, y| {
+            image.get_pixel(x as u32, y as u32).0[0]
+        });
+
+    Ok(prepared
+        .detect_grids()
+        .iter()
+        .filter_map(|grid| grid.decode().ok())
+        .map(|(_, content)| content)
+        .collect())
+}
+
+/// Shows a payload in the terminal as QR codes of `<index>/<total>:<chunk>` parts, cycling through them until enter is
+/// pressed.
+pub fn show_qr_parts(payload: &str, title: &str) -> Result<(), Error> {
+    let chunks = payload
+        .chars()
+        .collect::<Vec<_>>()
+        .chunks(PART_SIZE)
+        .map(|chunk| chunk.iter().collect::<String>())
+        .collect::<Vec<_>>();
+    let parts = chunks
+        .iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            let code =
+                QrCode::with_error_correction_level(format!("{}/{}:{chunk}", index + 1, chunks.len()), EcLevel::L)
+                    .map_err(|e| Error::Miscellaneous(format!("can't encode the QR code: {e}")))?;
+
+            Ok(render_terminal(&code))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut terminal = ratatui::try_init()?;
+    let result = (|| {
+        let (mut index, mut paused, mut shown_at) = (0, false, Instant::now());
+
+        loop {
+            terminal.draw(|frame| {
+                let [header, code, footer] =
+                    Layout::vertical([Constraint::Length(1), Constraint::Min(1), Constraint::Length(1)])
+                        .areas(frame.area());
+
+                frame.render_widget(
+                    Line::from(format!("{title}, part {}/{}", index + 1, parts.len())),
+                    header,
+                );
+                frame.render_widget(Paragraph::new(parts[index].as_str()), code);
+                frame.render_widget(
+                    Line::from("left/right: part, space: pause, enter: done once every part is scanned"),
+                    footer,
+                );
+            })?;
+
+            let timeout = if paused {
+                Duration::from_secs(60)
+            } else {
+                PART_INTERVAL.saturating_sub(shown_at.elapsed())
+            };
+
+            if event::poll(timeout)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        match key.code {
+                            KeyCode::Enter | KeyCode::Esc => return Ok(()),
+                            KeyCode::Char(' ') => paused = !paused,
+                            KeyCode::Left => index = (index + parts.len() - 1) % parts.len(),
+                            KeyCode::Right => index = (index + 1) % parts.len(),
+                            _ => {}
+                        }
+                        shown_at = Instant::now();
+                    }
+                }
+            } else if !paused {
+                index = (index + 1) % parts.len();
+                shown_at = Instant::now();
+            }
+        }
+    })();
+
+    ratatui::try_restore()?;
+
+    result
+}
+
+/// Renders a payload as a QR code of unicode characters to be printed in the terminal.
+pub fn qr_text(payload: &str) -> Result<String, Error> {
+    let code = QrCode::new(payload).map_err(|e| Error::Miscellaneous(format!("can't encode the QR code: {e}")))?;
+
+    Ok(render_terminal(&code))
+}
+
+/// Writes a payload as a QR code to a PNG image, black on white with a margin.
+pub fn write_qr_png(payload: &str, path: &str) -> Result<(), Error> {
+    if Path::new(path).exists() {
+        return Err(Error::Miscellaneous(format!(
+            "\"{path}\" already exists, refusing to overwrite it"
+        )));
+    }
+
+    let code = QrCode::new(payload).map_err(|e| Error::Miscellaneous(format!("can't encode the QR code: {e}")))?;
+    let width = code.width() as u32;
+    let colors = code.to_colors();
+    let size = (width + 2 * PNG_QUIET_ZONE) * PNG_MODULE_SIZE;
+    let image = GrayImage::from_fn(size, size, |x, y| {
+        let (column, row) = (x / PNG_MODULE_SIZE, y / PNG_MODULE_SIZE);
+        let dark = (PNG_QUIET_ZONE..width + PNG_QUIET_ZONE).contains(&column)
+            && (PNG_QUIET_ZONE..width + PNG_QUIET_ZONE).contains(&row)
+            && colors[((row - PNG_QUIET_ZONE) * width + column - PNG_QUIET_ZONE) as usize] == Color::Dark;
+
+        if dark {
+            Luma([0])
+        } else {
+            Luma([255])
+        }
+    });
+
+    image
+        .save_with_format(path, ImageFormat::Png)
+        .map_err(|e| Error::Miscellaneous(format!("can't write the image \"{path}\": {e}")))
+}
+
+// Renders a QR code with half blocks, two rows of modules per line, inverted as terminals usually have a dark
+// background.
+fn render_terminal(code: &QrCode) -> String {
+    code.render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build()
+}
+
+/// Reassembles a payload from the QR code parts of image files, asking for their paths until every part is read.
+pub fn read_qr_parts(prompt: &str) -> Result<String, Error> {
+    let mut parts = Vec::<Option<String>>::new();
+
+    loop {
+        let paths: String = Input::new().with_prompt(prompt).interact_text()?;
+
+        for path in paths.split_whitespace() {
+            for content in decode_qr_image(path)? {
+                let (index, total, chunk) = content
+                    .split_once(':')
+                    .and_then(|(position, chunk)| {
+                        let (index, total) = position.split_once('/')?;
+
+                        Some((index.parse::<usize>().ok()?, total.parse::<usize>().ok()?, chunk))
+                    })
+                    .filter(|(index, total, _)| (1..=*total).contains(index))
+                    .ok_or_else(|| Error::Miscellaneous(format!("\"{path}\" holds a QR code that isn't a part")))?;
+
+                if parts.is_empty() {
+                    parts.resize(total, None);
+                } else if parts.len() != total {
+                    return Err(Error::Miscellaneous(format!(
+                        "\"{path}\" holds a part of another payload, of {total} parts instead of {}",
+                        parts.len()
+                    )));
+                }
+
+                parts[index - 1] = Some(chunk.to_string());
+            }
+        }
+
+        let missing = parts
+            .iter()
+            .enumerate()
+            .filter(|(_, part)| part.is_none())
+            .map(|(index, _)| (index + 1).to_string())
+            .collect::<Vec<_>>();
+
+        if parts.is_empty() {
+            log::warn!("No part read yet.");
+        } else if missing.is_empty() {
+            return Ok(parts.into_iter().flatten().collect());
+        } else {
+            log::warn!("Missing parts {} of {}.", missing.join(", "), parts.len());
+        }
+    }
+}
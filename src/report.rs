@@ -0,0 +1,364 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use iota_wallet::{
+    account::{types::InclusionState, AccountHandle},
+    iota_client::{
+        block::payload::{transaction::TransactionEssence, Payload},
+        constants::{IOTA_COIN_TYPE, SHIMMER_COIN_TYPE},
+    },
+    U256,
+};
+
+use crate::{
+    command::account::{transaction_changes, ReportFormat},
+    error::Error,
+    helper::{format_timestamp, parse_date},
+    price::{daily_prices, day, price_config},
+};
+
+// A confirmed transaction of the period, with what it moved for each asset.
+struct Entry {
+    timestamp: u64,
+    transaction_id: String,
+    // Asset, base coin first then token ids, and amount received (positive) or sent (negative).
+    changes: Vec<(String, i128)>,
+    // Value in the fiat currency of the base coins moved, at the price of the day.
+    value: Option<f64>,
+}
+
+/// Parses a `YYYY`, `YYYY-QN` or `YYYY-MM` period into its start and end, as seconds since the Unix epoch.
+pub fn parse_period(period: &str) -> Result<(u64, u64), Error> {
+    let invalid = || {
+        Error::Miscellaneous(format!(
+            "invalid period \"{period}\", expected YYYY, YYYY-QN or YYYY-MM"
+        ))
+    };
+    let (year, rest) = period.split_once('-').unwrap_or((period, ""));
+    let year = year.parse::<u64>().map_err(|_| invalid())?;
+    let (first_month, months) = match rest {
+        "" => (1, 12),
+        quarter if quarter.starts_with('Q') => match quarter[1..].parse::<u64>() {
+            Ok(quarter @ 1..=4) => (quarter * 3 - 2, 3),
+            _ => return Err(invalid()),
+        },
+        month if month.len() == 2 => match month.parse::<u64>() {
+            Ok(month @ 1..=12) => (month, 1),
+            _ => return Err(invalid()),
+        },
+        _ => return Err(invalid()),
+    };
+    let month_start = |month: u64| {
+        let (year, month) = (year + (month - 1) / 12, (month - 1) % 12 + 1);
+        parse_date(&format!("{year:04}-{month:02}-01"))
+    };
+
+    Ok((month_start(first_month)?, month_start(first_month + months)?))
+}
+
+/// Builds the accounting report of an account for a period: what each confirmed transaction received and sent, valued
+/// in a fiat currency at the price of its day, and the totals by asset.
+pub async fn accounting_report(
+    account_handle: &AccountHandle,
+    period: &str,
+    format: ReportFormat,
+    currency: Option<String>,
+) -> Result<String, Error> {
+    let (start, end) = parse_period(period)?;
+    let mut config = price_config()?;
+
+    if let Some(currency) = currency {
+        config.currency = currency.to_lowercase();
+    }
+
+    let currency = config.currency.clone();
+    let (alias, coin_type) = {
+        let account = account_handle.read().await;
+        (account.alias().clone(), *account.coin_type())
+    };
+    let coin = match coin_type {
+        IOTA_COIN_TYPE => "IOTA",
+        SHIMMER_COIN_TYPE => "SMR",
+        _ => "base coin",
+    };
+    let mut transactions = account_handle
+        .transactions()
+        .await?
+        .into_iter()
+        .filter(|transaction| {
+            transaction.inclusion_state == InclusionState::Confirmed
+                && (start..end).contains(&((transaction.timestamp / 1000) as u64))
+        })
+        .collect::<Vec<_>>();
+
+    transactions.sort_by_key(|transaction| transaction.timestamp);
+
+    let mut entries = Vec::new();
+
+    for transaction in transactions {
+        let changes = transaction_changes(account_handle, &transaction).await?;
+        let timestamp = (transaction.timestamp / 1000) as u64;
+        let transfer = changes.transfer();
+        let mut moved = Vec::new();
+
+        if transfer != 0 {
+            moved.push((coin.to_string(), transfer));
+        }
+        for (token_id, (received, sent)) in changes.native_tokens {
+            // Token amounts beyond the i128 range are capped, they can't be valued anyway.
+            let amount = |amount: U256| amount.min(U256::from(i128::MAX as u128)).as_u128() as i128;
+
+            if received != sent {
+                moved.push((token_id.to_string(), amount(received) - amount(sent)));
+            }
+        }
+
+        if !moved.is_empty() {
+            entries.push(Entry {
+                timestamp,
+                transaction_id: transaction.transaction_id.to_string(),
+                value: None,
+                changes: moved,
+            });
+        }
+    }
+
+    // Prices are only needed, and fetched, when base coins moved.
+    if entries.iter().any(|entry| entry.changes[0].0 == coin) {
+        let prices = daily_prices(&config, coin_type, start, end).await?;
+
+        for entry in entries.iter_mut().filter(|entry| entry.changes[0].0 == coin) {
+            entry.value = prices
+                .get(&day(entry.timestamp))
+                .map(|price| entry.changes[0].1.unsigned_abs() as f64 / 1_000_000.0 * price);
+        }
+    }
+
+    // Received, sent and their value by asset.
+    let mut totals = BTreeMap::<String, (i128, i128, f64, f64)>::new();
+    let mut unvalued = 0;
+
+    for entry in &entries {
+        for (asset, amount) in &entry.changes {
+            let total = totals.entry(asset.clone()).or_default();
+            let value = if asset == coin { entry.value } else { None };
+
+            if asset == coin && value.is_none() {
+                unvalued += 1;
+            }
+            if *amount > 0 {
+                total.0 += amount;
+                total.2 += value.unwrap_or_default();
+            } else {
+                total.1 -= amount;
+                total.3 += value.unwrap_or_default();
+            }
+        }
+    }
+
+    let amount = |asset: &str, amount: i128| {
+        if asset == coin {
+            coin_amount(amount)
+        } else {
+            amount.to_string()
+        }
+    };
+    let value = |value: Option<f64>| value.map_or_else(String::new, |value| format!("{value:.2}"));
+
+    Ok(match format {
+        ReportFormat::Csv => {
+            let mut csv = format!("date,transaction_id,asset,in,out,{currency}_value\n");
+
+            for entry in &entries {
+                for (asset, moved) in &entry.changes {
+                    let (received, sent) = if *moved > 0 { (*moved, 0) } else { (0, -moved) };
+
+                    csv = format!(
+                        "{csv}{},{},{asset},{},{},{}\n",
+                        format_timestamp(entry.timestamp),
+                        entry.transaction_id,
+                        amount(asset, received),
+                        amount(asset, sent),
+                        if asset == coin {
+                            value(entry.value)
+                        } else {
+                            String::new()
+                        }
+                    );
+                }
+            }
+            // Totals of the period, with the period as date.
+            for (asset, (received, sent, value_in, value_out)) in &totals {
+                csv = format!(
+                    "{csv}{period},total,{asset},{},{},{}\n",
+                    amount(asset, *received),
+                    amount(asset, *sent),
+                    if asset == coin {
+                        value(Some(value_in - value_out))
+                    } else {
+                        String::new()
+                    }
+                );
+            }
+
+            csv
+        }
+        ReportFormat::PdfFriendly => {
+            let currency = currency.to_uppercase();
+            let mut report = format!(
+                "Accounting report of account \"{alias}\"\nPeriod: {period}, from {} to {}\nGenerated on {}\n\nSummary\n\
+                 {:<20} {:>18} {:>18} {:>18}",
+                format_timestamp(start),
+                format_timestamp(end - 1),
+                format_timestamp(
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |duration| duration.as_secs())
+                ),
+                "Asset",
+                "In",
+                "Out",
+                "Net"
+            );
+
+            for (asset, (received, sent, value_in, value_out)) in &totals {
+                // Token ids don't fit in the column, shown on their own line.
+                let name = if asset.len() > 20 {
+                    report = format!("{report}\n{asset}");
+                    String::new()
+                } else {
+                    asset.clone()
+                };
+
+                report = format!(
+                    "{report}\n{name:<20} {:>18} {:>18} {:>18}",
+                    amount(asset, *received),
+                    amount(asset, *sent),
+                    amount(asset, received - sent)
+                );
+
+                if asset == coin {
+                    report = format!(
+                        "{report}\n{:<20} {:>18} {:>18} {:>18}",
+                        format!("  in {currency}"),
+                        format!("{value_in:.2}"),
+                        format!("{value_out:.2}"),
+                        format!("{:.2}", value_in - value_out)
+                    );
+                }
+            }
+
+            if totals.is_empty() {
+                report = format!("{report}\nNo transactions.");
+            }
+
+            report = format!("{report}\n\nTransactions");
+
+            for entry in &entries {
+                report = format!(
+                    "{report}\n{}  {}",
+                    format_timestamp(entry.timestamp),
+                    entry.transaction_id
+                );
+
+                for (asset, moved) in &entry.changes {
+                    let valued = if asset == coin {
+                        entry
+                            .value
+                            .map(|value| format!(" ({value:.2} {currency})"))
+                            .unwrap_or_default()
+                    } else {
+                        String::new()
+                    };
+
+                    report = format!(
+                        "{report}\n    {}{} {asset}{valued}",
+                        if *moved > 0 { "+" } else { "-" },
+                        amount(asset, moved.abs())
+                    );
+                }
+            }
+
+            if unvalued > 0 {
+                report = format!("{report}\n\n{unvalued} transaction(s) without a price for their day aren't valued.");
+            }
+
+            format!("{report}\n")
+        }
+    })
+}
+
+/// Builds the CSV of the whole transaction history of an account, oldest first, with what each transaction moved
+/// relative to the addresses of the account. Returns it with the number of transactions.
+pub async fn transactions_csv(account_handle: &AccountHandle) -> Result<(String, usize), Error> {
+    let mut transactions = account_handle.transactions().await?;
+
+    transactions.sort_by_key(|transaction| transaction.timestamp);
+
+    let mut csv =
+        "transaction_id,timestamp,direction,amount,native_tokens,counterparts,inclusion_state,tag\n".to_string();
+
+    for transaction in &transactions {
+        let changes = transaction_changes(account_handle, transaction).await?;
+        // Net amount of each native token, received (positive) or sent (negative).
+        let mut native_tokens = changes
+            .native_tokens
+            .iter()
+            .filter(|(_, (received, sent))| received != sent)
+            .map(|(token_id, (received, sent))| {
+                if received > sent {
+                    format!("{token_id}:+{}", *received - *sent)
+                } else {
+                    format!("{token_id}:-{}", *sent - *received)
+                }
+            })
+            .collect::<Vec<_>>();
+        let TransactionEssence::Regular(essence) = transaction.payload.essence();
+        let tag = match essence.payload() {
+            Some(Payload::TaggedData(tagged_data)) => match std::str::from_utf8(tagged_data.tag()) {
+                Ok(text) if !text.chars().any(char::is_control) => text.to_string(),
+                _ => prefix_hex::encode(tagged_data.tag()),
+            },
+            _ => String::new(),
+        };
+
+        native_tokens.sort();
+        csv = format!(
+            "{csv}{},{},{},{},{},{},{:?},{}\n",
+            transaction.transaction_id,
+            format_timestamp((transaction.timestamp / 1000) as u64),
+            changes.direction.as_str(),
+            coin_amount(changes.transfer()),
+            native_tokens.join(";"),
+            changes.counterparts.join(";"),
+            transaction.inclusion_state,
+            csv_field(&tag)
+        );
+    }
+
+    Ok((csv, transactions.len()))
+}
+
+// Formats an amount of the smallest unit of the base coin in the base coin unit, without the unit.
+fn coin_amount(amount: i128) -> String {
+    format!(
+        "{}{}.{:06}",
+        if amount < 0 { "-" } else { "" },
+        amount.unsigned_abs() / 1_000_000,
+        amount.unsigned_abs() % 1_000_000
+    )
+}
+
+// Quotes a free-form CSV field if needed, doubling its quotes.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::str::FromStr;
+
+use iota_wallet::{
+    account::{types::OutputData, AccountHandle},
+    iota_client::block::{output::Output, payload::transaction::TransactionId},
+    AddressWithAmount,
+};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, INT};
+use tokio::runtime::Handle;
+
+use crate::{
+    error::Error,
+    helper::{check_network, record_balance},
+};
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Builds a Rhai engine exposing the operations of the account as functions.
+///
+/// Script functions are synchronous, the engine must run on a blocking thread from which the async operations are
+/// awaited with `runtime`.
+pub fn script_engine(account_handle: AccountHandle, runtime: Handle) -> Engine {
+    let mut engine = Engine::new();
+
+    engine.on_print(|text| log::info!("{text}"));
+    engine.on_debug(|text, _, _| log::debug!("{text}"));
+
+    let (account, rt) = (account_handle.clone(), runtime.clone());
+    engine.register_fn("balance", move || -> ScriptResult<Map> {
+        rt.block_on(async {
+            let balance = account.balance().await.map_err(script_error)?;
+
+            Ok(balance_map(balance.base_coin.total, balance.base_coin.available))
+        })
+    });
+
+    let (account, rt) = (account_handle.clone(), runtime.clone());
+    engine.register_fn("sync", move || -> ScriptResult<Map> {
+        rt.block_on(async {
+            let balance = account.sync(None).await.map_err(script_error)?;
+
+            record_balance(&account).await.map_err(script_error)?;
+
+            Ok(balance_map(balance.base_coin.total, balance.base_coin.available))
+        })
+    });
+
+    let (account, rt) = (account_handle.clone(), runtime.clone());
+    engine.register_fn("outputs", move || -> ScriptResult<Array> {
+        rt.block_on(async {
+            Ok(account
+                .outputs(None)
+                .await
+                .map_err(script_error)?
+                .iter()
+                .map(output_map)
+                .collect())
+        })
+    });
+
+    let (account, rt) = (account_handle.clone(), runtime.clone());
+    engine.register_fn("unspent_outputs", move || -> ScriptResult<Array> {
+        rt.block_on(async {
+            Ok(account
+                .unspent_outputs(None)
+                .await
+                .map_err(script_error)?
+                .iter()
+                .map(output_map)
+                .collect())
+        })
+    });
+
+    let (account, rt) = (account_handle.clone(), runtime.clone());
+    engine.register_fn("send", move |address: &str, amount: INT| -> ScriptResult<String> {
+        let amount = u64::try_from(amount).map_err(|_| format!("invalid amount {amount}"))?;
+
+        rt.block_on(async {
+            check_network(&account, address).await.map_err(script_error)?;
+
+            let outputs = vec![AddressWithAmount {
+                address: address.to_string(),
+                amount,
+            }];
+            let transaction = account.send_amount(outputs, None).await.map_err(script_error)?;
+
+            Ok(transaction.transaction_id.to_string())
+        })
+    });
+
+    let (account, rt) = (account_handle.clone(), runtime.clone());
+    engine.register_fn("consolidate", move || -> ScriptResult<String> {
+        rt.block_on(async {
+            let transaction = account.consolidate_outputs(true, None).await.map_err(script_error)?;
+
+            Ok(transaction.transaction_id.to_string())
+        })
+    });
+
+    let (account, rt) = (account_handle, runtime);
+    engine.register_fn(
+        "wait_for_confirmation",
+        move |transaction_id: &str| -> ScriptResult<String> {
+            let transaction_id = TransactionId::from_str(transaction_id).map_err(script_error)?;
+
+            rt.block_on(async {
+                let block_id = account
+                    .get_transaction(&transaction_id)
+                    .await
+                    .and_then(|transaction| transaction.block_id)
+                    .ok_or_else(|| format!("no block known for transaction {transaction_id}"))?;
+                let blocks = account
+                    .retry_until_included(&block_id, None, None)
+                    .await
+                    .map_err(script_error)?;
+
+                // Updates the inclusion state of the transaction.
+                account.sync(None).await.map_err(script_error)?;
+
+                Ok(blocks
+                    .first()
+                    .map_or_else(|| block_id.to_string(), |(block_id, _)| block_id.to_string()))
+            })
+        },
+    );
+
+    engine
+}
+
+fn script_error(error: impl Into<Error>) -> Box<EvalAltResult> {
+    error.into().to_string().into()
+}
+
+fn balance_map(total: u64, available: u64) -> Map {
+    let mut map = Map::new();
+
+    map.insert("total".into(), Dynamic::from(total as INT));
+    map.insert("available".into(), Dynamic::from(available as INT));
+
+    map
+}
+
+fn output_map(output_data: &OutputData) -> Dynamic {
+    let kind = match output_data.output {
+        Output::Alias(_) => "alias",
+        Output::Basic(_) => "basic",
+        Output::Foundry(_) => "foundry",
+        Output::Nft(_) => "nft",
+        Output::Treasury(_) => "treasury",
+    };
+    let mut map = Map::new();
+
+    map.insert("id".into(), output_data.output_id.to_string().into());
+    map.insert("kind".into(), kind.into());
+    map.insert("amount".into(), Dynamic::from(output_data.output.amount() as INT));
+    map.insert("spent".into(), output_data.is_spent.into());
+
+    map.into()
+}
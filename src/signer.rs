@@ -0,0 +1,174 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use iota_wallet::{
+    account::{
+        types::{AccountAddress, Transaction},
+        AccountHandle,
+    },
+    iota_client::{
+        api::{PreparedTransactionData, PreparedTransactionDataDto, SignedTransactionData},
+        block::{
+            address::Address,
+            payload::TransactionPayload,
+            unlock::{dto::UnlockDto, Unlock, Unlocks},
+        },
+    },
+};
+
+use crate::{
+    error::Error,
+    qr::{read_qr_parts, show_qr_parts},
+};
+
+// Prefix of the `--secret-manager` value selecting an external signer, followed by its command line.
+const EXTERNAL_PREFIX: &str = "external:";
+
+/// A signer holding the keys outside of the wallet, answering JSON requests.
+#[derive(Debug, Clone)]
+pub struct ExternalSigner {
+    transport: Transport,
+}
+
+#[derive(Debug, Clone)]
+enum Transport {
+    // A subprocess run for each request, reading the request on its stdin and writing the response on its stdout.
+    Command(String),
+    // An air-gapped wallet running `sign-qr`, the request is shown as QR codes and the response read from pictures of
+    // the ones it shows.
+    Qr,
+}
+
+impl ExternalSigner {
+    /// Parses the `--secret-manager` value, `stronghold`, `qr` or `external:<command>`.
+    pub fn from_secret_manager(secret_manager: Option<&str>) -> Result<Option<Self>, Error> {
+        match secret_manager {
+            None | Some("stronghold") => Ok(None),
+            Some("qr") => Ok(Some(Self {
+                transport: Transport::Qr,
+            })),
+            Some(value) => match value.strip_prefix(EXTERNAL_PREFIX).map(str::trim) {
+                Some(command) if !command.is_empty() => Ok(Some(Self {
+                    transport: Transport::Command(command.to_string()),
+                })),
+                _ => Err(Error::Miscellaneous(format!(
+                    "invalid secret manager \"{value}\", expected \"stronghold\", \"qr\" or \"external:<command>\""
+                ))),
+            },
+        }
+    }
+
+    fn request(&self, request: serde_json::Value) -> Result<serde_json::Value, Error> {
+        let response = match &self.transport {
+            Transport::Command(command) => command_request(command, &request)?,
+            Transport::Qr => {
+                show_qr_parts(&request.to_string(), "Scan with the air-gapped wallet running sign-qr")?;
+                serde_json::from_str(&read_qr_parts("Pictures of the response QR codes")?)?
+            }
+        };
+
+        if let Some(error) = response["error"].as_str() {
+            return Err(Error::Miscellaneous(format!("external signer: {error}")));
+        }
+
+        Ok(response)
+    }
+
+    /// Asks the signer for the first public address of a new account.
+    pub fn first_address(&self, coin_type: u32, account_index: u32) -> Result<AccountAddress, Error> {
+        let response = self.request(serde_json::json!({
+            "type": "generateAddress",
+            "coinType": coin_type,
+            "accountIndex": account_index,
+            "addressIndex": 0,
+            "internal": false,
+        }))?;
+        let address = response["address"]
+            .as_str()
+            .ok_or_else(|| Error::Miscellaneous("external signer: missing \"address\" in response".to_string()))?;
+
+        Address::try_from_bech32(address)?;
+
+        // `AccountAddress` has no public constructor.
+        Ok(serde_json::from_value(serde_json::json!({
+            "address": address,
+            "keyIndex": 0,
+            "internal": false,
+            "used": false,
+        }))?)
+    }
+
+    /// Has the signer sign a prepared transaction, then submits and stores it.
+    pub async fn sign_and_submit(
+        &self,
+        account_handle: &AccountHandle,
+        prepared_transaction_data: PreparedTransactionData,
+    ) -> Result<Transaction, Error> {
+        let signed_transaction_data = match self.sign(&prepared_transaction_data) {
+            Ok(signed_transaction_data) => signed_transaction_data,
+            Err(e) => {
+                // Unlocking the inputs isn't exposed, the library does it when its own signing fails, which it always
+                // does without the keys.
+                let _ = account_handle
+                    .sign_and_submit_transaction(prepared_transaction_data)
+                    .await;
+                return Err(e);
+            }
+        };
+
+        Ok(account_handle
+            .submit_and_store_transaction(signed_transaction_data)
+            .await?)
+    }
+
+    fn sign(&self, prepared_transaction_data: &PreparedTransactionData) -> Result<SignedTransactionData, Error> {
+        let response = self.request(serde_json::json!({
+            "type": "signTransaction",
+            "preparedTransactionData": PreparedTransactionDataDto::from(prepared_transaction_data),
+        }))?;
+        let unlocks = serde_json::from_value::<Vec<UnlockDto>>(response["unlocks"].clone())?
+            .iter()
+            .map(Unlock::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| Error::Miscellaneous(format!("external signer: invalid unlock: {e}")))?;
+        let transaction_payload =
+            TransactionPayload::new(prepared_transaction_data.essence.clone(), Unlocks::new(unlocks)?)?;
+
+        Ok(SignedTransactionData {
+            transaction_payload,
+            inputs_data: prepared_transaction_data.inputs_data.clone(),
+        })
+    }
+}
+
+fn command_request(command: &str, request: &serde_json::Value) -> Result<serde_json::Value, Error> {
+    let mut args = command.split_whitespace();
+    // PANIC: the command isn't empty, see `from_secret_manager`.
+    let program = args.next().unwrap();
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::Miscellaneous(format!("can't run external signer \"{program}\": {e}")))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(stdin, "{request}")?;
+    }
+
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        return Err(Error::Miscellaneous(format!(
+            "external signer \"{program}\" exited with {}",
+            output.status
+        )));
+    }
+
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
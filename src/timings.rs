@@ -0,0 +1,153 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use iota_wallet::events::types::TransactionProgressEvent;
+
+/// Phases the wall-clock duration of a command is broken down into, the rest being reported as other.
+#[derive(Debug, Clone, Copy)]
+pub enum Phase {
+    SecretManager,
+    Node,
+    Pow,
+    Storage,
+}
+
+impl Phase {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::SecretManager => "secret manager",
+            Self::Node => "node API",
+            Self::Pow => "PoW",
+            Self::Storage => "storage",
+        }
+    }
+}
+
+const PHASES: [Phase; 4] = [Phase::SecretManager, Phase::Node, Phase::Pow, Phase::Storage];
+
+struct Timings {
+    enabled: bool,
+    durations: [Duration; 4],
+    // Time spent waiting for the user, left out of the total.
+    waiting: Duration,
+    // Phase entered on a transaction progress event, lasting until the next event, measured phase or report.
+    current: Option<(Phase, Instant)>,
+}
+
+impl Timings {
+    fn close_current(&mut self) {
+        if let Some((phase, start)) = self.current.take() {
+            self.durations[phase as usize] += start.elapsed();
+        }
+    }
+}
+
+static TIMINGS: Mutex<Timings> = Mutex::new(Timings {
+    enabled: false,
+    durations: [Duration::ZERO; 4],
+    waiting: Duration::ZERO,
+    current: None,
+});
+
+fn with_timings<T>(f: impl FnOnce(&mut Timings) -> T) -> T {
+    // A panic while holding the lock can't leave the timings inconsistent, they're only added up.
+    f(&mut TIMINGS.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Enables the timings, for `--timings`.
+pub fn enable_timings() {
+    with_timings(|timings| timings.enabled = true);
+}
+
+/// Starts timing a command, forgetting what was measured outside of the previous ones.
+pub fn start_timings() -> Instant {
+    with_timings(|timings| {
+        timings.durations = Default::default();
+        timings.waiting = Duration::ZERO;
+        timings.current = None;
+    });
+
+    Instant::now()
+}
+
+/// Awaits a future, counting its duration in a phase when the timings are enabled.
+pub async fn timed<T>(phase: Phase, future: impl Future<Output = T>) -> T {
+    if !with_timings(|timings| timings.enabled) {
+        return future.await;
+    }
+
+    with_timings(Timings::close_current);
+
+    let start = Instant::now();
+    let output = future.await;
+
+    with_timings(|timings| timings.durations[phase as usize] += start.elapsed());
+
+    output
+}
+
+/// Runs a prompt, leaving the time the user takes to answer out of the timings.
+pub fn untimed<T>(prompt: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let output = prompt();
+
+    with_timings(|timings| timings.waiting += start.elapsed());
+
+    output
+}
+
+/// Follows the progress of the transactions sent by the library, whose signing, PoW and broadcasting can't be measured
+/// from the outside.
+pub fn transaction_progress(event: &TransactionProgressEvent) {
+    let phase = match event {
+        TransactionProgressEvent::SelectingInputs => None,
+        TransactionProgressEvent::GeneratingRemainderDepositAddress(_)
+        | TransactionProgressEvent::SigningTransaction => Some(Phase::SecretManager),
+        TransactionProgressEvent::PerformingPow => Some(Phase::Pow),
+        TransactionProgressEvent::Broadcasting => Some(Phase::Node),
+        // Sent along with signing.
+        TransactionProgressEvent::PreparedTransaction(_)
+        | TransactionProgressEvent::PreparedTransactionEssenceHash(_) => {
+            return;
+        }
+    };
+
+    with_timings(|timings| {
+        timings.close_current();
+        timings.current = phase.map(|phase| (phase, Instant::now()));
+    });
+}
+
+/// Reports the duration of a command started at `start` and its phases, when the timings are enabled.
+pub fn report_timings(command: &str, start: Instant) {
+    let (enabled, durations, total) = with_timings(|timings| {
+        timings.close_current();
+        (
+            timings.enabled,
+            timings.durations,
+            start.elapsed().saturating_sub(timings.waiting),
+        )
+    });
+
+    if !enabled {
+        return;
+    }
+
+    let measured = durations.iter().sum::<Duration>();
+    let phases = PHASES
+        .iter()
+        .map(|phase| format!("{} {:.2?}", phase.name(), durations[*phase as usize]))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    log::info!(
+        "Timings of `{command}`: {total:.2?} in total, {phases}, other {:.2?}",
+        total.saturating_sub(measured)
+    );
+}
@@ -0,0 +1,484 @@
+// Copyright 2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use iota_wallet::account::{AccountHandle, OutputsToClaim};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    command::account::{format_transaction, TransactionDirection},
+    error::Error,
+    helper::{format_amount, format_timestamp, record_balance},
+};
+
+// Number of transactions shown in the dashboard.
+const DASHBOARD_TRANSACTIONS: usize = 10;
+
+const COLUMNS: [&str; 5] = ["Date", "Direction", "State", "Net change", "Transaction id"];
+
+/// A transaction as shown in the table, with the details shown when it's selected.
+pub struct TransactionRow {
+    pub timestamp: u64,
+    pub direction: TransactionDirection,
+    pub state: String,
+    pub net: i128,
+    pub transaction_id: String,
+    pub details: String,
+}
+
+impl TransactionRow {
+    fn cells(&self) -> [String; 5] {
+        [
+            format_timestamp(self.timestamp),
+            self.direction.as_str().to_string(),
+            self.state.clone(),
+            format!("{:+}", self.net),
+            self.transaction_id.clone(),
+        ]
+    }
+}
+
+enum Mode {
+    Table,
+    Filter,
+    Details { scroll: u16 },
+}
+
+struct TransactionsTable {
+    rows: Vec<TransactionRow>,
+    // Indexes in `rows` of the rows matching the filter, in the sort order.
+    visible: Vec<usize>,
+    sort_column: usize,
+    descending: bool,
+    filter: String,
+    state: TableState,
+    mode: Mode,
+}
+
+impl TransactionsTable {
+    fn new(rows: Vec<TransactionRow>) -> Self {
+        let mut table = Self {
+            rows,
+            visible: Vec::new(),
+            // Latest first.
+            sort_column: 0,
+            descending: true,
+            filter: String::new(),
+            state: TableState::default(),
+            mode: Mode::Table,
+        };
+
+        table.update();
+
+        table
+    }
+
+    // Applies the filter and the sort order, keeping the selection on the first row.
+    fn update(&mut self) {
+        let filter = self.filter.to_lowercase();
+        let rows = &self.rows;
+
+        self.visible = (0..rows.len())
+            .filter(|index| {
+                filter.is_empty()
+                    || rows[*index]
+                        .cells()
+                        .iter()
+                        .chain([&rows[*index].details])
+                        .any(|text| text.to_lowercase().contains(&filter))
+            })
+            .collect();
+        self.visible.sort_by(|a, b| {
+            let (a, b) = (&rows[*a], &rows[*b]);
+            let ordering = match self.sort_column {
+                0 => a.timestamp.cmp(&b.timestamp),
+                1 => a.direction.cmp(&b.direction),
+                2 => a.state.cmp(&b.state),
+                3 => a.net.cmp(&b.net),
+                _ => a.transaction_id.cmp(&b.transaction_id),
+            };
+
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        self.state.select(if self.visible.is_empty() { None } else { Some(0) });
+    }
+
+    fn selected(&self) -> Option<&TransactionRow> {
+        self.state
+            .selected()
+            .and_then(|index| self.visible.get(index))
+            .map(|index| &self.rows[*index])
+    }
+
+    fn move_selection(&mut self, offset: isize) {
+        if let Some(selected) = self.state.selected() {
+            let last = self.visible.len().saturating_sub(1);
+
+            self.state
+                .select(Some(selected.saturating_add_signed(offset).min(last)));
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, footer] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+
+        match self.mode {
+            Mode::Details { scroll } => {
+                let details = self.selected().map(|row| row.details.clone()).unwrap_or_default();
+
+                frame.render_widget(
+                    Paragraph::new(details)
+                        .block(Block::default().borders(Borders::ALL).title("Transaction"))
+                        .scroll((scroll, 0)),
+                    main,
+                );
+                frame.render_widget(Line::from("up/down: scroll, esc: back, q: quit"), footer);
+            }
+            Mode::Table | Mode::Filter => {
+                let header = Row::new(COLUMNS.iter().enumerate().map(|(index, name)| {
+                    let marker = match (index == self.sort_column, self.descending) {
+                        (true, true) => " v",
+                        (true, false) => " ^",
+                        (false, _) => "",
+                    };
+
+                    Cell::from(format!("{}:{name}{marker}", index + 1))
+                }))
+                .style(Style::default().add_modifier(Modifier::BOLD));
+                let rows = self.visible.iter().map(|index| Row::new(self.rows[*index].cells()));
+                let title = format!("Transactions ({}/{})", self.visible.len(), self.rows.len());
+                let table = Table::new(
+                    rows,
+                    [
+                        Constraint::Length(23),
+                        Constraint::Length(13),
+                        Constraint::Length(12),
+                        Constraint::Length(16),
+                        Constraint::Min(66),
+                    ],
+                )
+                .header(header)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+                frame.render_stateful_widget(table, main, &mut self.state);
+
+                let footer_text = match self.mode {
+                    Mode::Filter => format!("filter: {}_", self.filter),
+                    _ if !self.filter.is_empty() => format!(
+                        "filter: {} | enter: details, 1-5: sort, /: filter, esc: clear filter, q: quit",
+                        self.filter
+                    ),
+                    _ => "enter: details, 1-5: sort, /: filter, q: quit".to_string(),
+                };
+
+                frame.render_widget(Line::from(footer_text), footer);
+            }
+        }
+    }
+
+    // Returns `true` when the table is quit.
+    fn handle_key(&mut self, code: KeyCode, page: isize) -> bool {
+        match self.mode {
+            Mode::Table => match code {
+                KeyCode::Char('q') => return true,
+                KeyCode::Esc if !self.filter.is_empty() => {
+                    self.filter.clear();
+                    self.update();
+                }
+                KeyCode::Esc => return true,
+                KeyCode::Up => self.move_selection(-1),
+                KeyCode::Down => self.move_selection(1),
+                KeyCode::PageUp => self.move_selection(-page),
+                KeyCode::PageDown => self.move_selection(page),
+                KeyCode::Home => self.move_selection(isize::MIN),
+                KeyCode::End => self.move_selection(isize::MAX),
+                KeyCode::Enter if self.selected().is_some() => self.mode = Mode::Details { scroll: 0 },
+                KeyCode::Char('/') => self.mode = Mode::Filter,
+                KeyCode::Char(c @ '1'..='5') => {
+                    let column = c as usize - '1' as usize;
+
+                    // Selecting the sort column again reverses the order.
+                    if column == self.sort_column {
+                        self.descending = !self.descending;
+                    } else {
+                        self.sort_column = column;
+                        self.descending = false;
+                    }
+                    self.update();
+                }
+                _ => {}
+            },
+            Mode::Filter => match code {
+                KeyCode::Enter => self.mode = Mode::Table,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.mode = Mode::Table;
+                    self.update();
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.update();
+                }
+                KeyCode::Char(c) => {
+                    self.filter.push(c);
+                    self.update();
+                }
+                _ => {}
+            },
+            Mode::Details { ref mut scroll } => match code {
+                KeyCode::Char('q') => return true,
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Backspace => self.mode = Mode::Table,
+                KeyCode::Up => *scroll = scroll.saturating_sub(1),
+                KeyCode::Down => *scroll = scroll.saturating_add(1),
+                _ => {}
+            },
+        }
+
+        false
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Error> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if let Event::Key(key) = event::read()? {
+                // Borders, header and footer aren't part of a page.
+                let page = terminal.size()?.height.saturating_sub(4).max(1) as isize;
+
+                if key.kind == KeyEventKind::Press && self.handle_key(key.code, page) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Shows the transactions in a scrollable, sortable and filterable table until it's quit.
+pub fn transactions_table(rows: Vec<TransactionRow>) -> Result<(), Error> {
+    let mut terminal = ratatui::try_init()?;
+    let result = TransactionsTable::new(rows).run(&mut terminal);
+
+    ratatui::try_restore()?;
+
+    result
+}
+
+#[derive(Default)]
+struct Dashboard {
+    balance: Vec<String>,
+    node: Vec<String>,
+    transactions: Vec<String>,
+    pending: Vec<String>,
+    status: String,
+}
+
+impl Dashboard {
+    // Syncs the account and reads the panels again, failures being shown in the panels rather than quitting.
+    async fn refresh(&mut self, account_handle: &AccountHandle) {
+        let (alias, coin_type) = {
+            let account = account_handle.read().await;
+            (account.alias().clone(), *account.coin_type())
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        self.status = match account_handle.sync(None).await {
+            Ok(_) => {
+                // Same as the `sync` command, the balance history is kept up to date.
+                let _ = record_balance(account_handle).await;
+                format!("Account \"{alias}\", synced at {}", format_timestamp(now))
+            }
+            Err(e) => format!(
+                "Account \"{alias}\", sync failed at {}: {}",
+                format_timestamp(now),
+                // The hint doesn't fit in the status line.
+                Error::from(e).to_string().lines().next().unwrap_or_default()
+            ),
+        };
+
+        self.balance = match account_handle.balance().await {
+            Ok(balance) => vec![
+                format!("Total: {}", format_amount(balance.base_coin.total, coin_type)),
+                format!("Available: {}", format_amount(balance.base_coin.available, coin_type)),
+                format!(
+                    "Storage deposit: {}",
+                    format_amount(balance.required_storage_deposit, coin_type)
+                ),
+                format!("Native tokens: {}", balance.native_tokens.len()),
+                format!("NFTs: {}", balance.nfts.len()),
+                format!("Aliases: {}", balance.aliases.len()),
+                format!("Foundries: {}", balance.foundries.len()),
+            ],
+            Err(e) => vec![Error::from(e).to_string()],
+        };
+
+        self.node = match account_handle.client().get_info().await {
+            Ok(node_info) => {
+                let info = &node_info.node_info;
+
+                vec![
+                    node_info.url.clone(),
+                    format!("{} {}", info.name, info.version),
+                    format!("Network: {}", info.protocol.network_name),
+                    format!("Healthy: {}", if info.status.is_healthy { "yes" } else { "no" }),
+                    format!("Latest milestone: {}", info.status.latest_milestone.index),
+                    format!("Confirmed milestone: {}", info.status.confirmed_milestone.index),
+                ]
+            }
+            Err(e) => vec![Error::from(e).to_string()],
+        };
+
+        self.transactions = match account_handle.transactions().await {
+            Ok(mut transactions) => {
+                transactions.sort_by_key(|transaction| std::cmp::Reverse(transaction.timestamp));
+
+                let mut lines = Vec::new();
+
+                for transaction in transactions.iter().take(DASHBOARD_TRANSACTIONS) {
+                    let (net, direction) = format_transaction(account_handle, transaction).await.map_or_else(
+                        |_| ("?".to_string(), "?"),
+                        |(_, net, direction)| (format!("{net:+}"), direction.as_str()),
+                    );
+
+                    lines.push(format!(
+                        "{}  {:<13}  {:<12}  {:>16}  {}",
+                        format_timestamp((transaction.timestamp / 1000) as u64),
+                        direction,
+                        format!("{:?}", transaction.inclusion_state),
+                        net,
+                        transaction.transaction_id
+                    ));
+                }
+                if lines.is_empty() {
+                    lines.push("No transactions".to_string());
+                }
+
+                lines
+            }
+            Err(e) => vec![Error::from(e).to_string()],
+        };
+
+        let mut pending = match account_handle.pending_transactions().await {
+            Ok(transactions) => transactions
+                .iter()
+                .map(|transaction| {
+                    format!(
+                        "Transaction {} pending since {}",
+                        transaction.transaction_id,
+                        format_timestamp((transaction.timestamp / 1000) as u64)
+                    )
+                })
+                .collect(),
+            Err(e) => vec![Error::from(e).to_string()],
+        };
+
+        match account_handle
+            .get_unlockable_outputs_with_additional_unlock_conditions(OutputsToClaim::All)
+            .await
+        {
+            Ok(output_ids) if !output_ids.is_empty() => {
+                pending.push(format!("{} outputs to claim, use `claim --all`", output_ids.len()))
+            }
+            Ok(_) => {}
+            Err(e) => pending.push(Error::from(e).to_string()),
+        }
+        if pending.is_empty() {
+            pending.push("Nothing pending".to_string());
+        }
+
+        self.pending = pending;
+    }
+
+    fn draw(&self, frame: &mut Frame, next_refresh: Duration) {
+        let [top, transactions, pending, footer] = Layout::vertical([
+            Constraint::Length(9),
+            Constraint::Length(DASHBOARD_TRANSACTIONS as u16 + 2),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [balance, node] = Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(top);
+
+        frame.render_widget(panel(&self.balance, "Balance"), balance);
+        frame.render_widget(panel(&self.node, "Node"), node);
+        frame.render_widget(panel(&self.transactions, "Recent transactions"), transactions);
+        frame.render_widget(panel(&self.pending, "Pending"), pending);
+        frame.render_widget(
+            Line::from(format!(
+                "{} | next refresh in {}s | r: refresh, q: quit",
+                self.status,
+                next_refresh.as_secs()
+            )),
+            footer,
+        );
+    }
+
+    async fn run(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        account_handle: &AccountHandle,
+        interval: Duration,
+    ) -> Result<(), Error> {
+        let mut next_refresh = Instant::now();
+
+        loop {
+            if Instant::now() >= next_refresh {
+                self.status = "Refreshing...".to_string();
+                terminal.draw(|frame| self.draw(frame, Duration::ZERO))?;
+                self.refresh(account_handle).await;
+                next_refresh = Instant::now() + interval;
+            }
+
+            terminal.draw(|frame| self.draw(frame, next_refresh.saturating_duration_since(Instant::now())))?;
+
+            // Short polls, to count down to the next refresh.
+            if event::poll(Duration::from_millis(250))? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        match key.code {
+                            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                            KeyCode::Char('r') => next_refresh = Instant::now(),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn panel<'a>(lines: &'a [String], title: &'a str) -> Paragraph<'a> {
+    // Errors come with a hint on a second line.
+    Paragraph::new(
+        lines
+            .iter()
+            .flat_map(|line| line.lines())
+            .map(Line::from)
+            .collect::<Vec<_>>(),
+    )
+    .block(Block::default().borders(Borders::ALL).title(title))
+}
+
+/// Shows the dashboard of the account, syncing it every `interval`, until it's quit.
+pub async fn dashboard(account_handle: &AccountHandle, interval: Duration) -> Result<(), Error> {
+    let mut terminal = ratatui::try_init()?;
+    let result = Dashboard::default().run(&mut terminal, account_handle, interval).await;
+
+    ratatui::try_restore()?;
+
+    result
+}